@@ -16,11 +16,20 @@ import cProfile
 old_path = sys.argv[1]
 new_path = sys.argv[2]
 
-if len(sys.argv) == 4:
+if len(sys.argv) >= 4:
     hg_map_file = sys.argv[3]
 else:
     hg_map_file = None
 
+# Optional file of obsolescence markers, one "<landed-hg> <original-hg>"
+# pair per line. This lets blame point back at the changeset that was
+# originally authored when the landed one is the result of a rebase or
+# fold.
+if len(sys.argv) >= 5:
+    hg_obsolete_file = sys.argv[4]
+else:
+    hg_obsolete_file = None
+
 old_repo = pygit2.Repository(pygit2.discover_repository(old_path))
 new_repo = pygit2.Repository(pygit2.discover_repository(new_path))
 
@@ -226,6 +235,9 @@ def transform_revision(commit):
     hg_id = git_to_hg_map.get(commit.id)
     if hg_id:
         msg = 'git %s\nhg %s\n' % (commit.id, hg_id)
+        orig_hg_id = hg_obsolete_map.get(hg_id)
+        if orig_hg_id and orig_hg_id != hg_id:
+            msg += 'orig-hg %s\n' % orig_hg_id
     else:
         msg = 'git %s\n' % commit.id
 
@@ -251,6 +263,18 @@ def index_mercurial(map_file):
 
     return m
 
+def index_obsolescence(obsolete_file):
+    f = open(obsolete_file)
+    m = {}
+    for line in f.readlines():
+        pieces = line.strip().split()
+        if len(pieces) != 2:
+            continue
+        (landed_rev, orig_rev) = pieces
+        m[landed_rev] = orig_rev
+
+    return m
+
 def index_existing():
     try:
         new_repo.head.target
@@ -270,6 +294,12 @@ if hg_map_file:
 else:
     git_to_hg_map = {}
 
+if hg_obsolete_file:
+    print 'Indexing obsolescence markers...'
+    hg_obsolete_map = index_obsolescence(hg_obsolete_file)
+else:
+    hg_obsolete_map = {}
+
 print 'Computing existing blame map...'
 blame_map = index_existing()
 
@@ -90,7 +90,8 @@ function generate(content, opt)
         <div id="search-box" class="h-flex-container" role="group">
           <div id="query-section">
               <label for="query" class="query_label visually-hidden">Find</label>
-              <input type="text" name="q"  value="${query}" maxlength="2048" id="query" accesskey="s" title="Search" placeholder="Search ${tree}" autocomplete="off" ${opt.autofocusSearch ? "autofocus" : ""}/>
+              <input type="text" name="q"  value="${query}" maxlength="2048" id="query" accesskey="s" title="Search" placeholder="Search ${tree}" autocomplete="off" list="query-completions" ${opt.autofocusSearch ? "autofocus" : ""}/>
+              <datalist id="query-completions"></datalist>
               <div class="zero-size-container">
                 <div class="bubble" id="query-bubble">
                 </div>
@@ -0,0 +1,18 @@
+import re
+
+patterns = {}
+
+def load(config):
+    global patterns
+    for tree_name in config['trees']:
+        boring = config['trees'][tree_name].get('boring_symbols', [])
+        patterns[tree_name] = [ re.compile(p) for p in boring ]
+
+# Returns whether |sym| or its pretty name |pretty| matches one of the
+# tree's boring_symbols patterns. The call graph tools use the same
+# list (see BoringSymbols in tools/src/callgraph.rs).
+def is_boring(tree_name, sym, pretty):
+    for p in patterns.get(tree_name, []):
+        if (sym and p.search(sym)) or (pretty and p.search(pretty)):
+            return True
+    return False
@@ -0,0 +1,36 @@
+import json
+import os.path
+from logger import log
+
+repo_data = {}
+
+def load(config):
+    global repo_data
+
+    for repo_name in config['trees']:
+        log('Loading bug references for %s', repo_name)
+        index_path = config['trees'][repo_name]['index_path']
+
+        bugs = {}
+        try:
+            f = open(os.path.join(index_path, 'bug-index'))
+        except IOError:
+            repo_data[repo_name] = bugs
+            continue
+
+        for line in f.readlines():
+            (bug, path, lineno, text) = json.loads(line)
+            bugs.setdefault(bug, []).append((path, lineno, text))
+        f.close()
+
+        repo_data[repo_name] = bugs
+
+# Returns the places that mention |bug| in a comment, grouped by path
+# the same way crossrefs.lookup groups its results.
+def lookup(tree_name, bug):
+    paths = []
+    for (path, lineno, text) in repo_data.get(tree_name, {}).get(bug, []):
+        if not paths or paths[-1]['path'] != path:
+            paths.append({'path': path, 'lines': []})
+        paths[-1]['lines'].append({'lno': lineno, 'line': text})
+    return paths
@@ -115,8 +115,9 @@ def startup_codesearch(data):
     time.sleep(5)
 
 def search(pattern, fold_case, path, tree_name):
-    repo = '%s|%s-__GENERATED__' % (tree_name, tree_name)
     data = tree_data[tree_name]
+    repo_name = data['repo_name']
+    repo = '%s|%s-__GENERATED__' % (repo_name, repo_name)
 
     try:
         codesearch = CodeSearch('localhost', data['codesearch_port'])
@@ -140,4 +141,6 @@ def load(config):
         tree_data[tree_name] = {
             'codesearch_path': config['trees'][tree_name]['codesearch_path'],
             'codesearch_port': config['trees'][tree_name]['codesearch_port'],
+            # Archived indexes are built under the name of the tree they came from.
+            'repo_name': config['trees'][tree_name].get('repo_name', tree_name),
         }
@@ -0,0 +1,31 @@
+import json
+import os.path
+from logger import log
+
+repo_data = {}
+
+def load(config):
+    global repo_data
+
+    for repo_name in config['trees']:
+        log('Loading bug components for %s', repo_name)
+        index_path = config['trees'][repo_name]['index_path']
+
+        files = {}
+        try:
+            f = open(os.path.join(index_path, 'bug-components'))
+        except IOError:
+            repo_data[repo_name] = files
+            continue
+
+        for line in f.readlines():
+            (path, product, component) = json.loads(line)
+            files[path] = (product, component)
+        f.close()
+
+        repo_data[repo_name] = files
+
+# Returns the (product, component) pair that owns |path| according to
+# moz.build metadata, or None if no component is assigned.
+def lookup(tree_name, path):
+    return repo_data.get(tree_name, {}).get(path)
@@ -0,0 +1,40 @@
+import json
+import os.path
+from logger import log
+
+repo_data = {}
+
+def load(config):
+    global repo_data
+
+    for repo_name in config['trees']:
+        log('Loading doc summaries for %s', repo_name)
+        index_path = config['trees'][repo_name]['index_path']
+
+        summaries = {}
+        try:
+            f = open(os.path.join(index_path, 'doc-summaries'))
+        except IOError:
+            repo_data[repo_name] = summaries
+            continue
+
+        for line in f.readlines():
+            (sym, summary) = json.loads(line)
+            summaries[sym] = summary
+        f.close()
+
+        repo_data[repo_name] = summaries
+
+# Returns the first sentence of the doc comment for |sym|, or None.
+def lookup(tree_name, sym):
+    return repo_data.get(tree_name, {}).get(sym)
+
+# Attaches the doc summary of |sym| to the definition lines of
+# |results|, as returned by crossrefs.lookup.
+def annotate(tree_name, sym, results):
+    summary = lookup(tree_name, sym)
+    if not summary:
+        return
+    for pathr in results.get('Definitions', []):
+        for line in pathr['lines']:
+            line['summary'] = summary
@@ -0,0 +1,45 @@
+import csv
+import json
+import re
+import StringIO
+
+fields = ['path', 'line', 'symbol', 'kind', 'pathkind', 'context', 'contextsym', 'text']
+
+# Flattens search results, as returned by SearchResults.get, into one
+# row per matching line (or per file, for file results). Identifier
+# searches qualify each kind with the name that matched ("Uses
+# (nsFoo::Bar)"); that name is reported as the symbol, and |symbol| is
+# used for the rest.
+def rows(results, symbol):
+    out = []
+    for pathkind in results:
+        if pathkind.startswith('*'):
+            continue
+        for (qkind, paths) in results[pathkind].items():
+            m = re.match(r'^(.*?)(?: \((.*)\))?$', qkind)
+            (kind, qual) = (m.group(1), m.group(2))
+            for path_result in paths:
+                for line in path_result['lines'] or [{}]:
+                    out.append({
+                        'path': path_result['path'],
+                        'line': line.get('lno'),
+                        'symbol': qual or symbol or '',
+                        'kind': kind,
+                        'pathkind': pathkind,
+                        'context': line.get('context', ''),
+                        'contextsym': line.get('contextsym', ''),
+                        'text': line.get('line', '').strip(),
+                    })
+    return out
+
+def to_jsonl(results, symbol):
+    return ''.join([ json.dumps(row) + '\n' for row in rows(results, symbol) ])
+
+def to_csv(results, symbol):
+    buf = StringIO.StringIO()
+    writer = csv.writer(buf)
+    writer.writerow(fields)
+    for row in rows(results, symbol):
+        values = [ row[f] if row[f] is not None else '' for f in fields ]
+        writer.writerow([ v.encode('utf-8') if isinstance(v, unicode) else v for v in values ])
+    return buf.getvalue()
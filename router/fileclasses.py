@@ -0,0 +1,47 @@
+import json
+import os.path
+from logger import log
+
+repo_data = {}
+
+def load(config):
+    global repo_data
+
+    for repo_name in config['trees']:
+        log('Loading file classes for %s', repo_name)
+        index_path = config['trees'][repo_name]['index_path']
+
+        try:
+            f = open(os.path.join(index_path, 'file-classes'))
+        except IOError:
+            repo_data[repo_name] = None
+            continue
+
+        files = {}
+        for line in f.readlines():
+            (path, cls) = json.loads(line)
+            files[path] = cls
+        f.close()
+
+        repo_data[repo_name] = files
+
+# The guess used for indexes that were built without a file-classes
+# file.
+def looks_like_test(p):
+    if '/unit/' in p:
+        return True
+    if 'test' not in p:
+        return False
+    return ('/test/' in p or '/tests/' in p or '/mochitest/' in p or '/unit/' in p or 'testing/' in p or
+            '/jsapi-tests/' in p or '/reftests/' in p or '/reftest/' in p or
+            '/crashtests/' in p or '/crashtest/' in p)
+
+# Returns the class of |path|: 'test', 'build', 'generated' or 'prod',
+# as recorded by scripts/classify-files.py.
+def lookup(tree_name, path):
+    if path.startswith('__GENERATED__/'):
+        return 'generated'
+    files = repo_data.get(tree_name)
+    if files is None:
+        return 'test' if looks_like_test(path) else 'prod'
+    return files.get(path, 'prod')
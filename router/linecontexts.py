@@ -0,0 +1,43 @@
+import os.path
+
+index_paths = {}
+cache = {}
+
+def load(config):
+    global index_paths
+    for tree_name in config['trees']:
+        index_paths[tree_name] = config['trees'][tree_name]['index_path']
+
+# Returns a list with an entry for each line of |path|. Each entry is a
+# triple of the pretty name of the innermost function containing the
+# line (or ''), a list of (start, end) column ranges covered by
+# comments, and the (first, last) line numbers of the statement the
+# line is part of (None for indexes built before these were recorded).
+# Returns None if the file has no context data.
+def lookup(tree_name, path):
+    key = (tree_name, path)
+    if key in cache:
+        return cache[key]
+
+    filename = os.path.join(index_paths[tree_name], 'line-contexts', path)
+    try:
+        contexts = []
+        for line in open(filename).read().split('\n'):
+            (function, _, rest) = line.partition('\t')
+            (comments, _, statement) = rest.partition('\t')
+            ranges = []
+            for r in comments.split(','):
+                if r:
+                    (start, end) = r.split('-')
+                    ranges.append((int(start), int(end)))
+            if statement:
+                (first, last) = statement.split('-')
+                statement = (int(first), int(last))
+            else:
+                statement = None
+            contexts.append((function, ranges, statement))
+    except (IOError, ValueError):
+        contexts = None
+
+    cache[key] = contexts
+    return contexts
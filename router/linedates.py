@@ -0,0 +1,26 @@
+import os.path
+
+index_paths = {}
+cache = {}
+
+def load(config):
+    global index_paths
+    for tree_name in config['trees']:
+        index_paths[tree_name] = config['trees'][tree_name]['index_path']
+
+# Returns a list giving the commit time (seconds since the epoch) of
+# the last change to each line of |path|, or None if there is no blame
+# data for the file.
+def lookup(tree_name, path):
+    key = (tree_name, path)
+    if key in cache:
+        return cache[key]
+
+    filename = os.path.join(index_paths[tree_name], 'line-dates', path)
+    try:
+        dates = [ int(l) for l in open(filename).read().split() ]
+    except (IOError, ValueError):
+        dates = None
+
+    cache[key] = dates
+    return dates
@@ -0,0 +1,51 @@
+import json
+import os.path
+
+index_paths = {}
+
+def load(config):
+    global index_paths
+    for tree_name in config['trees']:
+        index_paths[tree_name] = config['trees'][tree_name]['index_path']
+
+# Parses "path:line:column" into (path, line, column), or returns None.
+# Lines are 1-based and columns are 0-based byte offsets, as in the
+# analysis files.
+def parse(position):
+    pieces = position.rsplit(':', 2)
+    if len(pieces) != 3:
+        return None
+    try:
+        return (pieces[0], int(pieces[1]), int(pieces[2]))
+    except ValueError:
+        return None
+
+# Reads the source records of |path| once, so a batch of positions in
+# the same file doesn't parse its analysis again for each of them.
+# Returns a dict from line number to (start, end, symbols, pretty)
+# tuples.
+def read_sources(tree_name, path):
+    if '..' in path.split('/'):
+        return {}
+    filename = os.path.join(index_paths[tree_name], 'analysis', path)
+    try:
+        f = open(filename)
+    except IOError:
+        return {}
+
+    result = {}
+    for line in f:
+        if '"source"' not in line:
+            continue
+        record = json.loads(line)
+        (line_str, _, cols) = record['loc'].partition(':')
+        (start, _, end) = cols.partition('-')
+        if not end:
+            continue
+        result.setdefault(int(line_str), []).append((int(start), int(end), record['sym'], record.get('pretty', '')))
+    return result
+
+# Returns (symbols, pretty) pairs for the source records from
+# read_sources that cover the given position.
+def lookup(sources, lineno, col):
+    return [ (syms, pretty) for (start, end, syms, pretty) in sources.get(lineno, []) if start <= col < end ]
@@ -0,0 +1,33 @@
+import json
+import os.path
+from logger import log
+
+repo_data = {}
+
+def load(config):
+    global repo_data
+
+    for repo_name in config['trees']:
+        log('Loading symbol renames for %s', repo_name)
+        index_path = config['trees'][repo_name]['index_path']
+
+        renames = {}
+        try:
+            f = open(os.path.join(index_path, 'symbol-renames'))
+        except IOError:
+            repo_data[repo_name] = renames
+            continue
+
+        for line in f.readlines():
+            (old, new, reason) = json.loads(line)
+            renames[old] = new
+        f.close()
+
+        repo_data[repo_name] = renames
+
+# Maps each of the comma-separated |symbols| that was renamed or merged
+# in a later index to its current symbol, as recorded by
+# scripts/match-renamed-symbols.py.
+def resolve(tree_name, symbols):
+    renames = repo_data.get(tree_name, {})
+    return ','.join([ renames.get(sym, sym) for sym in symbols.split(',') ])
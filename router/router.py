@@ -11,6 +11,8 @@ import re
 import subprocess
 import signal
 import time
+import calendar
+import datetime
 import errno
 import traceback
 import collections
@@ -18,11 +20,47 @@ import collections
 import crossrefs
 import identifiers
 import codesearch
+import vendored
+import linedates
+import linecontexts
+import sourcelines
+import components
+import fileclasses
+import boring
+import docsummaries
+import sarif
+import export
+import positions
+import bugrefs
+import renames
 from logger import log
 
 def index_path(tree_name):
     return config['trees'][tree_name]['index_path']
 
+# Trees can list older indexes that are kept around after an index
+# rotation. Each one is loaded as its own tree named "<tree>@<rev>" so
+# that the lookup modules don't need to know about archives at all.
+def add_archived_trees(config):
+    for tree_name in config['trees'].keys():
+        for archive in config['trees'][tree_name].get('archived_indexes', []):
+            archive_config = dict(archive)
+            archive_config['repo_name'] = tree_name
+            config['trees']['%s@%s' % (tree_name, archive['rev'])] = archive_config
+
+# Picks the tree whose index should answer a query. Queries with a
+# rev= parameter are routed to the matching archived index.
+def select_tree(tree_name, query):
+    try:
+        rev = query['rev'][0]
+    except:
+        return tree_name
+
+    archived_name = '%s@%s' % (tree_name, rev)
+    if archived_name not in config['trees']:
+        return None
+    return archived_name
+
 # Simple globbing implementation, except ^ and $ are also allowed.
 def parse_path_filter(filter):
     filter = filter.replace('(', '\\(')
@@ -52,7 +90,33 @@ def parse_search(searchString):
     pieces = searchString.split(' ')
     result = {}
     for i in range(len(pieces)):
-        if pieces[i].startswith('path:'):
+        if pieces[i] == 'path:first-party':
+            result['party'] = 'first'
+        elif pieces[i] == 'path:third-party':
+            result['party'] = 'third'
+        elif pieces[i].startswith('changed-since:'):
+            try:
+                date = datetime.datetime.strptime(pieces[i][len('changed-since:'):], '%Y-%m-%d')
+                result['since'] = calendar.timegm(date.timetuple())
+            except ValueError:
+                pass
+        elif pieces[i].startswith('component:'):
+            result['component'] = pieces[i][len('component:'):]
+        elif pieces[i] in ('is:test', 'is:prod', 'is:build', 'is:generated'):
+            result['file_class'] = pieces[i][len('is:'):]
+        elif pieces[i] == 'context:stmt':
+            result['context_lines'] = 'stmt'
+        elif pieces[i].startswith('context:') and pieces[i][len('context:'):].isdigit():
+            result['context_lines'] = min(int(pieces[i][len('context:'):]), 10)
+        elif pieces[i] == 'in:comment':
+            result['in_comment'] = True
+        elif pieces[i].startswith('in:function:'):
+            result['in_function'] = pieces[i][len('in:function:'):]
+        elif pieces[i].startswith('in:class:'):
+            result['in_class'] = pieces[i][len('in:class:'):]
+        elif pieces[i].startswith('bug:') and pieces[i][len('bug:'):].isdigit():
+            result['bug'] = int(pieces[i][len('bug:'):])
+        elif pieces[i].startswith('path:'):
             result['pathre'] = parse_path_filter(pieces[i][len('path:'):])
         elif pieces[i].startswith('pathre:'):
             result['pathre'] = pieces[i][len('pathre:'):]
@@ -73,21 +137,32 @@ def parse_search(searchString):
     return result
 
 def is_trivial_search(parsed):
-    if 'symbol' in parsed:
+    if 'symbol' in parsed or 'bug' in parsed:
         return False
 
     for k in parsed:
+        if k in ('party', 'since', 'component', 'file_class', 'context_lines', 'in_comment', 'in_function', 'in_class'):
+            continue
         if len(parsed[k]) >= 3:
             return False
 
     return True
 
 class SearchResults(object):
-    def __init__(self):
+    def __init__(self, tree_name):
+        self.tree_name = tree_name
         self.results = []
         self.qualified_results = []
 
         self.pathre = None
+        self.party = None
+        self.since = None
+        self.component = None
+        self.file_class = None
+        self.in_comment = False
+        self.in_function = None
+        self.in_class = None
+        self.context_lines = None
         self.compiled = {}
 
     def set_path_filter(self, path):
@@ -101,6 +176,77 @@ class SearchResults(object):
             # In case the pattern is not a valid RE, treat it as literal string.
             self.pathre = re.compile(re.escape(path), re.IGNORECASE)
 
+    # Restricts results to first-party ('first') or vendored ('third') code.
+    def set_party_filter(self, party):
+        self.party = party
+
+    # Restricts results to lines changed at or after |since| (seconds
+    # since the epoch), according to blame.
+    def set_since_filter(self, since):
+        self.since = since
+
+    # Restricts results to files whose Bugzilla product or component
+    # contains |component| (ignoring case).
+    def set_component_filter(self, component):
+        self.component = component.lower() if component else None
+
+    # Restricts results to files of the class |file_class| ('test',
+    # 'prod', 'build' or 'generated'), see fileclasses.lookup.
+    def set_file_class_filter(self, file_class):
+        self.file_class = file_class
+
+    # Restricts textual results to matches inside comments, inside
+    # functions whose name matches the regexp |function|, or inside
+    # methods of the class |cls|. Other kinds of results are dropped.
+    def set_context_filter(self, comment, function, cls):
+        self.in_comment = comment
+        self.in_class = cls
+        try:
+            self.in_function = re.compile(function) if function else None
+        except re.error:
+            self.in_function = re.compile(re.escape(function))
+
+    def has_context_filter(self):
+        return self.in_comment or self.in_function or self.in_class
+
+    def context_matches(self, contexts, line):
+        if line['lno'] > len(contexts):
+            return False
+        (function, comments, _) = contexts[line['lno'] - 1]
+        if self.in_comment:
+            col = line['bounds'][0]
+            if not any([ start <= col < end for (start, end) in comments ]):
+                return False
+        if self.in_function and not self.in_function.search(function):
+            return False
+        if self.in_class and not function.startswith(self.in_class + '::'):
+            return False
+        return True
+
+    # Adds the text around each matching line to the results: |context|
+    # lines before and after it, or with 'stmt', the rest of the
+    # statement the line is part of, as found by output-file's tokenizer
+    # (up to max_statement_lines either way).
+    def set_context_lines(self, context):
+        self.context_lines = context
+
+    def add_context_lines(self, path, line):
+        lines = sourcelines.lookup(self.tree_name, path)
+        lno = line['lno']
+        if not lines or lno > len(lines):
+            return
+        if self.context_lines == 'stmt':
+            contexts = linecontexts.lookup(self.tree_name, path)
+            if not contexts or lno > len(contexts) or not contexts[lno - 1][2]:
+                return
+            (first, last) = contexts[lno - 1][2]
+            first = max(first, lno - self.max_statement_lines, 1)
+            last = min(last, lno + self.max_statement_lines, len(lines))
+        else:
+            (first, last) = (max(lno - self.context_lines, 1), min(lno + self.context_lines, len(lines)))
+        line['before'] = lines[first - 1 : lno - 1]
+        line['after'] = lines[lno : last]
+
     def add_results(self, results):
         self.results.append(results)
 
@@ -108,24 +254,15 @@ class SearchResults(object):
         self.qualified_results.append((qual, results, modifier))
 
     max_count = 1000
+    max_statement_lines = 10
     max_work = 750
     path_precedences = ['normal', 'test', 'generated']
-    key_precedences = ["Files", "IDL", "Definitions", "Assignments", "Uses", "Declarations", "Textual Occurrences"]
+    key_precedences = ["Files", "IDL", "Definitions", "Assignments", "Uses", "Declarations", "Bug References", "Textual Occurrences"]
 
     def categorize_path(self, path):
-        def is_test(p):
-            if '/unit/' in p:
-                return True
-            if 'test' not in p:
-                return False
-            return ('/test/' in p or '/tests/' in p or '/mochitest/' in p or '/unit/' in p or 'testing/' in p or
-                    '/jsapi-tests/' in p or '/reftests/' in p or '/reftest/' in p or
-                    '/crashtests/' in p or '/crashtest/' in p)
-
-        if '__GENERATED__' in path:
-            return 'generated'
-        elif is_test(path):
-            return 'test'
+        cls = fileclasses.lookup(self.tree_name, path)
+        if cls == 'generated' or cls == 'test':
+            return cls
         else:
             return 'normal'
 
@@ -143,6 +280,41 @@ class SearchResults(object):
         if self.pathre and not self.pathre.search(path):
             return
 
+        if self.party:
+            is_vendored = vendored.lookup(self.tree_name, path) != None
+            if is_vendored != (self.party == 'third'):
+                return
+
+        if self.file_class and fileclasses.lookup(self.tree_name, path) != self.file_class:
+            return
+
+        if self.component:
+            info = components.lookup(self.tree_name, path)
+            if not info or self.component not in ('%s :: %s' % info).lower():
+                return
+
+        if self.since:
+            dates = linedates.lookup(self.tree_name, path)
+            if not dates:
+                return
+            if kind == 'Files':
+                if max(dates) < self.since:
+                    return
+            else:
+                lines = [ l for l in lines if l['lno'] <= len(dates) and dates[l['lno'] - 1] >= self.since ]
+                if not lines:
+                    return
+
+        if self.has_context_filter():
+            if kind != 'Textual Occurrences':
+                return
+            contexts = linecontexts.lookup(self.tree_name, path)
+            if not contexts:
+                return
+            lines = [ l for l in lines if self.context_matches(contexts, l) ]
+            if not lines:
+                return
+
         # compiled is a map {pathkind: {qkind: {path: [(lines, line_modifier)]}}}
         kind_results = self.compiled.setdefault(pathkind, collections.OrderedDict()).setdefault(qkind, {})
         path_results = kind_results.setdefault(path, ([], line_modifier))
@@ -168,8 +340,16 @@ class SearchResults(object):
                         if key in line_hash:
                             continue
                         line_hash[key] = True
+                        # Saying a match is "in nsString::Assign" is
+                        # never helpful, so drop boring contexts.
+                        contextsym = line.get('contextsym', '').split(',')[0]
+                        if boring.is_boring(self.tree_name, contextsym, line.get('context')):
+                            line.pop('context', None)
+                            line.pop('contextsym', None)
                         if line_modifier:
                             line_modifier(line)
+                        if self.context_lines:
+                            self.add_context_lines(path, line)
                         lines_out.append(line)
                         count += 1
                         if count == self.max_count:
@@ -177,7 +357,14 @@ class SearchResults(object):
 
                     if lines_out or qkind == 'Files':
                         l = result.setdefault(pathkind, collections.OrderedDict()).setdefault(qkind, [])
-                        l.append({'path': path, 'lines': lines_out})
+                        path_result = {'path': path, 'lines': lines_out}
+                        vendor_info = vendored.lookup(self.tree_name, path)
+                        if vendor_info:
+                            path_result['vendored'] = vendor_info
+                        component_info = components.lookup(self.tree_name, path)
+                        if component_info:
+                            path_result['component'] = component_info
+                        l.append(path_result)
                     if count == self.max_count:
                         break
                 if count == self.max_count:
@@ -253,9 +440,10 @@ def identifier_search(search, tree_name, needle, complete, fold_case):
             q = qualified
 
         results = crossrefs.lookup(tree_name, sym)
+        docsummaries.annotate(tree_name, sym, results)
         search.add_qualified_results(q, results, line_modifier)
 
-def get_json_search_results(tree_name, query):
+def get_search_results(tree_name, query):
     try:
         search_string = query['q'][0]
     except:
@@ -292,14 +480,19 @@ def get_json_search_results(tree_name, query):
         del parsed['default']
 
     if is_trivial_search(parsed):
-        results = {}
-        return json.dumps(results)
+        return {}
 
     title = search_string
     if not title:
         title = 'Files ' + path_filter
 
-    search = SearchResults()
+    search = SearchResults(tree_name)
+    search.set_party_filter(parsed.get('party'))
+    search.set_since_filter(parsed.get('since'))
+    search.set_component_filter(parsed.get('component'))
+    search.set_file_class_filter(parsed.get('file_class'))
+    search.set_context_filter(parsed.get('in_comment'), parsed.get('in_function'), parsed.get('in_class'))
+    search.set_context_lines(parsed.get('context_lines'))
 
     work_limit = False
 
@@ -307,7 +500,20 @@ def get_json_search_results(tree_name, query):
         search.set_path_filter(parsed.get('pathre'))
         symbols = parsed['symbol']
         title = 'Symbol ' + symbols
-        search.add_results(crossrefs.lookup(tree_name, symbols))
+        results = crossrefs.lookup(tree_name, symbols)
+        if not results:
+            # Saved searches name symbols that may have been renamed since.
+            current = renames.resolve(tree_name, symbols)
+            if current != symbols:
+                title += ' (now %s)' % current
+                symbols = current
+                results = crossrefs.lookup(tree_name, symbols)
+        docsummaries.annotate(tree_name, symbols.split(',')[0], results)
+        search.add_results(results)
+    elif 'bug' in parsed:
+        search.set_path_filter(parsed.get('pathre'))
+        title = 'Bug %d' % parsed['bug']
+        search.add_results({'Bug References': bugrefs.lookup(tree_name, parsed['bug'])})
     elif 're' in parsed:
         path = parsed.get('pathre', '.*')
         substr_results = codesearch.search(parsed['re'], fold_case, path, tree_name)
@@ -335,6 +541,35 @@ def get_json_search_results(tree_name, query):
     results = search.get(work_limit)
 
     results['*title*'] = title
+    return results
+
+def get_json_search_results(tree_name, query):
+    return json.dumps(get_search_results(tree_name, query))
+
+# Resolves each "path:line:column" in |query| to the symbols there and
+# their definitions, for tools that need to symbolicate positions in
+# bulk.
+def get_json_resolved_positions(tree_name, query):
+    results = []
+    sources = {}
+    for position in query.get('pos', []):
+        parsed = positions.parse(position)
+        if not parsed:
+            results.append({'position': position, 'error': 'expected path:line:column'})
+            continue
+        (path, lineno, col) = parsed
+        if path not in sources:
+            sources[path] = positions.read_sources(tree_name, path)
+        symbols = []
+        for (syms, pretty) in positions.lookup(sources[path], lineno, col):
+            for sym in syms.split(','):
+                definitions = crossrefs.lookup(tree_name, sym).get('Definitions', [])
+                symbols.append({
+                    'symbol': sym,
+                    'pretty': pretty,
+                    'definitions': [ '%s:%d' % (d['path'], l['lno']) for d in definitions for l in d['lines'] ],
+                })
+        results.append({'position': position, 'symbols': symbols})
     return json.dumps(results)
 
 class Handler(SimpleHTTPServer.SimpleHTTPRequestHandler):
@@ -410,22 +645,55 @@ class Handler(SimpleHTTPServer.SimpleHTTPRequestHandler):
         elif len(path_elts) >= 2 and path_elts[1] == 'search':
             tree_name = path_elts[0]
             query = urlparse.parse_qs(url.query)
-            j = get_json_search_results(tree_name, query)
+            data_tree_name = select_tree(tree_name, query)
+            if not data_tree_name:
+                self.send_error(404, 'No archived index for that revision')
+                return
+            if query.get('format') == ['sarif']:
+                results = get_search_results(data_tree_name, query)
+                self.generate(sarif.from_search_results(results.get('*title*', ''), results), 'application/sarif+json')
+                return
+            if query.get('format') in (['jsonl'], ['csv']):
+                results = get_search_results(data_tree_name, query)
+                symbol = parse_search(query.get('q', [''])[0]).get('symbol')
+                if query['format'] == ['jsonl']:
+                    self.generate(export.to_jsonl(results, symbol), 'application/x-ndjson')
+                else:
+                    self.generate(export.to_csv(results, symbol), 'text/csv')
+                return
+            j = get_json_search_results(data_tree_name, query)
             if 'json' in self.headers.getheader('Accept', ''):
                 self.generate(j, 'application/json')
             else:
                 j = j.replace("</", "<\\/").replace("<script", "<\\script").replace("<!", "<\\!")
                 template = os.path.join(index_path(tree_name), 'templates/search.html')
                 self.generateWithTemplate({'{{BODY}}': j, '{{TITLE}}': 'Search'}, template)
+        elif len(path_elts) >= 2 and path_elts[1] == 'resolve':
+            tree_name = path_elts[0]
+            query = urlparse.parse_qs(url.query)
+            data_tree_name = select_tree(tree_name, query)
+            if not data_tree_name:
+                self.send_error(404, 'No archived index for that revision')
+                return
+            self.generate(get_json_resolved_positions(data_tree_name, query), 'application/json')
         elif path_elts[1] == 'define':
             tree_name = path_elts[0]
             query = urlparse.parse_qs(url.query)
             symbol = query['q'][0]
-            results = crossrefs.lookup(tree_name, symbol)
+            data_tree_name = select_tree(tree_name, query)
+            if not data_tree_name:
+                self.send_error(404, 'No archived index for that revision')
+                return
+            results = crossrefs.lookup(data_tree_name, symbol)
+            if not results:
+                results = crossrefs.lookup(data_tree_name, renames.resolve(data_tree_name, symbol))
             definition = results['Definitions'][0]
             filename = definition['path']
             lineno = definition['lines'][0]['lno']
-            url = '/' + tree_name + '/source/' + filename + '#' + str(lineno)
+            if data_tree_name != tree_name:
+                url = '/' + tree_name + '/rev/' + query['rev'][0] + '/' + filename + '#' + str(lineno)
+            else:
+                url = '/' + tree_name + '/source/' + filename + '#' + str(lineno)
 
             self.send_response(301)
             self.send_header("Location", url)
@@ -459,12 +727,24 @@ else:
     config_fname = 'config.json'
 
 config = json.load(open(config_fname))
+add_archived_trees(config)
 
 os.chdir(config['mozsearch_path'])
 
 crossrefs.load(config)
 codesearch.load(config)
 identifiers.load(config)
+vendored.load(config)
+linedates.load(config)
+linecontexts.load(config)
+sourcelines.load(config)
+components.load(config)
+fileclasses.load(config)
+boring.load(config)
+docsummaries.load(config)
+positions.load(config)
+bugrefs.load(config)
+renames.load(config)
 
 class ForkingServer(ForkingMixIn, HTTPServer):
     pass
@@ -0,0 +1,38 @@
+import json
+
+# Converts search results, as returned by SearchResults.get, into a SARIF
+# 2.1.0 log. Each matching line becomes a result whose rule is the kind
+# of match ("Uses", "Textual Occurrences", ...). Paths are relative to
+# the SRCROOT base.
+def from_search_results(title, results):
+    rules = set()
+    out = []
+    for pathkind in results:
+        if pathkind.startswith('*'):
+            continue
+        for (qkind, paths) in results[pathkind].items():
+            rules.add(qkind)
+            for path_result in paths:
+                artifact = {'uri': path_result['path'], 'uriBaseId': 'SRCROOT'}
+                lines = path_result['lines'] or [None]
+                for line in lines:
+                    location = {'artifactLocation': artifact}
+                    text = title
+                    if line:
+                        # Bounds are relative to the trimmed line text,
+                        # so only the line number is reported.
+                        location['region'] = {'startLine': line['lno']}
+                        text = '%s: %s' % (title, line['line'].strip())
+                    out.append({
+                        'ruleId': qkind,
+                        'level': 'note',
+                        'message': {'text': text},
+                        'locations': [{'physicalLocation': location}],
+                    })
+
+    driver = {'name': 'searchfox', 'rules': [ {'id': r} for r in sorted(rules) ]}
+    return json.dumps({
+        'version': '2.1.0',
+        '$schema': 'https://json.schemastore.org/sarif-2.1.0.json',
+        'runs': [{'tool': {'driver': driver}, 'results': out}],
+    })
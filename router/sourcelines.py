@@ -0,0 +1,33 @@
+import os.path
+
+roots = {}
+cache = {}
+
+def load(config):
+    global roots
+    for tree_name in config['trees']:
+        tree = config['trees'][tree_name]
+        roots[tree_name] = (tree.get('files_path'), tree.get('objdir_path'))
+
+# Returns the lines of the source file |path| (without line endings),
+# or None if it can't be read. Generated files live in the objdir.
+def lookup(tree_name, path):
+    key = (tree_name, path)
+    if key in cache:
+        return cache[key]
+
+    (files_path, objdir_path) = roots.get(tree_name, (None, None))
+    if path.startswith('__GENERATED__/'):
+        (root, path) = (objdir_path, path[len('__GENERATED__/'):])
+    else:
+        root = files_path
+
+    lines = None
+    if root and '..' not in path.split('/'):
+        try:
+            lines = open(os.path.join(root, path)).read().decode('utf-8', 'replace').split('\n')
+        except IOError:
+            pass
+
+    cache[key] = lines
+    return lines
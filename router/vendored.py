@@ -0,0 +1,41 @@
+import json
+import os.path
+from logger import log
+
+repo_data = {}
+
+def load(config):
+    global repo_data
+
+    for repo_name in config['trees']:
+        log('Loading vendored directories for %s', repo_name)
+        index_path = config['trees'][repo_name]['index_path']
+
+        dirs = {}
+        try:
+            f = open(os.path.join(index_path, 'vendored-dirs'))
+        except IOError:
+            repo_data[repo_name] = dirs
+            continue
+
+        for line in f.readlines():
+            (d, name, version) = json.loads(line)
+            dirs[d] = (name, version)
+        f.close()
+
+        repo_data[repo_name] = dirs
+
+# Returns the (upstream name, version) of the vendored directory
+# containing |path|, or None if |path| is first-party code.
+def lookup(tree_name, path):
+    dirs = repo_data.get(tree_name, {})
+    if not dirs:
+        return None
+
+    elts = path.split('/')
+    for i in range(len(elts) - 1, 0, -1):
+        info = dirs.get('/'.join(elts[:i]))
+        if info:
+            return info
+
+    return None
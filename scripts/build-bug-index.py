@@ -0,0 +1,29 @@
+#!/usr/bin/env python
+
+# Gathers the per-file lists of bug references written by output-file
+# into one index, sorted by bug number, with one JSON line per
+# reference: [bug, path, line number, line text].
+
+import os
+import os.path
+import json
+
+indexRoot = os.environ['INDEX_ROOT']
+refsRoot = os.path.join(indexRoot, 'bug-refs')
+
+refs = []
+for (dirpath, dirnames, filenames) in os.walk(refsRoot):
+    for filename in filenames:
+        fullpath = os.path.join(dirpath, filename)
+        path = os.path.relpath(fullpath, refsRoot)
+        for line in open(fullpath).read().split('\n'):
+            if not line:
+                continue
+            (bug, lineno, text) = line.split('\t', 2)
+            refs.append((int(bug), path, int(lineno), text))
+
+refs.sort()
+out = open(os.path.join(indexRoot, 'bug-index'), 'w')
+for ref in refs:
+    out.write(json.dumps(ref) + '\n')
+out.close()
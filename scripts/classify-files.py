@@ -0,0 +1,120 @@
+#!/usr/bin/env python
+
+# Classifies every file in the tree as "test", "build", "generated" or
+# "prod" and writes $INDEX_ROOT/file-classes, with a JSON line
+# [path, class] for each file that isn't "prod". The tree's config can
+# give its own path rules as regexps, checked in order:
+#
+#   "file_classes": {
+#     "test": ["^testing/", "/tests?/"],
+#     "build": ["(^|/)moz\\.build$", "^build/"]
+#   }
+#
+# Without them, the guesses the tools used to make are kept (test_dirs if
+# the tree has them). Either way, files listed in test manifests are
+# tests. Files in the objdir are always generated, which readers can
+# tell from their __GENERATED__ prefix, so they aren't listed.
+
+import os
+import os.path
+import json
+import re
+
+indexRoot = os.environ['INDEX_ROOT']
+config = json.load(open(os.environ['CONFIG_FILE']))
+tree = config['trees'][os.environ['TREE_NAME']]
+
+def read_lines(name):
+    try:
+        return [ l for l in open(os.path.join(indexRoot, name)).read().split('\n') if l ]
+    except IOError:
+        return []
+
+repoFiles = read_lines('repo-files')
+repoSet = set(repoFiles)
+
+def looks_like_test(path):
+    if '/unit/' in path:
+        return True
+    if 'test' not in path:
+        return False
+    return any([ d in path for d in ('/test/', '/tests/', '/mochitest/', 'testing/', '/jsapi-tests/',
+                                     '/reftests/', '/reftest/', '/crashtests/', '/crashtest/') ])
+
+buildNames = ('moz.build', 'Makefile.in', 'Makefile', 'configure.in', 'old-configure.in', 'CMakeLists.txt', 'BUILD.gn')
+buildExts = ('.mozbuild', '.mk', '.gn', '.gni', '.cmake')
+
+def looks_like_build(path):
+    name = os.path.basename(path)
+    return name in buildNames or name.endswith(buildExts) or path.startswith('build/')
+
+rules = tree.get('file_classes')
+if rules:
+    rules = [ (cls, [ re.compile(r) for r in rules.get(cls, []) ]) for cls in ('test', 'build') ]
+
+def classify(path):
+    if rules:
+        for (cls, regexps) in rules:
+            if any([ r.search(path) for r in regexps ]):
+                return cls
+        return 'prod'
+    if 'test_dirs' in tree:
+        if any([ path.startswith(d) for d in tree['test_dirs'] ]):
+            return 'test'
+    elif looks_like_test(path):
+        return 'test'
+    if looks_like_build(path):
+        return 'build'
+    return 'prod'
+
+# Test manifests name their tests in [sections] and list helper files
+# under support-files. Reftest manifests name the files they load.
+manifestTests = set()
+
+def add_manifest_file(manifest, name):
+    name = name.strip()
+    if not name or name.startswith(('!', '/')) or '*' in name:
+        return
+    path = os.path.normpath(os.path.join(os.path.dirname(manifest), name))
+    if path in repoSet:
+        manifestTests.add(path)
+
+def read_source(path):
+    try:
+        return open(os.path.join(os.environ['FILES_ROOT'], path)).read().split('\n')
+    except IOError:
+        return []
+
+for path in repoFiles:
+    name = os.path.basename(path)
+    if name.endswith('.ini') and re.search(r'(mochitest|xpcshell|browser|chrome|a11y)', name):
+        inSupport = False
+        for line in read_source(path):
+            m = re.match(r'^\[(.*)\]\s*$', line)
+            if m:
+                inSupport = False
+                if m.group(1) != 'DEFAULT':
+                    add_manifest_file(path, m.group(1))
+                continue
+            m = re.match(r'^support-files\s*=(.*)$', line)
+            if m:
+                inSupport = True
+                for f in m.group(1).split():
+                    add_manifest_file(path, f)
+            elif inSupport and line[:1].isspace():
+                for f in line.split():
+                    add_manifest_file(path, f)
+            else:
+                inSupport = False
+    elif name in ('reftest.list', 'crashtests.list'):
+        for line in read_source(path):
+            for token in line.split('#')[0].split():
+                if '.' in token and ':' not in token and '(' not in token:
+                    add_manifest_file(path, token)
+
+out = open(os.path.join(indexRoot, 'file-classes'), 'w')
+for path in repoFiles:
+    cls = 'test' if path in manifestTests else classify(path)
+    if cls != 'prod':
+        out.write(json.dumps([path, cls]) + '\n')
+out.close()
@@ -0,0 +1,83 @@
+#!/usr/bin/env python
+
+# Records the Bugzilla component that owns each file, according to the
+# BUG_COMPONENT assignments in `with Files(...)` blocks of moz.build
+# files. As in the build system, patterns are relative to the directory
+# of the moz.build file, and assignments in deeper moz.build files (or
+# later in the same file) take precedence.
+
+import os
+import os.path
+import json
+import re
+
+indexRoot = os.environ['INDEX_ROOT']
+treeRoot = os.environ['FILES_ROOT']
+
+def pattern_regexp(pattern):
+    # A pattern without wildcards also matches everything below it.
+    if '*' not in pattern:
+        return re.compile(re.escape(pattern.rstrip('/')) + '(/.*)?$')
+
+    r = ''
+    i = 0
+    while i < len(pattern):
+        if pattern.startswith('**/', i):
+            r += '(.*/)?'
+            i += 3
+        elif pattern.startswith('**', i):
+            r += '.*'
+            i += 2
+        elif pattern[i] == '*':
+            r += '[^/]*'
+            i += 1
+        else:
+            r += re.escape(pattern[i])
+            i += 1
+    return re.compile(r + '$')
+
+def parse_moz_build(path):
+    rules = []
+    patterns = None
+    try:
+        lines = open(os.path.join(treeRoot, path)).read().split('\n')
+    except IOError:
+        return rules
+
+    for line in lines:
+        m = re.match(r'^with Files\((.*)\):', line)
+        if m:
+            patterns = [ pattern_regexp(p) for p in re.findall(r'[\'"]([^\'"]*)[\'"]', m.group(1)) ]
+            continue
+        if line and not line[0].isspace():
+            patterns = None
+            continue
+
+        m = re.match(r'^\s+BUG_COMPONENT\s*=\s*\(\s*[\'"]([^\'"]*)[\'"]\s*,\s*[\'"]([^\'"]*)[\'"]', line)
+        if m and patterns:
+            rules.append((patterns, m.group(1), m.group(2)))
+
+    return rules
+
+files = [ line.strip() for line in open(os.path.join(indexRoot, 'repo-files')).readlines() ]
+
+# Map from directory to the rules in its moz.build file.
+rules = {}
+for path in files:
+    (dirname, basename) = os.path.split(path)
+    if basename == 'moz.build':
+        rules[dirname] = parse_moz_build(path)
+
+out = open(os.path.join(indexRoot, 'bug-components'), 'w')
+for path in files:
+    elts = path.split('/')
+    component = None
+    for i in range(len(elts)):
+        d = '/'.join(elts[:i])
+        relative = '/'.join(elts[i:])
+        for (patterns, product, comp) in rules.get(d, []):
+            if any([ p.match(relative) for p in patterns ]):
+                component = (product, comp)
+    if component:
+        out.write(json.dumps([path, component[0], component[1]]) + '\n')
+out.close()
@@ -0,0 +1,97 @@
+#!/usr/bin/env python
+
+# Finds directories containing vendored third-party code and records
+# the upstream name and version of each one. We recognize moz.yaml
+# manifests and Rust crates vendored with `cargo vendor` (which leave a
+# .cargo-checksum.json file behind).
+
+import os
+import os.path
+import json
+import re
+
+indexRoot = os.environ['INDEX_ROOT']
+treeRoot = os.environ['FILES_ROOT']
+
+def read_lines(path):
+    try:
+        return open(os.path.join(treeRoot, path)).read().split('\n')
+    except IOError:
+        return []
+
+def strip_value(v):
+    v = v.split('#')[0].strip()
+    if len(v) >= 2 and v[0] == v[-1] and v[0] in '"\'':
+        v = v[1:-1]
+    return v
+
+def parse_moz_yaml(path):
+    name = None
+    version = None
+    in_origin = False
+    for line in read_lines(path):
+        if not line.strip() or line.lstrip().startswith('#'):
+            continue
+        if not line[0].isspace():
+            in_origin = line.startswith('origin:')
+            continue
+        if not in_origin:
+            continue
+
+        m = re.match(r'^\s+(name|release|revision):\s*(.*)$', line)
+        if not m:
+            continue
+        (key, value) = (m.group(1), strip_value(m.group(2)))
+        if key == 'name':
+            name = value
+        elif key == 'release' or (key == 'revision' and not version):
+            version = value
+
+    return (name, version)
+
+def parse_cargo_toml(path):
+    name = None
+    version = None
+    in_package = False
+    for line in read_lines(path):
+        line = line.strip()
+        if line.startswith('['):
+            in_package = line == '[package]'
+            continue
+        if not in_package:
+            continue
+
+        m = re.match(r'^(name|version)\s*=\s*(.*)$', line)
+        if not m:
+            continue
+        if m.group(1) == 'name':
+            name = strip_value(m.group(2))
+        else:
+            version = strip_value(m.group(2))
+
+    return (name, version)
+
+vendored = {}
+
+for line in open(os.path.join(indexRoot, 'repo-files')).readlines():
+    path = line.strip()
+    (dirname, basename) = os.path.split(path)
+    if basename == 'moz.yaml':
+        (name, version) = parse_moz_yaml(path)
+    elif basename == '.cargo-checksum.json':
+        (name, version) = parse_cargo_toml(os.path.join(dirname, 'Cargo.toml'))
+    else:
+        continue
+
+    if not name:
+        name = os.path.basename(dirname)
+    vendored[dirname] = (name, version or '')
+
+dirs = vendored.keys()
+dirs.sort()
+
+out = open(os.path.join(indexRoot, 'vendored-dirs'), 'w')
+for d in dirs:
+    (name, version) = vendored[d]
+    out.write(json.dumps([d, name, version]) + '\n')
+out.close()
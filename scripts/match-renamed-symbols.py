@@ -0,0 +1,148 @@
+#!/usr/bin/env python
+
+# Matches the symbols that disappeared since the previous index with
+# symbols that appeared in this one, so that saved searches, watched
+# symbols and graph bookmarks keep working after a refactoring. Writes
+# $INDEX_ROOT/symbol-renames, with a JSON line [old, new, reason] for
+# each match. Only symbols with a single definition (those in the jumps
+# file) are matched. A vanished symbol is matched with a new one:
+#
+#   "pretty":  with the same pretty name, defined in the same file (or
+#              in the file git says it was renamed to);
+#   "moved":   with the same pretty name, if it is the only one in the
+#              tree;
+#   "renamed": with the same qualifier, defined in the lines that git's
+#              diff (the same one blame follows) says replaced the old
+#              definition line.
+#
+# Several old symbols can map to the same new one when they were merged.
+# Matches from earlier runs are kept and followed through later renames.
+# The previous symbols and revision are kept in the file named by the
+# tree's "symbol_state" config entry, which must live outside the index
+# directory if the index is rebuilt from scratch. It defaults to
+# $INDEX_ROOT/symbol-state.json.
+
+import os
+import os.path
+import json
+import re
+import subprocess
+
+indexRoot = os.environ['INDEX_ROOT']
+config = json.load(open(os.environ['CONFIG_FILE']))
+tree = config['trees'][os.environ['TREE_NAME']]
+statePath = tree.get('symbol_state', os.path.join(indexRoot, 'symbol-state.json'))
+
+def git(*args):
+    try:
+        return subprocess.check_output(['git'] + list(args), cwd=os.environ['GIT_ROOT'])
+    except (subprocess.CalledProcessError, OSError):
+        return None
+
+rev = git('rev-parse', 'HEAD')
+if rev:
+    rev = rev.strip()
+
+current = {}
+for line in open(os.path.join(indexRoot, 'jumps')):
+    (sym, path, lno, pretty) = json.loads(line)
+    current[sym] = [path, lno, pretty]
+
+try:
+    previous = json.load(open(statePath))
+except (IOError, ValueError):
+    previous = None
+
+def qualifier(pretty):
+    return pretty.rsplit('::', 1)[0] if '::' in pretty else ''
+
+# Returns the files git detected as renamed between the two revisions
+# (old path -> new path) and the changed line ranges of each old path,
+# as (old_start, old_count, new_path, new_start, new_count).
+def read_diff(old_rev, new_rev):
+    moved = {}
+    hunks = {}
+    diff = git('diff', '-M', '-U0', old_rev, new_rev)
+    if diff is None:
+        return (moved, hunks)
+
+    (old_path, new_path) = (None, None)
+    for line in diff.split('\n'):
+        if line.startswith('diff --git '):
+            (old_path, new_path) = (None, None)
+        elif line.startswith('--- '):
+            old_path = line[6:] if line.startswith('--- a/') else None
+        elif line.startswith('+++ '):
+            new_path = line[6:] if line.startswith('+++ b/') else None
+            if old_path and new_path and old_path != new_path:
+                moved[old_path] = new_path
+        elif line.startswith('@@ ') and old_path and new_path:
+            m = re.match(r'^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@', line)
+            if not m:
+                continue
+            count = lambda s: 1 if s is None else int(s)
+            hunks.setdefault(old_path, []).append((int(m.group(1)), count(m.group(2)),
+                                                   new_path, int(m.group(3)), count(m.group(4))))
+    return (moved, hunks)
+
+def match(old, appeared, moved, hunks):
+    (path, lno, pretty) = old
+    new_path = moved.get(path, path)
+
+    same_pretty = appeared['pretty'].get(pretty, [])
+    in_file = [ s for s in same_pretty if current[s][0] == new_path ]
+    if len(in_file) == 1:
+        return (in_file[0], 'pretty')
+    if len(same_pretty) == 1:
+        return (same_pretty[0], 'moved')
+
+    for (old_start, old_count, hunk_path, new_start, new_count) in hunks.get(path, []):
+        if old_start <= lno < old_start + old_count:
+            replaced = [ s for s in appeared['file'].get(hunk_path, [])
+                         if new_start <= current[s][1] < new_start + new_count
+                         and qualifier(current[s][2]) == qualifier(pretty) ]
+            if len(replaced) == 1:
+                return (replaced[0], 'renamed')
+            break
+    return None
+
+renames = {}
+if previous is not None:
+    old_symbols = previous['symbols']
+
+    appeared = {'pretty': {}, 'file': {}}
+    for (sym, (path, lno, pretty)) in current.items():
+        if sym not in old_symbols:
+            appeared['pretty'].setdefault(pretty, []).append(sym)
+            appeared['file'].setdefault(path, []).append(sym)
+
+    if previous.get('rev') and rev:
+        (moved, hunks) = read_diff(previous['rev'], rev)
+    else:
+        (moved, hunks) = ({}, {})
+
+    for (sym, old) in old_symbols.items():
+        if sym in current:
+            continue
+        found = match(old, appeared, moved, hunks)
+        if found:
+            renames[sym] = list(found)
+
+    # Follow earlier matches through this run's renames, and forget the
+    # ones whose symbols came back or whose targets vanished unmatched.
+    for (sym, (target, reason)) in previous.get('renames', {}).items():
+        if sym in current or sym in renames:
+            continue
+        if target in renames:
+            target = renames[target][0]
+        if target in current:
+            renames[sym] = [target, reason]
+
+out = open(os.path.join(indexRoot, 'symbol-renames'), 'w')
+for sym in sorted(renames):
+    out.write(json.dumps([sym] + renames[sym]) + '\n')
+out.close()
+
+out = open(statePath, 'w')
+json.dump({'rev': rev, 'symbols': current, 'renames': renames}, out)
+out.close()
@@ -6,6 +6,9 @@ set +x # Don't show commands!
 mkdir -p $INDEX_ROOT/analysis
 mkdir -p $INDEX_ROOT/file
 mkdir -p $INDEX_ROOT/dir
+mkdir -p $INDEX_ROOT/line-dates
+mkdir -p $INDEX_ROOT/line-contexts
+mkdir -p $INDEX_ROOT/bug-refs
 
 mkdir -p $INDEX_ROOT/analysis/__GENERATED__
 
@@ -14,6 +17,9 @@ do
   mkdir -p "$INDEX_ROOT/file/$dir"
   mkdir -p "$INDEX_ROOT/dir/$dir"
   mkdir -p "$INDEX_ROOT/analysis/$dir"
+  mkdir -p "$INDEX_ROOT/line-dates/$dir"
+  mkdir -p "$INDEX_ROOT/line-contexts/$dir"
+  mkdir -p "$INDEX_ROOT/bug-refs/$dir"
 done
 mkdir -p $INDEX_ROOT/templates
 
@@ -21,7 +21,13 @@ export PYTHONPATH=$MOZSEARCH_PATH/scripts
 date
 
 $MOZSEARCH_PATH/scripts/find-objdir-files.py
+$MOZSEARCH_PATH/scripts/find-vendored-dirs.py
+$MOZSEARCH_PATH/scripts/find-bug-components.py
+$MOZSEARCH_PATH/scripts/classify-files.py
 #$MOZSEARCH_PATH/scripts/objdir-mkdirs.sh
 
 echo CROSS REF
 $MOZSEARCH_PATH/scripts/crossref.sh $CONFIG_FILE $TREE_NAME
+
+$MOZSEARCH_PATH/scripts/match-renamed-symbols.py
+$MOZSEARCH_PATH/scripts/notify-watched-symbols.py
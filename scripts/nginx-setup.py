@@ -51,6 +51,10 @@ for repo in config['trees']:
     proxy_pass http://localhost:8000;
   }
 
+  location /%(repo)s/resolve {
+    proxy_pass http://localhost:8000;
+  }
+
   location /%(repo)s/diff {
     proxy_pass http://localhost:8001;
   }
@@ -0,0 +1,116 @@
+#!/usr/bin/env python
+
+# Compares the crossref entries of the symbols in the tree's watch list
+# with those recorded by the previous run and posts a JSON webhook for
+# each symbol whose callers or signature changed. The config entry looks
+# like:
+#
+#   "watch": {
+#     "symbols": ["_ZN7nsINode8GetChildEj", ...],
+#     "webhook": "https://example.com/hook",
+#     "state": "/home/ubuntu/watched-symbols.json"
+#   }
+#
+# The state file must live outside the index directory if the index is
+# rebuilt from scratch. It defaults to $INDEX_ROOT/watched-symbols.json.
+
+import os
+import os.path
+import json
+import urllib2
+
+indexRoot = os.environ['INDEX_ROOT']
+config = json.load(open(os.environ['CONFIG_FILE']))
+watch = config['trees'][os.environ['TREE_NAME']].get('watch')
+if not watch or not watch.get('symbols'):
+    exit(0)
+
+statePath = watch.get('state', os.path.join(indexRoot, 'watched-symbols.json'))
+watched = set(watch['symbols'])
+
+# Summarizes a crossref entry as its callers (contextsym -> pretty
+# name) and the text of its first definition line.
+def summarize(entry):
+    callers = {}
+    for path in entry.get('Uses', []):
+        for line in path['lines']:
+            sym = line.get('contextsym', '').split(',')[0]
+            if sym:
+                callers[sym] = line.get('context', '')
+    signature = None
+    for path in entry.get('Definitions', []):
+        if path['lines']:
+            signature = path['lines'][0]['line']
+            break
+    return {'callers': callers, 'signature': signature}
+
+# Watched symbols that were renamed or merged are followed to their new
+# symbol (see match-renamed-symbols.py).
+renamedTo = {}
+try:
+    for line in open(os.path.join(indexRoot, 'symbol-renames')):
+        (old, new, reason) = json.loads(line)
+        if old in watched:
+            renamedTo.setdefault(new, []).append(old)
+except IOError:
+    pass
+
+current = {}
+f = open(os.path.join(indexRoot, 'crossref'))
+while True:
+    key = f.readline()
+    if not key:
+        break
+    value = f.readline()
+    key = key.strip()
+    if key in watched:
+        current[key] = summarize(json.loads(value))
+    for sym in renamedTo.get(key, []):
+        current[sym] = summarize(json.loads(value))
+        current[sym]['renamed_to'] = key
+f.close()
+
+try:
+    previous = json.load(open(statePath))
+except (IOError, ValueError):
+    previous = None
+
+def post(payload):
+    request = urllib2.Request(watch['webhook'], json.dumps(payload), {'Content-Type': 'application/json'})
+    try:
+        urllib2.urlopen(request, timeout=30).read()
+    except Exception, e:
+        # A broken hook shouldn't break indexing.
+        print 'Webhook for %s failed: %s' % (payload['symbol'], e)
+
+# Nothing to compare with on the first run.
+if previous is not None and watch.get('webhook'):
+    for sym in sorted(watched):
+        old = previous.get(sym)
+        new = current.get(sym)
+        if old == new:
+            continue
+
+        payload = {'tree': os.environ['TREE_NAME'], 'symbol': sym}
+        if old is None:
+            payload['event'] = 'added'
+        elif new is None:
+            payload['event'] = 'removed'
+        else:
+            payload['event'] = 'changed'
+            added = set(new['callers']) - set(old['callers'])
+            removed = set(old['callers']) - set(new['callers'])
+            payload['added_callers'] = [ {'symbol': c, 'pretty': new['callers'][c]} for c in sorted(added) ]
+            payload['removed_callers'] = [ {'symbol': c, 'pretty': old['callers'][c]} for c in sorted(removed) ]
+            if old['signature'] != new['signature']:
+                payload['signature'] = {'old': old['signature'], 'new': new['signature']}
+            if old.get('renamed_to') != new.get('renamed_to'):
+                payload['renamed_to'] = new.get('renamed_to')
+            elif not added and not removed and 'signature' not in payload:
+                # Only a caller's pretty name changed.
+                continue
+        post(payload)
+
+out = open(statePath, 'w')
+json.dump(current, out)
+out.close()
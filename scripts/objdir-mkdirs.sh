@@ -7,6 +7,8 @@ cat $INDEX_ROOT/objdir-dirs | while IFS= read dir
 do
   mkdir -p "$INDEX_ROOT/file/$dir"
   mkdir -p "$INDEX_ROOT/dir/$dir"
+  mkdir -p "$INDEX_ROOT/line-contexts/$dir"
+  mkdir -p "$INDEX_ROOT/bug-refs/$dir"
 done
 
 set -x
@@ -28,6 +28,8 @@ cat $INDEX_ROOT/repo-files $INDEX_ROOT/objdir-files | grep "$FILTER" | \
 
 if [ "${FILTER}" = ".*" ]
 then
+    $MOZSEARCH_PATH/scripts/build-bug-index.py
+
     cat $INDEX_ROOT/repo-files $INDEX_ROOT/objdir-files > /tmp/dirs
     js $MOZSEARCH_PATH/output-dir.js $FILES_ROOT $INDEX_ROOT $MOZSEARCH_PATH $OBJDIR $TREE_NAME /tmp/dirs
 
@@ -37,6 +37,11 @@ function updateBlamePopup() {
 
     var content = json.header;
 
+    if (json.orig_hg) {
+      var origLink = `https://hg.mozilla.org/mozilla-central/rev/${json.orig_hg}`;
+      content += `<br><span class="deemphasize">Originally authored as <a href="${origLink}">${json.orig_hg.substr(0, 12)}</a></span>`;
+    }
+
     var diffLink = `/${tree}/diff/${rev}/${path}#${lineno}`;
     content += `<br><a href="${diffLink}">Show annotated diff</a>`;
 
@@ -53,6 +53,7 @@ $(function() {
   // We start the history timeout after the search updates (i.e., after
   // timeouts.search has elapsed).
   timeouts.history = 2000 - timeouts.search;
+  timeouts.complete = 100;
 
   // Check if the currently loaded page has a hash in the URL
   if (window.location.hash) {
@@ -108,6 +109,15 @@ $(function() {
     }
   }
 
+  /**
+   * Returns the archived revision the search is pinned to (via the
+   * `rev` URL parameter), or null when searching the latest index.
+   */
+  function revFromUrl() {
+    var match = /[?&]rev=([^&]+)/.exec(location.search);
+    return match ? decodeURIComponent(match[1]) : null;
+  }
+
   var searchForm = $('#basic_search'),
   queryField = $('#query'),
   pathField = $('#path'),
@@ -164,6 +174,10 @@ $(function() {
     params['case'] = caseSensitiveBox.prop('checked');
     params.regexp = regexpBox.prop('checked');
     params.path = $.trim(pathField.val());
+    var rev = revFromUrl();
+    if (rev) {
+      params.rev = rev;
+    }
 
     return search + '?' + $.param(params);
   }
@@ -235,6 +249,10 @@ $(function() {
     window.scrollTo(0, 0);
 
     function makeURL(path) {
+      var rev = revFromUrl();
+      if (rev) {
+        return "/" + dxr.tree + "/rev/" + rev + "/" + path;
+      }
       return "/" + dxr.tree + "/source/" + path;
     }
 
@@ -269,6 +287,10 @@ $(function() {
       }[suffix] || "unknown";
     }
 
+    function escape(s) {
+      return s.replace(/&/gm, "&amp;").replace(/</gm, "&lt;");
+    }
+
     function renderPath(pathkind, qkind, fileResult) {
       var klass = classOfResult(pathkind, qkind);
 
@@ -291,6 +313,18 @@ $(function() {
         pathSoFar += "/";
       }
 
+      if (fileResult.vendored) {
+        var [vendorName, vendorVersion] = fileResult.vendored;
+        html += " <span class='deemphasize'>(third-party: " + escape(vendorName) +
+          (vendorVersion ? " " + escape(vendorVersion) : "") + ")</span>";
+      }
+
+      if (fileResult.component) {
+        var [product, component] = fileResult.component;
+        var owner = escape(product + " :: " + component);
+        html += " <span class='deemphasize'>(" + owner + ")</span>";
+      }
+
       html += "</td>";
       html += "</tr>"
 
@@ -305,15 +339,24 @@ $(function() {
 
       var klass = classOfResult(pathkind, qkind);
       var html = "";
+
+      function contextRows(lines, firstLno) {
+        var rows = "";
+        for (var i = 0; i < lines.length; i++) {
+          rows += "<tr class='" + klass + "'><td class='left-column deemphasize'>" + (firstLno + i) + "</td>";
+          rows += "<td class='deemphasize'><code>" + escape(lines[i]) + "</code></td></tr>";
+        }
+        return rows;
+      }
+
+      if (line.before) {
+        html += contextRows(line.before, line.lno - line.before.length);
+      }
       html += "<tr class='" + klass + "'>";
       html += "<td class='left-column'><a href='" + makeURL(file.path) + "#" + line.lno + "'>" +
         line.lno + "</a></td>";
       html += "<td><a href='" + makeURL(file.path) + "#" + line.lno + "'>";
 
-      function escape(s) {
-        return s.replace(/&/gm, "&amp;").replace(/</gm, "&lt;");
-      }
-
       html += "<code>";
       html += escape(before);
       html += "<b>" + escape(middle) + "</b>";
@@ -331,9 +374,17 @@ $(function() {
         html += " <span class='result-context'>// found in <code>" + inside + "</code></span>";
       }
 
+      if (line.summary) {
+        html += " <span class='result-context'>// " + escape(line.summary) + "</span>";
+      }
+
       html += "</td>";
       html += "</tr>";
 
+      if (line.after) {
+        html += contextRows(line.after, line.lno + 1);
+      }
+
       return html;
     }
 
@@ -363,9 +414,9 @@ $(function() {
       if (count == 1) {
         var line = file.lines[0];
         var lno = line.lno;
-        window.location = `/${dxr.tree}/source/${path}#${lno}`;
+        window.location = makeURL(path) + "#" + lno;
       } else {
-        window.location = `/${dxr.tree}/source/${path}`;
+        window.location = makeURL(path);
       }
       return;
     }
@@ -520,8 +571,35 @@ $(function() {
       });
   }
 
+  var completionList = $('#query-completions'),
+  completionWaiter = null;
+
+  /**
+   * Fills the query field's datalist with ranked completions of the
+   * last word typed, from the index's completion table.
+   */
+  function completeSoon() {
+    clearTimeout(completionWaiter);
+    completionWaiter = setTimeout(function() {
+      var word = queryField.val().split(/\s+/).pop();
+      // Leave query terms like path: alone, but not qualified names.
+      if (!word || /^[a-z-]*:(?!:)/.test(word)) {
+        completionList.empty();
+        return;
+      }
+      var prefix = queryField.val().slice(0, queryField.val().length - word.length);
+      $.getJSON('/' + dxr.tree + '/complete/' + encodeURIComponent(word), function(completions) {
+        completionList.empty();
+        $.each(completions, function(i, completion) {
+          completionList.append($('<option>').attr('value', prefix + completion.name).text(completion.kind));
+        });
+      });
+    }, timeouts.complete);
+  }
+
   // Do a search every time you pause typing for 300ms:
   queryField.on('input', querySoon);
+  queryField.on('input', completeSoon);
   pathField.on('input', querySoon);
 
   // Update the search when the case-sensitive box is toggled, canceling any pending query:
@@ -0,0 +1,77 @@
+use std::env;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+extern crate tools;
+
+use tools::config;
+use tools::file_format::crossref::CrossrefMap;
+
+fn is_under(path: &str, dir: &str) -> bool {
+    dir.is_empty() || (path.starts_with(dir) && path[dir.len() ..].starts_with('/'))
+}
+
+fn dirname(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(pos) => &path[.. pos],
+        None => "/",
+    }
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    if args.len() != 4 {
+        println!("Usage: {} config.json tree_name directory", args[0]);
+        return;
+    }
+
+    let cfg = config::load(&args[1], false);
+    let tree_name = &args[2];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let dir = args[3].trim_right_matches('/');
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+
+    // Symbols defined under |dir| that are used from outside it, with
+    // the set of outside callers in each directory.
+    let mut api = vec![];
+    for sym in crossref.symbols() {
+        let entry = match crossref.lookup(sym) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        let def = match entry.first_def() {
+            Some((path, lineno)) if is_under(path, dir) => format!("{}:{}", path, lineno),
+            _ => continue,
+        };
+
+        let mut callers = BTreeMap::new();
+        for path in entry.get("Uses") {
+            if is_under(&path.path, dir) {
+                continue;
+            }
+            let dir_callers = callers.entry(dirname(&path.path).to_string()).or_insert(BTreeSet::new());
+            for line in &path.lines {
+                dir_callers.insert(line.contextsym.split(',').next().unwrap().to_string());
+            }
+        }
+        if callers.is_empty() {
+            continue;
+        }
+
+        let total = callers.values().map(|c| c.len()).sum::<usize>();
+        let name = match entry.get("Definitions").first().and_then(|p| p.lines.first()) {
+            Some(line) if !line.line.is_empty() => line.line.clone(),
+            _ => sym.to_string(),
+        };
+        api.push((total, sym, name, def, callers));
+    }
+    api.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(b.1)));
+
+    for (total, sym, name, def, callers) in api {
+        println!("{}\t{}\t{} external callers\t{}", sym, def, total, name);
+        for (caller_dir, dir_callers) in callers {
+            println!("  {}\t{}", caller_dir, dir_callers.len());
+        }
+    }
+}
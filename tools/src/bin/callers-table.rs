@@ -0,0 +1,142 @@
+use std::env;
+use std::collections::BTreeMap;
+use std::process;
+
+extern crate tools;
+extern crate getopts;
+
+use getopts::Options;
+
+use tools::config;
+use tools::callgraph::{callee_map, collect_callees, collect_callers, find_uses, follow_rename, load_callers, BoringSymbols};
+use tools::file_format::crossref::CrossrefMap;
+use tools::file_format::renames::read_renames;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("d", "max-depth",
+                "Maximum number of hops to follow (default 1, direct callers only)",
+                "DEPTH");
+    opts.optopt("n", "node-limit",
+                "Maximum number of symbols to list (default 256)",
+                "COUNT");
+    opts.optflag("", "callees",
+                 "List the functions the symbol uses instead of its callers");
+    opts.optmulti("", "exclude-sym",
+                  "Leave out symbols matching this regexp (repeatable)",
+                  "REGEXP");
+    opts.optmulti("", "exclude-pretty",
+                  "Leave out symbols whose pretty name matches this regexp (repeatable)",
+                  "REGEXP");
+    opts
+}
+
+struct Row {
+    name: String,
+    depth: u32,
+    sites: usize,
+    location: String,
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 3 {
+        let brief = format!("Usage: {} [options] config.json tree_name symbol", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let callees = matches.opt_present("callees");
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let max_depth = matches.opt_str("max-depth").map_or(1, |d| d.parse::<u32>().unwrap());
+    let node_limit = matches.opt_str("node-limit").map_or(tree_config.paths.graph_node_limit.unwrap_or(256), |n| n.parse::<usize>().unwrap());
+    let mut sym = matches.free[2].to_string();
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+    if let Some((renamed, note)) = follow_rename(&sym, &crossref, &read_renames(&tree_config.paths.index_path)) {
+        println!("{}", note);
+        sym = renamed;
+    }
+    let boring = match BoringSymbols::from_options(tree_config, &matches) {
+        Ok(boring) => boring,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(2);
+        },
+    };
+    let graph = if callees {
+        collect_callees(&callee_map(&crossref), (&sym[..], ""), &boring, max_depth, node_limit + 1)
+    } else {
+        let callers = load_callers(&tree_config.paths.index_path);
+        collect_callers(&crossref, callers.as_ref(), &[(sym.to_string(), "".to_string())], &boring, max_depth, node_limit + 1)
+    };
+
+    // Group the rows by the directory holding each function's definition.
+    let mut groups = BTreeMap::new();
+    for (i, node) in graph.nodes.iter().enumerate().skip(1) {
+        // Count the call sites on the edges connecting this node to the
+        // nodes one hop closer to the root.
+        let neighbors = if callees { graph.callers_of(i) } else { graph.callees_of(i) };
+        let mut sites = 0;
+        for n in neighbors {
+            if graph.nodes[n].depth + 1 != node.depth {
+                continue;
+            }
+            let (caller, callee) = if callees { (n, i) } else { (i, n) };
+            sites += find_uses(&crossref, &graph.nodes[caller].sym, &graph.nodes[callee].sym).len();
+        }
+
+        let def = crossref.lookup(&node.sym).as_ref().and_then(|e| e.first_def().map(|(p, l)| (p.to_string(), l)));
+        let (dir, location) = match def {
+            Some((path, lineno)) => {
+                let dir = match path.rfind('/') {
+                    Some(pos) => path[.. pos].to_string(),
+                    None => "/".to_string(),
+                };
+                (dir, format!("{}:{}", path, lineno))
+            },
+            None => ("(no definition)".to_string(), "".to_string()),
+        };
+
+        let name = if node.pretty.is_empty() { node.sym.clone() } else { node.pretty.clone() };
+        groups.entry(dir).or_insert(Vec::new()).push(Row {
+            name: name,
+            depth: node.depth,
+            sites: sites,
+            location: location,
+        });
+    }
+
+    let mut groups = groups.into_iter().map(|(dir, mut rows)| {
+        rows.sort_by(|a, b| b.sites.cmp(&a.sites).then(a.name.cmp(&b.name)));
+        let total = rows.iter().map(|r| r.sites).sum::<usize>();
+        (dir, total, rows)
+    }).collect::<Vec<_>>();
+    groups.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    for (dir, total, rows) in groups {
+        println!("{} ({} call sites)", dir, total);
+        for row in rows {
+            println!("  {}\t{}\tdepth {}\t{}", row.name, row.sites, row.depth, row.location);
+        }
+    }
+
+    if graph.truncated {
+        println!("");
+        println!("Node limit of {} reached; the table is incomplete.", node_limit);
+        println!("{}", graph.limit_hint(node_limit).unwrap());
+    }
+    if let Some(note) = graph.filter_note() {
+        println!("");
+        println!("{}", note);
+    }
+}
@@ -0,0 +1,219 @@
+use std::cmp;
+use std::collections::HashSet;
+use std::env;
+use std::process;
+
+extern crate tools;
+extern crate getopts;
+
+use getopts::Options;
+
+use tools::config;
+use tools::callgraph::{collect, entry_point_set, find_uses, load_callers, resolve_name};
+use tools::callgraph::{BoringSymbols, Budgeted, Callers, SymbolGraph};
+use tools::file_format::crossref::CrossrefMap;
+use tools::file_format::identifiers::IdentMap;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optmulti("f", "from",
+                  "A symbol or pretty name the paths may start at (repeatable)",
+                  "NAME");
+    opts.optopt("", "from-set",
+                "Start at the entry points in this set of the tree's entry_points",
+                "SET");
+    opts.optmulti("t", "to",
+                  "A symbol or pretty name the paths may end at (repeatable)",
+                  "NAME");
+    opts.optopt("", "to-set",
+                "End at the entry points in this set of the tree's entry_points",
+                "SET");
+    opts.optopt("d", "max-depth",
+                "Maximum number of caller hops to follow (default 8)",
+                "DEPTH");
+    opts.optopt("n", "node-limit",
+                "Maximum number of callers to examine (default 1024)",
+                "COUNT");
+    opts.optopt("p", "paths",
+                "Number of the shortest paths to print from each source (default 1)",
+                "COUNT");
+    opts.optmulti("", "exclude-sym",
+                  "Leave out symbols matching this regexp (repeatable)",
+                  "REGEXP");
+    opts.optmulti("", "exclude-pretty",
+                  "Leave out symbols whose pretty name matches this regexp (repeatable)",
+                  "REGEXP");
+    opts.optopt("", "max-lookups",
+                "Stop walking after looking up the callers of this many symbols",
+                "COUNT");
+    opts.optopt("", "max-millis",
+                "Stop walking after this many milliseconds",
+                "MS");
+    opts
+}
+
+fn set_entries<'a>(tree_config: &'a config::TreeConfig, set: &str) -> &'a [String] {
+    match tree_config.paths.entry_points.as_ref().and_then(|sets| sets.get(set)) {
+        Some(entries) => entries,
+        None => {
+            println!("Tree has no entry point set named {}.", set);
+            process::exit(2);
+        }
+    }
+}
+
+// Resolves every name to its symbols, as (symbol, pretty) pairs.
+fn resolve_all(names: &[String], crossref: &CrossrefMap, ids: &IdentMap) -> Vec<(String, String)> {
+    let mut result = vec![];
+    for name in names {
+        let syms = resolve_name(name, crossref, ids);
+        if syms.is_empty() {
+            println!("{} was not found in the index.", name);
+        }
+        for sym in syms {
+            let pretty = if sym == *name { "".to_string() } else { name.clone() };
+            result.push((sym, pretty));
+        }
+    }
+    result
+}
+
+// Describes each hop of |path|, one line per hop, using |locate| to
+// find where the caller uses the callee.
+fn path_lines(graph: &SymbolGraph, path: &[usize],
+              locate: &Fn(&str, &str) -> Option<(String, u64)>) -> Vec<String> {
+    path.windows(2).map(|pair| {
+        let (caller, callee) = (&graph.nodes[pair[0]], &graph.nodes[pair[1]]);
+        let location = match locate(&caller.sym, &callee.sym) {
+            Some((path, lineno)) => format!("{}:{}", path, lineno),
+            None => "(unknown location)".to_string(),
+        };
+        let name = if callee.pretty.is_empty() { &callee.sym } else { &callee.pretty };
+        format!("-> uses {}\tat {}", name, location)
+    }).collect()
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    let has_from = matches.opt_present("from") || matches.opt_present("from-set");
+    let has_to = matches.opt_present("to") || matches.opt_present("to-set");
+    if matches.free.len() != 2 || !has_from || !has_to {
+        let brief = format!("Usage: {} [options] --from NAME|--from-set SET --to NAME|--to-set SET config.json tree_name", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let max_depth = matches.opt_str("max-depth").map_or(tree_config.paths.graph_max_depth.unwrap_or(8), |d| d.parse::<u32>().unwrap());
+    let node_limit = matches.opt_str("node-limit").map_or(tree_config.paths.graph_node_limit.unwrap_or(1024), |n| n.parse::<usize>().unwrap());
+    let from_set = matches.opt_str("from-set");
+    let path_count = matches.opt_str("paths").map_or(1, |n| cmp::max(n.parse::<usize>().unwrap(), 1));
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+    let ids = IdentMap::new(&format!("{}/identifiers", tree_config.paths.index_path));
+
+    let mut to_names = matches.opt_strs("to");
+    if let Some(set) = matches.opt_str("to-set") {
+        to_names.extend(set_entries(tree_config, &set).iter().cloned());
+    }
+    let roots = resolve_all(&to_names, &crossref, &ids);
+
+    let from_syms = resolve_all(&matches.opt_strs("from"), &crossref, &ids)
+        .into_iter().map(|(sym, _)| sym).collect::<HashSet<_>>();
+    // Fail early if the set name is misspelled.
+    if let Some(ref set) = from_set {
+        set_entries(tree_config, set);
+    }
+    if roots.is_empty() || (from_syms.is_empty() && from_set.is_none()) {
+        process::exit(2);
+    }
+
+    // Walking callers up from the targets finds every path into them at
+    // once, however many sources there are. Nodes are added
+    // breadth-first, so the sources closest to the targets come first.
+    let boring = match BoringSymbols::from_options(tree_config, &matches) {
+        Ok(boring) => boring,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(2);
+        },
+    };
+    let callers_index = load_callers(&tree_config.paths.index_path);
+    let callers = Callers { crossref: &crossref, callers: callers_index.as_ref() };
+    let max_lookups = matches.opt_str("max-lookups").map(|n| n.parse::<usize>().unwrap());
+    let max_millis = matches.opt_str("max-millis").map(|n| n.parse::<u64>().unwrap());
+    let budgeted = Budgeted::new(&callers, max_lookups, max_millis);
+    let graph = collect(&budgeted, &roots, &boring, max_depth, node_limit);
+
+    let locate = |caller: &str, callee: &str| find_uses(&crossref, caller, callee).into_iter().next();
+    let mut found = 0;
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let in_set = from_set.as_ref().map_or(false, |set| {
+            entry_point_set(tree_config, Some(set.as_str()), &node.sym, &node.pretty).is_some()
+        });
+        if !in_set && !from_syms.contains(&node.sym) {
+            continue;
+        }
+        found += 1;
+
+        let name = if node.pretty.is_empty() { &node.sym } else { &node.pretty };
+        println!("{}\t{} hops", name, node.depth);
+        let (paths, more) = graph.paths_to_roots(i, path_count, max_depth as usize);
+        for (n, path) in paths.iter().enumerate() {
+            if n > 0 {
+                println!("  or, in {} hops:", path.len() - 1);
+            }
+            for line in path_lines(&graph, path, &locate) {
+                println!("    {}", line);
+            }
+        }
+        if more && path_count > 1 {
+            println!("  (more paths not shown)");
+        }
+    }
+
+    if found == 0 {
+        println!("No path connects the two sets within {} hops.", max_depth);
+    }
+    if graph.truncated {
+        println!("");
+        println!("Node limit of {} reached; some callers were not examined.", node_limit);
+        println!("{}", graph.limit_hint(node_limit).unwrap());
+    }
+    if let Some(note) = budgeted.exhausted_note() {
+        println!("");
+        println!("{}", note);
+    }
+    if let Some(note) = graph.filter_note() {
+        println!("");
+        println!("{}", note);
+    }
+    if found == 0 {
+        process::exit(1);
+    }
+}
+
+#[test]
+fn test_path_lines() {
+    let mut graph = SymbolGraph::new();
+    let target = graph.add_node("_Z6targetv", "target", 0);
+    let middle = graph.add_node("_Z6middlev", "", 1);
+    let source = graph.add_node("_Z6sourcev", "source", 2);
+    graph.add_edge(middle, target);
+    graph.add_edge(source, middle);
+
+    let locate = |caller: &str, _: &str| {
+        if caller == "_Z6sourcev" { Some(("a.cpp".to_string(), 12)) } else { None }
+    };
+    assert_eq!(path_lines(&graph, &graph.path_to_root(source), &locate),
+               vec!["-> uses _Z6middlev\tat a.cpp:12", "-> uses target\tat (unknown location)"]);
+    assert!(path_lines(&graph, &[target], &locate).is_empty());
+}
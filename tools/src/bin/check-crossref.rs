@@ -0,0 +1,216 @@
+use std::env;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::process;
+use std::collections::BTreeMap;
+
+extern crate tools;
+extern crate getopts;
+extern crate rustc_serialize;
+
+use getopts::Options;
+use rustc_serialize::json::Json;
+
+use tools::config;
+use tools::file_format::crossref::CrossrefMap;
+use tools::sarif::{to_sarif, Finding};
+
+const KINDS: &'static [&'static str] = &["Uses", "Definitions", "Assignments", "Declarations", "IDL"];
+
+// Checks that a crossref entry has the shape the crossref tool writes
+// and returns a description of each problem found.
+fn check_entry(json: &Json) -> Vec<String> {
+    let mut problems = vec![];
+    let kinds = match json.as_object() {
+        Some(kinds) => kinds,
+        None => return vec!["entry is not an object".to_string()],
+    };
+
+    for (kind, paths) in kinds {
+        if !KINDS.contains(&kind.as_str()) {
+            problems.push(format!("unknown kind {}", kind));
+        }
+        let paths = match paths.as_array() {
+            Some(paths) => paths,
+            None => {
+                problems.push(format!("{} is not an array", kind));
+                continue;
+            }
+        };
+        for path in paths {
+            if path.find("path").and_then(|p| p.as_string()).is_none() {
+                problems.push(format!("{} has a result without a path", kind));
+            }
+            let lines = match path.find("lines").and_then(|l| l.as_array()) {
+                Some(lines) => lines,
+                None => {
+                    problems.push(format!("{} has a result without lines", kind));
+                    continue;
+                }
+            };
+            for line in lines {
+                if line.find("lno").and_then(|l| l.as_u64()).is_none() {
+                    problems.push(format!("{} has a line without a line number", kind));
+                }
+                for key in &["line", "context", "contextsym"] {
+                    if line.find(key).and_then(|v| v.as_string()).is_none() {
+                        problems.push(format!("{} has a line without {}", kind, key));
+                    }
+                }
+            }
+        }
+    }
+    problems
+}
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("s", "sarif",
+                "File to write the problems to, in SARIF format",
+                "FILE");
+    opts
+}
+
+struct Violation {
+    check: &'static str,
+    sym: String,
+    detail: String,
+    location: Option<(String, u64)>,
+}
+
+fn violation(check: &'static str, sym: &str, detail: String, location: Option<(&str, u64)>) -> Violation {
+    Violation {
+        check: check,
+        sym: sym.to_string(),
+        detail: detail,
+        location: location.map(|(path, lineno)| (path.to_string(), lineno)),
+    }
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 2 {
+        let brief = format!("Usage: {} [options] config.json tree_name", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+
+    let mut violations = vec![];
+
+    for sym in crossref.symbols() {
+        let json = match crossref.lookup_json(sym) {
+            Some(json) => json,
+            None => {
+                violations.push(violation("schema", sym, "invalid JSON".to_string(), None));
+                continue;
+            }
+        };
+        let problems = check_entry(&json);
+        if !problems.is_empty() {
+            for problem in problems {
+                violations.push(violation("schema", sym, problem, None));
+            }
+            continue;
+        }
+
+        let entry = crossref.lookup(sym).unwrap();
+        for (_, paths) in &entry.kinds {
+            for path in paths {
+                for line in &path.lines {
+                    for context in line.contextsym.split(',') {
+                        if !context.is_empty() && !crossref.contains(context) {
+                            violations.push(violation("contextsym", sym,
+                                                      format!("{}:{} refers to missing {}", path.path, line.lineno, context),
+                                                      Some((&path.path, line.lineno))));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let jumps_fname = format!("{}/jumps", tree_config.paths.index_path);
+    for line in BufReader::new(File::open(jumps_fname).unwrap()).lines() {
+        let line = line.unwrap();
+        let jump = match Json::from_str(&line) {
+            Ok(Json::Array(ref jump)) if jump.len() >= 3 => jump.clone(),
+            _ => {
+                violations.push(violation("jumps", "", format!("malformed line {}", line), None));
+                continue;
+            }
+        };
+        let sym = jump[0].as_string().unwrap_or("");
+        let path = jump[1].as_string().unwrap_or("");
+        let lineno = jump[2].as_u64().unwrap_or(0);
+        let resolves = match crossref.lookup(sym) {
+            Some(entry) => entry.get("Definitions").iter().any(|p| {
+                p.path == path && p.lines.iter().any(|l| l.lineno == lineno)
+            }),
+            None => false,
+        };
+        if !resolves {
+            violations.push(violation("jumps", sym, format!("no definition at {}:{}", path, lineno), Some((path, lineno))));
+        }
+    }
+
+    let ids_fname = format!("{}/identifiers", tree_config.paths.index_path);
+    for line in BufReader::new(File::open(ids_fname).unwrap()).lines() {
+        let line = line.unwrap();
+        let sym = line.split(' ').nth(1).unwrap_or("");
+        if !crossref.contains(sym) {
+            violations.push(violation("identifiers", sym, format!("missing symbol for {}", line), None));
+        }
+    }
+
+    let mut counts = BTreeMap::new();
+    for v in &violations {
+        println!("{}\t{}\t{}", v.check, v.sym, v.detail);
+        *counts.entry(v.check).or_insert(0) += 1;
+    }
+
+    if let Some(sarif_fname) = matches.opt_str("sarif") {
+        let findings = violations.iter().map(|v| Finding {
+            rule: v.check.to_string(),
+            message: if v.sym.is_empty() { v.detail.clone() } else { format!("{}: {}", v.sym, v.detail) },
+            path: v.location.as_ref().map(|l| l.0.clone()),
+            line: v.location.as_ref().map(|l| l.1),
+        }).collect::<Vec<_>>();
+        let mut sarif_file = File::create(sarif_fname).unwrap();
+        write!(sarif_file, "{}", to_sarif("check-crossref", "error", &findings).pretty()).unwrap();
+    }
+
+    if violations.is_empty() {
+        println!("No problems found.");
+    } else {
+        println!("");
+        for (check, count) in counts {
+            println!("{}: {} problems", check, count);
+        }
+        process::exit(1);
+    }
+}
+
+#[test]
+fn test_check_entry() {
+    let good = Json::from_str(r#"{"Uses":[{"path":"a.cpp","lines":[{"lno":1,"line":"f();","context":"g","contextsym":"_Z1gv","bounds":[0,1]}]}]}"#).unwrap();
+    assert!(check_entry(&good).is_empty());
+
+    let bad = Json::from_str(r#"{"Calls":[],"Uses":[{"lines":[{"line":"f();","context":"g","contextsym":"_Z1gv"}]}]}"#).unwrap();
+    assert_eq!(check_entry(&bad), vec!["unknown kind Calls".to_string(),
+                                      "Uses has a result without a path".to_string(),
+                                      "Uses has a line without a line number".to_string()]);
+}
@@ -0,0 +1,120 @@
+use std::env;
+use std::collections::BTreeMap;
+
+extern crate tools;
+extern crate getopts;
+extern crate rustc_serialize;
+
+use getopts::Options;
+use rustc_serialize::json::{Json, ToJson};
+
+use tools::config;
+use tools::file_format::completions::symbol_kind;
+use tools::file_format::identifiers::IdentMap;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("d", "depth",
+                "Number of levels of nested classes and namespaces to list (default 1)",
+                "DEPTH");
+    opts.optopt("n", "max-members",
+                "Maximum number of identifiers to read for each scope (default 10000)",
+                "COUNT");
+    opts.optflag("j", "json",
+                 "Print the tree as JSON");
+    opts
+}
+
+struct Member {
+    name: String,
+    // All the symbols with this name, which there are several of for
+    // overloads.
+    syms: Vec<String>,
+    children: Vec<Member>,
+}
+
+impl ToJson for Member {
+    fn to_json(&self) -> Json {
+        let mut obj = BTreeMap::new();
+        obj.insert("name".to_string(), self.name.to_json());
+        obj.insert("symbols".to_string(), self.syms.to_json());
+        obj.insert("kind".to_string(), self.kind().to_json());
+        obj.insert("children".to_string(), self.children.to_json());
+        Json::Object(obj)
+    }
+}
+
+impl Member {
+    fn kind(&self) -> &'static str {
+        symbol_kind(&self.syms[0])
+    }
+
+    fn is_scope(&self) -> bool {
+        self.syms.iter().any(|sym| sym.starts_with("NS_") || sym.starts_with("T_"))
+    }
+}
+
+// The identifiers file has a line for each qualified name, so asking for
+// the names starting with "scope::" and without any further "::" gives
+// exactly the direct members of |scope|.
+fn children(ids: &IdentMap, scope: &str, depth: u32, max_members: usize) -> Vec<Member> {
+    let mut members: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (id, sym) in ids.lookup_raw(&format!("{}::", scope), false, false, max_members) {
+        let syms = members.entry(&id[scope.len() + 2 ..]).or_insert(vec![]);
+        if !syms.iter().any(|s| s == sym) {
+            syms.push(sym.to_string());
+        }
+    }
+
+    members.into_iter().filter(|&(name, _)| !name.is_empty()).map(|(name, syms)| {
+        let mut member = Member { name: name.to_string(), syms: syms, children: vec![] };
+        if depth > 1 && member.is_scope() {
+            member.children = children(ids, &format!("{}::{}", scope, name), depth - 1, max_members);
+        }
+        member
+    }).collect()
+}
+
+fn print_tree(members: &[Member], indent: usize) {
+    for member in members {
+        let overloads = if member.syms.len() > 1 { format!(", {} symbols", member.syms.len()) } else { "".to_string() };
+        println!("{}{} ({}{})", "  ".repeat(indent), member.name, member.kind(), overloads);
+        print_tree(&member.children, indent + 1);
+    }
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 3 {
+        let brief = format!("Usage: {} [options] config.json tree_name scope", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let depth = matches.opt_str("depth").map_or(1, |d| d.parse::<u32>().unwrap());
+    let max_members = matches.opt_str("max-members").map_or(10000, |n| n.parse::<usize>().unwrap());
+    let scope = matches.free[2].trim_right_matches(':');
+
+    let ids = IdentMap::new(&format!("{}/identifiers", tree_config.paths.index_path));
+    let members = children(&ids, scope, depth, max_members);
+
+    if matches.opt_present("json") {
+        println!("{}", members.to_json().pretty());
+        return;
+    }
+    if members.is_empty() {
+        println!("No members of {} found.", scope);
+        return;
+    }
+    println!("{}", scope);
+    print_tree(&members, 1);
+}
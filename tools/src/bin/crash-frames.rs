@@ -0,0 +1,230 @@
+use std::env;
+use std::io;
+use std::io::BufRead;
+use std::process;
+
+extern crate tools;
+extern crate getopts;
+
+use getopts::Options;
+
+use tools::config;
+use tools::callgraph::{add_output_options, collect_callers, load_callers, resolve_name, write_outputs};
+use tools::callgraph::{BoringSymbols, SymbolGraph};
+use tools::file_format::crossref::CrossrefMap;
+use tools::file_format::identifiers::IdentMap;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("g", "gap",
+                "Maximum number of caller hops to search between adjacent frames (default 3)",
+                "HOPS");
+    opts.optopt("n", "node-limit",
+                "Maximum number of callers to examine for each pair of frames (default 1024)",
+                "COUNT");
+    opts.optmulti("", "exclude-sym",
+                  "Leave out symbols matching this regexp (repeatable)",
+                  "REGEXP");
+    opts.optmulti("", "exclude-pretty",
+                  "Leave out symbols whose pretty name matches this regexp (repeatable)",
+                  "REGEXP");
+    add_output_options(&mut opts, "the call graph connecting the frames");
+    opts
+}
+
+// Crash reports give frames as pretty names, sometimes with their
+// argument list ("nsFoo::Bar(int) const"). Only the qualified name is
+// in the identifiers file.
+fn frame_name(frame: &str) -> &str {
+    let frame = frame.trim();
+    let frame = frame.trim_right_matches(" const");
+    if !frame.ends_with(')') {
+        return frame;
+    }
+    let mut nesting = 0;
+    for (i, c) in frame.char_indices().rev() {
+        if c == ')' {
+            nesting += 1;
+        } else if c == '(' {
+            nesting -= 1;
+            if nesting == 0 {
+                return if i > 0 { frame[.. i].trim() } else { frame };
+            }
+        }
+    }
+    frame
+}
+
+// Frames come one per line, or as a crash signature with the frames
+// separated by " | ". Either way the innermost frame is first.
+fn read_frames(input: &str) -> Vec<String> {
+    input.lines()
+        .flat_map(|line| line.split(" | "))
+        .map(frame_name)
+        .filter(|frame| !frame.is_empty())
+        .map(|frame| frame.to_string())
+        .collect()
+}
+
+// Returns the path from the caller frame's symbol to a root in |graph|,
+// the callers of the callee frame. A caller that is itself one of the
+// roots is only taken when no other path turns up, giving a path of
+// one node for a recursive call: the walk never records self-edges.
+fn caller_path(graph: &SymbolGraph, caller_syms: &[String]) -> Option<Vec<usize>> {
+    graph.nodes.iter().position(|node| node.depth > 0 && caller_syms.contains(&node.sym))
+        .or_else(|| graph.nodes.iter().position(|node| caller_syms.contains(&node.sym)))
+        .map(|caller| graph.path_to_root(caller))
+}
+
+// Describes how the first node of |path| reaches its last.
+fn describe_path(graph: &SymbolGraph, path: &[usize]) -> String {
+    match path.len() {
+        1 => "recursive call".to_string(),
+        2 => "direct call".to_string(),
+        len => {
+            let via = path[1 .. len - 1].iter().map(|&n| {
+                let node = &graph.nodes[n];
+                if node.pretty.is_empty() { node.sym.clone() } else { node.pretty.clone() }
+            }).collect::<Vec<_>>();
+            format!("via {}", via.join(" -> "))
+        },
+    }
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 2 {
+        let brief = format!("Usage: {} [options] config.json tree_name < frames", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let gap = matches.opt_str("gap").map_or(3, |g| g.parse::<u32>().unwrap());
+    let node_limit = matches.opt_str("node-limit").map_or(tree_config.paths.graph_node_limit.unwrap_or(1024), |n| n.parse::<usize>().unwrap());
+
+    let mut input = String::new();
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        input.push_str(&line.unwrap());
+        input.push('\n');
+    }
+    let frames = read_frames(&input);
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+    let callers = load_callers(&tree_config.paths.index_path);
+    let ids = IdentMap::new(&format!("{}/identifiers", tree_config.paths.index_path));
+
+    let mut resolved = vec![];
+    for (i, frame) in frames.iter().enumerate() {
+        println!("#{} {}", i, frame);
+        let syms = resolve_name(frame, &crossref, &ids);
+        if syms.is_empty() {
+            println!("    (not found in the index)");
+        }
+        for sym in &syms {
+            let location = match crossref.lookup(sym).as_ref().and_then(|entry| entry.first_def().map(|(path, lineno)| format!("{}:{}", path, lineno))) {
+                Some(location) => location,
+                None => "(no definition)".to_string(),
+            };
+            println!("    {}\t{}", sym, location);
+        }
+        resolved.push(syms);
+    }
+
+    // Each frame was called by the one after it. Look for the shortest
+    // chain of static callers between them; a gap usually means an
+    // inlined or indirect call.
+    let boring = match BoringSymbols::from_options(tree_config, &matches) {
+        Ok(boring) => boring,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(2);
+        },
+    };
+    let mut fragment = SymbolGraph::new();
+    println!("");
+    for i in 0 .. frames.len().saturating_sub(1) {
+        let (callee_syms, caller_syms) = (&resolved[i], &resolved[i + 1]);
+        if callee_syms.is_empty() || caller_syms.is_empty() {
+            continue;
+        }
+
+        let roots = callee_syms.iter().map(|sym| (sym.clone(), frames[i].clone())).collect::<Vec<_>>();
+        let graph = collect_callers(&crossref, callers.as_ref(), &roots, &boring, gap, node_limit);
+        let path = match caller_path(&graph, caller_syms) {
+            Some(path) => path,
+            None => {
+                let note = if graph.truncated { format!(" (node limit of {} reached)", node_limit) } else { "".to_string() };
+                println!("#{} -> #{}: no static call path within {} hops{}", i + 1, i, gap, note);
+                continue;
+            }
+        };
+
+        println!("#{} -> #{}: {}", i + 1, i, describe_path(&graph, &path));
+
+        let hops = path.len() - 1;
+        if hops == 0 {
+            let node = &graph.nodes[path[0]];
+            let index = fragment.add_node(&node.sym, &frames[i], i as u32);
+            fragment.add_edge(index, index);
+            continue;
+        }
+        let mut prev = None;
+        for (j, &n) in path.iter().enumerate() {
+            let node = &graph.nodes[n];
+            let pretty = if j == 0 { &frames[i + 1] } else if j == hops { &frames[i] } else { &node.pretty };
+            let index = fragment.add_node(&node.sym, pretty, i as u32);
+            if let Some(prev) = prev {
+                fragment.add_edge(prev, index);
+            }
+            prev = Some(index);
+        }
+    }
+
+    let title = format!("Crash stack {}", frames.first().map_or("", |f| f.as_str()));
+    if let Err(e) = write_outputs(&matches, fragment, tree_config, &crossref, "uses", &title) {
+        println!("{}", e);
+        process::exit(2);
+    }
+}
+
+#[test]
+fn test_read_frames() {
+    assert_eq!(read_frames("mozilla::dom::Foo::Bar(int) const | nsThread::ProcessNextEvent\n"),
+               vec!["mozilla::dom::Foo::Bar".to_string(), "nsThread::ProcessNextEvent".to_string()]);
+    assert_eq!(read_frames("leaf\n\n  mid  \n"), vec!["leaf".to_string(), "mid".to_string()]);
+    assert_eq!(frame_name("(anonymous namespace)::Run(int)"), "(anonymous namespace)::Run");
+    assert_eq!(frame_name("Dispatch(std::function<void (int)>)"), "Dispatch");
+}
+
+#[test]
+fn test_caller_path() {
+    // Two identical frames: the caller's symbol is the root itself.
+    let mut graph = SymbolGraph::new();
+    let a = graph.add_node("_Z1av", "a", 0);
+    let syms = vec!["_Z1av".to_string()];
+    let path = caller_path(&graph, &syms).unwrap();
+    assert_eq!(path, vec![a]);
+    assert_eq!(describe_path(&graph, &path), "recursive call");
+
+    // A path through other callers wins over recursion.
+    let b = graph.add_node("_Z1bv", "b", 1);
+    let c = graph.add_node("_Z1cv", "", 2);
+    graph.add_edge(b, a);
+    graph.add_edge(c, b);
+    let syms = vec!["_Z1av".to_string(), "_Z1cv".to_string()];
+    let path = caller_path(&graph, &syms).unwrap();
+    assert_eq!(path, vec![c, b, a]);
+    assert_eq!(describe_path(&graph, &path), "via b");
+    assert_eq!(describe_path(&graph, &[b, a]), "direct call");
+    assert_eq!(caller_path(&graph, &["_Z1dv".to_string()]), None);
+}
@@ -13,6 +13,7 @@ use std::rc::Rc;
 extern crate tools;
 use tools::find_source_file;
 use tools::file_format::analysis::{read_analysis, read_target, AnalysisKind};
+use tools::file_format::completions::{symbol_kind, CompletionsBuilder};
 use tools::config;
 
 extern crate rustc_serialize;
@@ -64,6 +65,53 @@ fn split_scopes(id: &str) -> Vec<String> {
     return result;
 }
 
+// Returns the first sentence of the comment block just above line
+// |lineno| (1-based), which is usually what a function claims to do.
+// Only C-style comments are recognized.
+fn doc_summary(lines: &[String], lineno: usize) -> Option<String> {
+    let mut text = vec![];
+    let mut in_block = false;
+    let mut i = lineno - 1;
+    while i > 0 {
+        let line = lines[i - 1].trim();
+        if !in_block && line.ends_with("*/") {
+            in_block = true;
+        } else if !in_block && !line.starts_with("//") {
+            break;
+        }
+        if in_block && line.starts_with("/*") {
+            in_block = false;
+        }
+        let line = line.trim_left_matches('/').trim_left_matches('*').trim_left_matches('!');
+        let line = line.trim_right_matches('/').trim_right_matches('*');
+        text.push(line.trim().to_string());
+        i -= 1;
+    }
+    if in_block {
+        return None;
+    }
+    text.reverse();
+    let text = text.into_iter().filter(|t| !t.is_empty()).collect::<Vec<_>>().join(" ");
+
+    let summary = match text.find(". ") {
+        Some(end) => &text[.. end + 1],
+        None => &text,
+    };
+    if summary.is_empty() {
+        return None;
+    }
+    let mut summary = summary.to_string();
+    if summary.len() > 200 {
+        let mut end = 200;
+        while !summary.is_char_boundary(end) {
+            end -= 1;
+        }
+        summary.truncate(end);
+        summary.push_str("...");
+    }
+    Some(summary)
+}
+
 struct StringIntern {
     set: HashMap<Rc<String>, ()>
 }
@@ -101,13 +149,15 @@ fn main() {
     let output_file = format!("{}/crossref", tree_config.paths.index_path);
     let jump_file = format!("{}/jumps", tree_config.paths.index_path);
     let id_file = format!("{}/identifiers", tree_config.paths.index_path);
+    let doc_file = format!("{}/doc-summaries", tree_config.paths.index_path);
+    let callers_file = format!("{}/callers", tree_config.paths.index_path);
 
     let mut strings = StringIntern::new();
-    let empty_string = strings.add("".to_string());
 
     let mut table = HashMap::new();
     let mut pretty_table = HashMap::new();
     let mut id_table = HashMap::new();
+    let mut doc_table = HashMap::new();
     let mut jumps = Vec::new();
 
     for path in &file_paths {
@@ -125,30 +175,30 @@ fn main() {
             },
         };
         let reader = BufReader::new(&source_file);
-        let lines: Vec<_> = reader.lines().map(
-            |l| match l {
-                Ok(line) => {
-                    let line_cut = line.trim_right();
-                    let len = line_cut.len();
-                    let line_cut = line_cut.trim_left();
-                    let offset = (len - line_cut.len()) as u32;
-                    let mut buf = String::new();
-                    let mut i = 0;
-                    for c in line_cut.chars() {
-                        buf.push(c);
-                        i += 1;
-                        if i > 100 {
-                            break;
-                        }
-                    };
-                    (strings.add(buf), offset)
-                },
-                Err(_) => (Rc::clone(&empty_string), 0)
+        let source_lines: Vec<_> = reader.lines().map(|l| l.unwrap_or("".to_string())).collect();
+        let lines: Vec<_> = source_lines.iter().map(
+            |line| {
+                let line_cut = line.trim_right();
+                let len = line_cut.len();
+                let line_cut = line_cut.trim_left();
+                let offset = (len - line_cut.len()) as u32;
+                let mut buf = String::new();
+                let mut i = 0;
+                for c in line_cut.chars() {
+                    buf.push(c);
+                    i += 1;
+                    if i > 100 {
+                        break;
+                    }
+                };
+                (strings.add(buf), offset)
             }).collect();
 
 
         for datum in analysis {
             for piece in datum.data {
+                let is_def = piece.kind == AnalysisKind::Def;
+                let is_decl = piece.kind == AnalysisKind::Decl;
                 let sym = strings.add(piece.sym.to_owned());
                 let t1 = table.entry(Rc::clone(&sym)).or_insert(BTreeMap::new());
                 let t2 = t1.entry(piece.kind).or_insert(BTreeMap::new());
@@ -168,6 +218,20 @@ fn main() {
                     contextsym: strings.add(piece.contextsym),
                 });
 
+                // Doc comments usually sit on the declaration in the
+                // header, but one on the definition wins.
+                if is_def || is_decl {
+                    let known = match doc_table.get(&sym) {
+                        Some(&(was_def, _)) => was_def || !is_def,
+                        None => false,
+                    };
+                    if !known {
+                        if let Some(summary) = doc_summary(&source_lines, datum.loc.lineno as usize) {
+                            doc_table.insert(Rc::clone(&sym), (is_def, summary));
+                        }
+                    }
+                }
+
                 let pretty = strings.add(piece.pretty.to_owned());
                 pretty_table.insert(Rc::clone(&sym), Rc::clone(&pretty));
 
@@ -181,8 +245,13 @@ fn main() {
     }
 
     let mut outputf = File::create(output_file).unwrap();
+    let mut callersf = File::create(callers_file).unwrap();
+    let mut use_counts = HashMap::new();
 
     for (id, id_data) in table {
+        let uses = id_data.get(&AnalysisKind::Use).map_or(0, |paths| paths.values().map(|r| r.len()).sum::<usize>());
+        use_counts.insert(Rc::clone(&id), uses);
+
         let mut kindmap = BTreeMap::new();
         for (kind, kind_data) in &id_data {
             let mut result = Vec::new();
@@ -205,6 +274,23 @@ fn main() {
 
         let _ = outputf.write_all(format!("{}\n{}\n", id, kindmap.to_string()).as_bytes());
 
+        // The callers file has the distinct functions containing the
+        // uses, named by the first symbol of their contextsym (see
+        // find_callers in callgraph.rs).
+        let mut callers = BTreeMap::new();
+        for results in id_data.get(&AnalysisKind::Use).into_iter().flat_map(|paths| paths.values()) {
+            for result in results {
+                let caller = result.contextsym.split(',').next().unwrap();
+                if !caller.is_empty() && caller != id.as_str() {
+                    callers.insert(caller.to_string(), Rc::clone(&result.context));
+                }
+            }
+        }
+        if !callers.is_empty() {
+            let callers = callers.into_iter().map(|(sym, pretty)| vec![sym, pretty.to_string()]).collect::<Vec<_>>();
+            let _ = callersf.write_all(format!("{}\n{}\n", id, callers.to_json()).as_bytes());
+        }
+
         if id_data.contains_key(&AnalysisKind::Def) {
             let defs = id_data.get(&AnalysisKind::Def).unwrap();
             if defs.len() == 1 {
@@ -228,7 +314,32 @@ fn main() {
         let _ = jumpf.write_all((jump.to_string() + "\n").as_bytes());
     }
 
+    let mut docf = File::create(doc_file).unwrap();
+    for (sym, (_, summary)) in doc_table {
+        let entry = vec![sym.to_json(), summary.to_json()];
+        let _ = docf.write_all((Json::Array(entry).to_string() + "\n").as_bytes());
+    }
+
     let mut idf = File::create(id_file).unwrap();
+    // Search-as-you-type offers each qualified name under every suffix
+    // the identifiers file has for it, ranked by its total number of
+    // uses.
+    let mut completions = CompletionsBuilder::new();
+    for (id, syms) in &id_table {
+        let uses = syms.iter().map(|sym| use_counts.get(sym).cloned().unwrap_or(0)).sum();
+        let kind = symbol_kind(syms.iter().next().unwrap());
+        let components = split_scopes(id);
+        for i in 0..components.len() {
+            let sub = components[i..components.len()].join("::");
+            if !sub.is_empty() {
+                completions.add(&sub, id, kind, uses);
+            }
+        }
+    }
+    completions.add_paths(&format!("{}/repo-files", tree_config.paths.index_path));
+    completions.add_paths(&format!("{}/objdir-files", tree_config.paths.index_path));
+    completions.write(&tree_config.paths.index_path);
+
     for (id, syms) in id_table {
         for sym in syms {
             let components = split_scopes(&id);
@@ -244,3 +355,14 @@ fn main() {
         }
     }
 }
+
+#[test]
+fn test_doc_summary() {
+    let lines = ["int x;", "", "/**", " * Frobs the widget. Never fails.", " */", "void Frob();",
+                 "// Counts things", "// in bulk", "int Count();", "int y;"]
+        .iter().map(|l| l.to_string()).collect::<Vec<_>>();
+    assert_eq!(doc_summary(&lines, 6), Some("Frobs the widget.".to_string()));
+    assert_eq!(doc_summary(&lines, 9), Some("Counts things in bulk".to_string()));
+    assert_eq!(doc_summary(&lines, 10), None);
+    assert_eq!(doc_summary(&lines, 1), None);
+}
@@ -0,0 +1,130 @@
+use std::env;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::collections::BTreeMap;
+
+extern crate tools;
+extern crate getopts;
+
+use getopts::Options;
+
+use tools::config;
+use tools::file_format::crossref::CrossrefMap;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("p", "prefix",
+                "Only consider files under this directory",
+                "DIR");
+    opts.optopt("l", "levels",
+                "Number of directory levels below the prefix to group by (default 1)",
+                "N");
+    opts.optopt("o", "output",
+                "File to write the diagram to, in dot format (default stdout)",
+                "FILE");
+    opts
+}
+
+// Returns the component a file belongs to: its directory, cut off
+// |levels| directories below |prefix|. Files outside |prefix| are not
+// part of any component.
+fn component(path: &str, prefix: &str, levels: usize) -> Option<String> {
+    let prefix = prefix.trim_right_matches('/');
+    let rest = if prefix.is_empty() {
+        path
+    } else if path.starts_with(prefix) && path[prefix.len() ..].starts_with('/') {
+        &path[prefix.len() + 1 ..]
+    } else {
+        return None;
+    };
+
+    let mut dirs = rest.split('/').collect::<Vec<_>>();
+    dirs.pop();
+    dirs.truncate(levels);
+
+    let mut result = prefix.to_string();
+    for dir in dirs {
+        if !result.is_empty() {
+            result.push('/');
+        }
+        result.push_str(dir);
+    }
+    if result.is_empty() {
+        result.push('/');
+    }
+    Some(result)
+}
+
+fn escape(s: &str) -> String {
+    s.replace("\\", "\\\\").replace("\"", "\\\"")
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 2 {
+        let brief = format!("Usage: {} [options] config.json tree_name", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let prefix = matches.opt_str("prefix").unwrap_or("".to_string());
+    let levels = matches.opt_str("levels").map_or(1, |l| l.parse::<usize>().unwrap());
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+
+    // Every use of a symbol from a component other than the one defining
+    // it counts as a dependency of the using component on the defining
+    // one.
+    let mut edges = BTreeMap::new();
+    for sym in crossref.symbols() {
+        let entry = match crossref.lookup(sym) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        let def_component = match entry.first_def().and_then(|(path, _)| component(path, &prefix, levels)) {
+            Some(c) => c,
+            None => continue,
+        };
+        for path in entry.get("Uses") {
+            let use_component = match component(&path.path, &prefix, levels) {
+                Some(c) => c,
+                None => continue,
+            };
+            if use_component != def_component {
+                *edges.entry((use_component, def_component.clone())).or_insert(0) += path.lines.len();
+            }
+        }
+    }
+
+    let mut writer: Box<Write> = match matches.opt_str("o") {
+        Some(fname) => Box::new(File::create(fname).unwrap()),
+        None => Box::new(io::stdout()),
+    };
+    write!(writer, "digraph G {{\n").unwrap();
+    write!(writer, "  node [shape=folder];\n").unwrap();
+    for (&(ref from, ref to), count) in &edges {
+        write!(writer, "  \"{}\" -> \"{}\" [label=\"{}\"];\n", escape(from), escape(to), count).unwrap();
+    }
+    write!(writer, "}}\n").unwrap();
+}
+
+#[test]
+fn test_component() {
+    assert_eq!(component("dom/base/nsDocument.cpp", "", 1), Some("dom".to_string()));
+    assert_eq!(component("dom/base/nsDocument.cpp", "dom", 1), Some("dom/base".to_string()));
+    assert_eq!(component("dom/base/nsDocument.cpp", "dom/", 2), Some("dom/base".to_string()));
+    assert_eq!(component("dom/Foo.cpp", "dom", 1), Some("dom".to_string()));
+    assert_eq!(component("domain/Foo.cpp", "dom", 1), None);
+    assert_eq!(component("README", "", 1), Some("/".to_string()));
+}
@@ -0,0 +1,166 @@
+use std::env;
+use std::process;
+
+extern crate tools;
+extern crate getopts;
+
+use getopts::Options;
+
+use tools::config;
+use tools::callgraph::{add_output_options, callee_map, collect_callees, follow_rename, resolve_name, write_outputs};
+use tools::callgraph::{BoringSymbols, SymbolGraph};
+use tools::file_format::crossref::CrossrefMap;
+use tools::file_format::identifiers::IdentMap;
+use tools::file_format::renames::read_renames;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("d", "max-depth",
+                "Maximum number of callee hops to follow (default 8)",
+                "DEPTH");
+    opts.optopt("n", "node-limit",
+                "Maximum number of symbols to add to the graph (default 1024)",
+                "COUNT");
+    opts.optmulti("t", "target",
+                  "A symbol or pretty name to list the functions every path to it passes through (repeatable)",
+                  "NAME");
+    add_output_options(&mut opts, "the dominator tree");
+    opts.optmulti("", "exclude-sym",
+                  "Leave out symbols matching this regexp (repeatable)",
+                  "REGEXP");
+    opts.optmulti("", "exclude-pretty",
+                  "Leave out symbols whose pretty name matches this regexp (repeatable)",
+                  "REGEXP");
+    opts
+}
+
+// Returns the dominators of |node| from the entry down to its immediate
+// dominator: the functions every path from the entry to |node| goes
+// through, in the order they are reached.
+fn dominator_chain(idom: &[Option<usize>], node: usize) -> Vec<usize> {
+    let mut chain = Vec::new();
+    let mut cur = idom[node];
+    while let Some(dom) = cur {
+        chain.push(dom);
+        cur = idom[dom];
+    }
+    chain.reverse();
+    chain
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 3 {
+        let brief = format!("Usage: {} [options] config.json tree_name entry-symbol", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let max_depth = matches.opt_str("max-depth").map_or(tree_config.paths.graph_max_depth.unwrap_or(8), |d| d.parse::<u32>().unwrap());
+    let node_limit = matches.opt_str("node-limit").map_or(tree_config.paths.graph_node_limit.unwrap_or(1024), |n| n.parse::<usize>().unwrap());
+    let mut entry_sym = matches.free[2].to_string();
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+    if let Some((sym, note)) = follow_rename(&entry_sym, &crossref, &read_renames(&tree_config.paths.index_path)) {
+        println!("{}", note);
+        entry_sym = sym;
+    }
+    let callees = callee_map(&crossref);
+    let boring = match BoringSymbols::from_options(tree_config, &matches) {
+        Ok(boring) => boring,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(2);
+        },
+    };
+    let graph = collect_callees(&callees, (&entry_sym[..], ""), &boring, max_depth, node_limit);
+    let idom = graph.dominators(0);
+
+    // A function dominates everything below it in the dominator tree,
+    // so count the size of each subtree.
+    let mut dominated = vec![0; graph.nodes.len()];
+    for i in 0 .. graph.nodes.len() {
+        let mut cur = idom[i];
+        while let Some(node) = cur {
+            dominated[node] += 1;
+            cur = idom[node];
+        }
+    }
+
+    let mut gateways = (1 .. graph.nodes.len()).filter(|&i| dominated[i] > 0).collect::<Vec<_>>();
+    gateways.sort_by(|&a, &b| dominated[b].cmp(&dominated[a]));
+
+    println!("Gateways from {} (every path from the entry reaches the given number of functions through them):",
+             entry_sym);
+    for i in gateways {
+        let node = &graph.nodes[i];
+        let location = match crossref.lookup(&node.sym).as_ref().and_then(|e| e.first_def().map(|(p, l)| format!("{}:{}", p, l))) {
+            Some(location) => location,
+            None => "(no definition)".to_string(),
+        };
+        let name = if node.pretty.is_empty() { &node.sym } else { &node.pretty };
+        println!("  {}\t{}\t{}", name, dominated[i], location);
+    }
+
+    let targets = matches.opt_strs("target");
+    if !targets.is_empty() {
+        let ids = IdentMap::new(&format!("{}/identifiers", tree_config.paths.index_path));
+        let name = |i: usize| if graph.nodes[i].pretty.is_empty() { &graph.nodes[i].sym } else { &graph.nodes[i].pretty };
+        for target in &targets {
+            let nodes = resolve_name(target, &crossref, &ids).iter().filter_map(|sym| graph.find(sym)).collect::<Vec<_>>();
+            println!("");
+            if nodes.is_empty() {
+                println!("{} is not reachable from {} within {} hops.", target, entry_sym, max_depth);
+                continue;
+            }
+            for node in nodes {
+                println!("Every path from {} to {} passes through:", entry_sym, name(node));
+                for dom in dominator_chain(&idom, node) {
+                    println!("  {}", name(dom));
+                }
+            }
+        }
+    }
+
+    if graph.truncated {
+        println!("");
+        println!("Node limit of {} reached; the results only cover part of the call graph.", node_limit);
+        println!("{}", graph.limit_hint(node_limit).unwrap());
+    }
+    if let Some(note) = graph.filter_note() {
+        println!("");
+        println!("{}", note);
+    }
+
+    let mut tree = SymbolGraph::new();
+    for node in &graph.nodes {
+        tree.add_node(&node.sym, &node.pretty, node.depth);
+    }
+    for (i, parent) in idom.iter().enumerate() {
+        if let Some(parent) = *parent {
+            tree.add_edge(parent, i);
+        }
+    }
+    let title = format!("Dominator tree of {}", entry_sym);
+    if let Err(e) = write_outputs(&matches, tree, tree_config, &crossref, "dominates", &title) {
+        println!("{}", e);
+        process::exit(2);
+    }
+}
+
+#[test]
+fn test_dominator_chain() {
+    let idom = vec![None, Some(0), Some(1), Some(1), Some(0)];
+    assert_eq!(dominator_chain(&idom, 3), vec![0, 1]);
+    assert_eq!(dominator_chain(&idom, 4), vec![0]);
+    assert!(dominator_chain(&idom, 0).is_empty());
+}
@@ -0,0 +1,250 @@
+use std::env;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::hash::{Hash, Hasher};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+
+extern crate tools;
+extern crate getopts;
+
+use getopts::Options;
+
+use tools::config;
+use tools::contexts::body_range;
+use tools::find_source_file;
+use tools::languages;
+use tools::languages::FormatAs;
+use tools::tokenize;
+use tools::tokenize::TokenKind;
+use tools::file_format::analysis::{read_analysis, read_target, AnalysisKind};
+
+// Number of tokens in each shingle.
+const SHINGLE_SIZE: usize = 4;
+
+// The MinHash signature is split into bands; two functions become a
+// candidate pair when any band matches exactly.
+const BANDS: usize = 6;
+const ROWS: usize = 4;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("t", "threshold",
+                "Minimum similarity (0 to 1) for two functions to be reported (default 0.8)",
+                "SIMILARITY");
+    opts.optopt("m", "min-tokens",
+                "Ignore functions with fewer tokens than this (default 50)",
+                "COUNT");
+    opts
+}
+
+struct Function {
+    pretty: String,
+    location: String,
+    shingles: Vec<u64>,
+}
+
+fn hash<T: Hash>(value: T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Returns the texts of the tokens in the body of the function whose
+// definition starts at byte offset |start|, not counting comments.
+fn function_body<'a>(input: &'a str, tokens: &[tokenize::Token], start: usize) -> Option<Vec<&'a str>> {
+    let (open, close) = match body_range(input, tokens, start) {
+        Some(range) => range,
+        None => return None,
+    };
+
+    let mut body = vec![];
+    for token in &tokens[open .. close + 1] {
+        match token.kind {
+            TokenKind::Comment | TokenKind::Newline => continue,
+            // Literals usually differ between copies of the same code.
+            TokenKind::StringLiteral => body.push("\"\""),
+            _ => body.push(&input[token.start .. token.end]),
+        }
+    }
+    Some(body)
+}
+
+fn shingles(body: &[&str]) -> Vec<u64> {
+    let set = body.windows(SHINGLE_SIZE).map(|w| hash(w)).collect::<BTreeSet<_>>();
+    set.into_iter().collect()
+}
+
+fn similarity(a: &[u64], b: &[u64]) -> f64 {
+    let (mut i, mut j, mut common) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            common += 1;
+            i += 1;
+            j += 1;
+        } else if a[i] < b[j] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    common as f64 / (a.len() + b.len() - common) as f64
+}
+
+fn signature(shingles: &[u64]) -> Vec<u64> {
+    (0 .. BANDS * ROWS).map(|seed| {
+        shingles.iter().map(|&s| hash((seed, s))).min().unwrap()
+    }).collect()
+}
+
+fn find(parents: &mut Vec<usize>, i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[i] = root;
+    root
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 3 {
+        let brief = format!("Usage: {} [options] config.json tree_name filenames-file", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let threshold = matches.opt_str("threshold").map_or(0.8, |t| t.parse::<f64>().unwrap());
+    let min_tokens = matches.opt_str("min-tokens").map_or(50, |m| m.parse::<usize>().unwrap());
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+
+    let file_paths: Vec<String> = BufReader::new(File::open(&matches.free[2]).unwrap())
+        .lines().map(|x| x.unwrap()).collect();
+
+    let mut functions = vec![];
+    for path in &file_paths {
+        let spec = match languages::select_formatting(path) {
+            FormatAs::FormatCLike(spec) => spec,
+            _ => continue,
+        };
+
+        let source_fname = find_source_file(path, &tree_config.paths.files_path, &tree_config.paths.objdir_path);
+        let mut input = String::new();
+        match File::open(source_fname) {
+            Ok(mut f) => if f.read_to_string(&mut input).is_err() { continue },
+            Err(_) => continue,
+        }
+        let tokens = tokenize::tokenize_c_like(&input, spec);
+
+        let mut line_starts = vec![0];
+        for (i, c) in input.bytes().enumerate() {
+            if c == '\n' as u8 {
+                line_starts.push(i + 1);
+            }
+        }
+
+        let analysis_fname = format!("{}/analysis/{}", tree_config.paths.index_path, path);
+        let mut seen = BTreeSet::new();
+        for datum in read_analysis(&analysis_fname, &read_target) {
+            let line_start = match line_starts.get(datum.loc.lineno as usize - 1) {
+                Some(&offset) => offset,
+                None => continue,
+            };
+            let start = line_start + datum.loc.col_start as usize;
+            for piece in datum.data {
+                if piece.kind != AnalysisKind::Def || !seen.insert(datum.loc.lineno) {
+                    continue;
+                }
+                let body = match function_body(&input, &tokens, start) {
+                    Some(body) => body,
+                    None => continue,
+                };
+                if body.len() < min_tokens || body.len() < SHINGLE_SIZE {
+                    continue;
+                }
+                functions.push(Function {
+                    pretty: piece.pretty,
+                    location: format!("{}:{}", path, datum.loc.lineno),
+                    shingles: shingles(&body),
+                });
+            }
+        }
+    }
+
+    let mut buckets = HashMap::new();
+    for (i, function) in functions.iter().enumerate() {
+        let sig = signature(&function.shingles);
+        for (band, rows) in sig.chunks(ROWS).enumerate() {
+            buckets.entry((band, hash(rows))).or_insert(Vec::new()).push(i);
+        }
+    }
+
+    let mut pairs = BTreeMap::new();
+    for (_, members) in buckets {
+        for (n, &i) in members.iter().enumerate() {
+            for &j in &members[n + 1 ..] {
+                if pairs.contains_key(&(i, j)) {
+                    continue;
+                }
+                pairs.insert((i, j), similarity(&functions[i].shingles, &functions[j].shingles));
+            }
+        }
+    }
+
+    let mut parents = (0 .. functions.len()).collect::<Vec<_>>();
+    let mut best = vec![0.0; functions.len()];
+    for (&(i, j), &score) in &pairs {
+        if score < threshold {
+            continue;
+        }
+        let (ri, rj) = (find(&mut parents, i), find(&mut parents, j));
+        parents[ri] = rj;
+        for &k in &[i, j] {
+            if score > best[k] {
+                best[k] = score;
+            }
+        }
+    }
+
+    let mut clusters = BTreeMap::new();
+    for i in 0 .. functions.len() {
+        let root = find(&mut parents, i);
+        clusters.entry(root).or_insert(Vec::new()).push(i);
+    }
+    let mut clusters = clusters.into_iter().map(|(_, m)| m).filter(|m| m.len() > 1).collect::<Vec<_>>();
+    clusters.sort_by(|a, b| b.len().cmp(&a.len()));
+
+    for (n, members) in clusters.iter().enumerate() {
+        println!("Cluster {} ({} functions)", n + 1, members.len());
+        for &i in members {
+            let f = &functions[i];
+            println!("  {:.2}\t{}\t{}", best[i], f.pretty, f.location);
+        }
+    }
+}
+
+#[test]
+fn test_function_body() {
+    let input = "void f() { // hi\n  g(\"x\"); }\nint x;";
+    let spec = match languages::select_formatting("a.cpp") {
+        FormatAs::FormatCLike(spec) => spec,
+        _ => panic!("Expected C-like formatting"),
+    };
+    let tokens = tokenize::tokenize_c_like(input, spec);
+    let body = function_body(input, &tokens, 0).unwrap();
+    assert_eq!(body, vec!["{", "g", "(", "\"\"", ")", ";", "}"]);
+    assert!(function_body(input, &tokens, input.find("int").unwrap()).is_none());
+}
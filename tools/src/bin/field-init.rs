@@ -0,0 +1,341 @@
+use std::env;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+extern crate tools;
+extern crate getopts;
+extern crate rustc_serialize;
+
+use getopts::Options;
+use rustc_serialize::json::{Json, ToJson};
+
+use tools::config;
+use tools::find_source_file;
+use tools::file_format::crossref::{CrossrefLine, CrossrefMap};
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optflag("j", "json",
+                 "Print the sites as JSON");
+    opts
+}
+
+// The ways a field or global gets a value, in the order they happen
+// over an object's lifetime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum WriteKind {
+    // An initializer at the declaration: a static initializer for a
+    // global or a default member initializer for a field.
+    Initializer,
+    InitList,
+    Constructor,
+    Mutation,
+}
+
+impl WriteKind {
+    fn name(&self) -> &'static str {
+        match *self {
+            WriteKind::Initializer => "initializer",
+            WriteKind::InitList => "init-list",
+            WriteKind::Constructor => "constructor",
+            WriteKind::Mutation => "mutation",
+        }
+    }
+}
+
+fn strip_template_args(name: &str) -> String {
+    let mut result = String::new();
+    let mut nesting = 0;
+    for c in name.chars() {
+        match c {
+            '<' => nesting += 1,
+            '>' if nesting > 0 => nesting -= 1,
+            _ if nesting == 0 => result.push(c),
+            _ => {},
+        }
+    }
+    result
+}
+
+// Constructors are the functions named after their class
+// (mozilla::Foo::Foo), or "constructor" in JS.
+fn is_constructor(context: &str) -> bool {
+    let name = strip_template_args(context);
+    if name.ends_with(".constructor") {
+        return true;
+    }
+    let parts = name.split("::").collect::<Vec<_>>();
+    parts.len() >= 2 && parts[parts.len() - 1] == parts[parts.len() - 2]
+}
+
+fn is_assignment(after: &str) -> bool {
+    if after.starts_with("==") {
+        return false;
+    }
+    after.starts_with('=') ||
+        ["+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<=", ">>="].iter().any(|op| after.starts_with(op))
+}
+
+// Decides from the text around a use of the field at |bounds| in |line|
+// whether the use writes it, and how. Returns None for reads.
+fn classify_use(line: &str, bounds: (usize, usize), in_constructor: bool) -> Option<WriteKind> {
+    let (start, end) = bounds;
+    if end > line.len() || !line.is_char_boundary(start) || !line.is_char_boundary(end) {
+        return None;
+    }
+    let before = line[.. start].trim_right();
+    let after = line[end ..].trim_left();
+
+    let list_start = before.is_empty() || before.ends_with(',') || (before.ends_with(':') && !before.ends_with("::"));
+    if in_constructor && list_start && (after.starts_with('(') || after.starts_with('{')) {
+        return Some(WriteKind::InitList);
+    }
+    if is_assignment(after) || after.starts_with("++") || after.starts_with("--") ||
+        before.ends_with("++") || before.ends_with("--") {
+        return Some(if in_constructor { WriteKind::Constructor } else { WriteKind::Mutation });
+    }
+    None
+}
+
+// Returns whether a definition line gives the symbol a value.
+fn has_initializer(line: &str, bounds: (usize, usize)) -> bool {
+    let (_, end) = bounds;
+    if end > line.len() || !line.is_char_boundary(end) {
+        return false;
+    }
+    let after = line[end ..].trim_left();
+    (after.starts_with('=') && !after.starts_with("==")) || after.starts_with('{') || after.starts_with('(')
+}
+
+fn is_guard(text: &str) -> bool {
+    let text = text.trim_left_matches('}').trim();
+    ["if ", "if(", "else", "while ", "while(", "for ", "for(", "switch ", "switch(", "case ", "default:"]
+        .iter().any(|k| text.starts_with(k))
+}
+
+fn clean_guard(text: &str) -> String {
+    text.trim().trim_left_matches('}').trim().trim_right_matches('{').trim().to_string()
+}
+
+fn strip_line_comment(line: &str) -> &str {
+    match line.find("//") {
+        Some(pos) => &line[.. pos],
+        None => line,
+    }
+}
+
+// Finds the conditions guarding line |lineno| (1-based) of |source|,
+// outermost first, by walking back over the enclosing braces until the
+// function itself is reached. Unbraced guards on the same or the
+// previous line are also found. This is a textual guess, so
+// conditions split over several lines are only found when their
+// parentheses balance within a few lines.
+fn enclosing_conditions(source: &[String], lineno: usize) -> Vec<String> {
+    let mut conditions = Vec::new();
+    if lineno == 0 || lineno > source.len() {
+        return conditions;
+    }
+    let index = lineno - 1;
+
+    let text = source[index].trim();
+    if is_guard(text) {
+        conditions.push(clean_guard(text));
+    }
+    if index > 0 {
+        let prev = strip_line_comment(&source[index - 1]).trim();
+        if is_guard(prev) && !prev.ends_with('{') && !prev.ends_with(';') {
+            conditions.push(clean_guard(prev));
+        }
+    }
+
+    let mut depth = 0;
+    let first = if index > 500 { index - 500 } else { 0 };
+    'lines: for i in (first .. index).rev() {
+        let line = strip_line_comment(&source[i]);
+        for (pos, c) in line.char_indices().rev() {
+            match c {
+                '}' => depth += 1,
+                '{' if depth > 0 => depth -= 1,
+                '{' => {
+                    // An unmatched brace opens a block containing the line.
+                    let mut header = line[.. pos].trim().to_string();
+                    let mut j = i;
+                    if header.is_empty() && j > 0 {
+                        j -= 1;
+                        header = strip_line_comment(&source[j]).trim().to_string();
+                    }
+                    while header.matches(')').count() > header.matches('(').count() && j > 0 && i - j < 4 {
+                        j -= 1;
+                        header = format!("{} {}", strip_line_comment(&source[j]).trim(), header);
+                    }
+                    if !is_guard(&header) {
+                        break 'lines;
+                    }
+                    conditions.push(clean_guard(&header));
+                },
+                _ => {},
+            }
+        }
+    }
+
+    conditions.reverse();
+    conditions
+}
+
+struct Site {
+    kind: WriteKind,
+    path: String,
+    lineno: u64,
+    function: String,
+    conditions: Vec<String>,
+    line: String,
+}
+
+impl ToJson for Site {
+    fn to_json(&self) -> Json {
+        let mut obj = BTreeMap::new();
+        obj.insert("kind".to_string(), self.kind.name().to_json());
+        obj.insert("path".to_string(), self.path.to_json());
+        obj.insert("lno".to_string(), self.lineno.to_json());
+        obj.insert("function".to_string(), self.function.to_json());
+        obj.insert("conditions".to_string(), self.conditions.to_json());
+        obj.insert("line".to_string(), self.line.to_json());
+        Json::Object(obj)
+    }
+}
+
+fn read_source(path: &str, tree_config: &config::TreeConfig) -> Vec<String> {
+    let fname = find_source_file(path, &tree_config.paths.files_path, &tree_config.paths.objdir_path);
+    match File::open(fname) {
+        Ok(file) => BufReader::new(file).lines().map(|l| l.unwrap_or("".to_string())).collect(),
+        Err(_) => vec![],
+    }
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 3 {
+        let brief = format!("Usage: {} [options] config.json tree_name symbol", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let sym = &matches.free[2];
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+    let entry = match crossref.lookup(sym) {
+        Some(entry) => entry,
+        None => {
+            println!("Symbol {} not found.", sym);
+            return;
+        },
+    };
+
+    let mut sites = Vec::new();
+    {
+        let mut add = |kind: WriteKind, path: &str, line: &CrossrefLine| {
+            sites.push(Site {
+                kind: kind,
+                path: path.to_string(),
+                lineno: line.lineno,
+                function: line.context.clone(),
+                conditions: vec![],
+                line: line.line.clone(),
+            });
+        };
+
+        for path in entry.get("Definitions") {
+            for line in &path.lines {
+                if line.bounds.map_or(false, |b| has_initializer(&line.line, b)) {
+                    add(WriteKind::Initializer, &path.path, line);
+                }
+            }
+        }
+        // The JS indexer records assignments separately. C++ assignments are
+        // uses, which are told apart by the text around them.
+        for path in entry.get("Assignments") {
+            for line in &path.lines {
+                let kind = if is_constructor(&line.context) { WriteKind::Constructor } else { WriteKind::Mutation };
+                add(kind, &path.path, line);
+            }
+        }
+        for path in entry.get("Uses") {
+            for line in &path.lines {
+                let kind = line.bounds.and_then(|b| classify_use(&line.line, b, is_constructor(&line.context)));
+                if let Some(kind) = kind {
+                    add(kind, &path.path, line);
+                }
+            }
+        }
+    }
+
+    let mut sources = HashMap::new();
+    for site in &mut sites {
+        if site.kind == WriteKind::Initializer || site.kind == WriteKind::InitList {
+            continue;
+        }
+        let source = sources.entry(site.path.clone()).or_insert_with(|| read_source(&site.path, tree_config));
+        site.conditions = enclosing_conditions(source, site.lineno as usize);
+    }
+
+    sites.sort_by(|a, b| (a.kind, &a.path, a.lineno).cmp(&(b.kind, &b.path, b.lineno)));
+
+    if matches.opt_present("json") {
+        println!("{}", sites.to_json());
+        return;
+    }
+
+    if sites.is_empty() {
+        println!("No writes to {} found.", sym);
+        return;
+    }
+    println!("kind\tlocation\tfunction\tconditions\tline");
+    for site in &sites {
+        println!("{}\t{}:{}\t{}\t{}\t{}", site.kind.name(), site.path, site.lineno,
+                 if site.function.is_empty() { "-" } else { &site.function },
+                 if site.conditions.is_empty() { "-".to_string() } else { site.conditions.join(" / ") },
+                 site.line);
+    }
+}
+
+#[test]
+fn test_is_constructor() {
+    assert!(is_constructor("mozilla::dom::Element::Element"));
+    assert!(is_constructor("nsTArray<int>::nsTArray"));
+    assert!(is_constructor("Foo.constructor"));
+    assert!(!is_constructor("mozilla::dom::Element::SetAttr"));
+    assert!(!is_constructor(""));
+}
+
+#[test]
+fn test_classify_use() {
+    assert_eq!(classify_use(": mFoo(0)", (2, 6), true), Some(WriteKind::InitList));
+    assert_eq!(classify_use(", mBar{nullptr}", (2, 6), true), Some(WriteKind::InitList));
+    assert_eq!(classify_use("mFoo = aFoo;", (0, 4), true), Some(WriteKind::Constructor));
+    assert_eq!(classify_use("mFoo += 2;", (0, 4), false), Some(WriteKind::Mutation));
+    assert_eq!(classify_use("++mCount;", (2, 8), false), Some(WriteKind::Mutation));
+    assert_eq!(classify_use("if (mFoo == 2) {", (4, 8), false), None);
+    assert_eq!(classify_use("return mFoo;", (7, 11), true), None);
+}
+
+#[test]
+fn test_enclosing_conditions() {
+    let source = ["void Foo::Bar(bool aBaz) {", "  if (aBaz) {", "    mCount = 0;", "  } else {",
+                  "    for (auto& x : mList)", "      mCount++;", "  }", "}"]
+        .iter().map(|l| l.to_string()).collect::<Vec<_>>();
+    assert_eq!(enclosing_conditions(&source, 3), vec!["if (aBaz)"]);
+    assert_eq!(enclosing_conditions(&source, 6), vec!["else", "for (auto& x : mList)"]);
+    assert!(enclosing_conditions(&source, 1).is_empty());
+}
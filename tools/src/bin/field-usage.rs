@@ -0,0 +1,122 @@
+use std::env;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::collections::BTreeMap;
+use std::process;
+
+extern crate tools;
+extern crate getopts;
+
+use getopts::Options;
+
+use tools::config;
+use tools::callgraph::{find_users, BoringSymbols};
+use tools::file_format::crossref::CrossrefMap;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("o", "output",
+                "File to write the diagram to, in dot format (default stdout)",
+                "FILE");
+    opts
+}
+
+// Methods are grouped by the class they belong to, which we take from
+// the qualified name of the method.
+fn class_of(pretty: &str) -> &str {
+    match pretty.rfind("::") {
+        Some(pos) => &pretty[.. pos],
+        None => "",
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace("\\", "\\\\").replace("\"", "\\\"")
+}
+
+fn write_diagram(writer: &mut Write,
+                 field: &str,
+                 readers: &[(String, String)],
+                 writers: &[(String, String)]) -> io::Result<()> {
+    // Map from class to method symbol to (pretty, reads, writes).
+    let mut classes = BTreeMap::new();
+    for &(ref sym, ref pretty) in readers {
+        let methods = classes.entry(class_of(pretty)).or_insert(BTreeMap::new());
+        methods.entry(sym.as_str()).or_insert((pretty.as_str(), false, false)).1 = true;
+    }
+    for &(ref sym, ref pretty) in writers {
+        let methods = classes.entry(class_of(pretty)).or_insert(BTreeMap::new());
+        methods.entry(sym.as_str()).or_insert((pretty.as_str(), false, false)).2 = true;
+    }
+
+    try!(write!(writer, "digraph G {{\n"));
+    try!(write!(writer, "  \"{}\" [shape=box, style=bold];\n", escape(field)));
+    for (i, (class, methods)) in classes.iter().enumerate() {
+        try!(write!(writer, "  subgraph cluster_{} {{\n", i));
+        try!(write!(writer, "    label=\"{}\";\n", escape(if class.is_empty() { "(no class)" } else { class })));
+        for (sym, &(pretty, _, _)) in methods {
+            let label = if pretty.is_empty() { sym } else { pretty };
+            try!(write!(writer, "    \"{}\" [label=\"{}\"];\n", escape(sym), escape(label)));
+        }
+        try!(write!(writer, "  }}\n"));
+
+        for (sym, &(_, reads, writes)) in methods {
+            if reads {
+                try!(write!(writer, "  \"{}\" -> \"{}\" [label=\"reads\", style=dashed];\n",
+                            escape(sym), escape(field)));
+            }
+            if writes {
+                try!(write!(writer, "  \"{}\" -> \"{}\" [label=\"writes\", style=bold, color=red];\n",
+                            escape(sym), escape(field)));
+            }
+        }
+    }
+    try!(write!(writer, "}}\n"));
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 3 {
+        let brief = format!("Usage: {} [options] config.json tree_name field-symbol", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let field = &matches.free[2];
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+    let boring = match BoringSymbols::from_config(tree_config) {
+        Ok(boring) => boring,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(2);
+        },
+    };
+    let mut readers = find_users(&crossref, field, "Uses");
+    let mut writers = find_users(&crossref, field, "Assignments");
+    readers.retain(|&(ref sym, ref pretty)| !boring.is_boring(sym, pretty));
+    writers.retain(|&(ref sym, ref pretty)| !boring.is_boring(sym, pretty));
+
+    let mut writer: Box<Write> = match matches.opt_str("o") {
+        Some(fname) => Box::new(File::create(fname).unwrap()),
+        None => Box::new(io::stdout()),
+    };
+    write_diagram(&mut writer, field, &readers, &writers).unwrap();
+}
+
+#[test]
+fn test_class_of() {
+    assert_eq!(class_of("mozilla::dom::Foo::Bar"), "mozilla::dom::Foo");
+    assert_eq!(class_of("main"), "");
+}
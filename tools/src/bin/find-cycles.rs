@@ -0,0 +1,114 @@
+use std::env;
+use std::process;
+
+extern crate tools;
+extern crate getopts;
+
+use getopts::Options;
+
+use tools::config;
+use tools::callgraph::{callee_map, collect_callees, follow_rename, BoringSymbols, SymbolGraph};
+use tools::file_format::crossref::CrossrefMap;
+use tools::file_format::renames::read_renames;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("d", "max-depth",
+                "Maximum number of callee hops to follow (default 8)",
+                "DEPTH");
+    opts.optopt("n", "node-limit",
+                "Maximum number of symbols to add to the graph (default 1024)",
+                "COUNT");
+    opts.optopt("l", "limit",
+                "Maximum number of cycles to report (default 100)",
+                "COUNT");
+    opts.optmulti("", "exclude-sym",
+                  "Leave out symbols matching this regexp (repeatable)",
+                  "REGEXP");
+    opts.optmulti("", "exclude-pretty",
+                  "Leave out symbols whose pretty name matches this regexp (repeatable)",
+                  "REGEXP");
+    opts
+}
+
+// Describes a cycle as the chain of names around it, back to the first.
+fn cycle_line(graph: &SymbolGraph, cycle: &[usize]) -> String {
+    let name = |i: usize| {
+        let node = &graph.nodes[i];
+        if node.pretty.is_empty() { node.sym.clone() } else { node.pretty.clone() }
+    };
+    let mut names = cycle.iter().map(|&i| name(i)).collect::<Vec<_>>();
+    names.push(name(cycle[0]));
+    names.join(" -> ")
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 3 {
+        let brief = format!("Usage: {} [options] config.json tree_name entry-symbol", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let max_depth = matches.opt_str("max-depth").map_or(tree_config.paths.graph_max_depth.unwrap_or(8), |d| d.parse::<u32>().unwrap());
+    let node_limit = matches.opt_str("node-limit").map_or(tree_config.paths.graph_node_limit.unwrap_or(1024), |n| n.parse::<usize>().unwrap());
+    let limit = matches.opt_str("limit").map_or(100, |n| n.parse::<usize>().unwrap());
+    let mut entry_sym = matches.free[2].to_string();
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+    if let Some((sym, note)) = follow_rename(&entry_sym, &crossref, &read_renames(&tree_config.paths.index_path)) {
+        println!("{}", note);
+        entry_sym = sym;
+    }
+    let callees = callee_map(&crossref);
+    let boring = match BoringSymbols::from_options(tree_config, &matches) {
+        Ok(boring) => boring,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(2);
+        },
+    };
+    let graph = collect_callees(&callees, (&entry_sym[..], ""), &boring, max_depth, node_limit);
+
+    let (cycles, more) = graph.elementary_cycles(limit);
+    if cycles.is_empty() && !more {
+        println!("No cycles are reachable from {}.", entry_sym);
+    } else {
+        println!("Cycles reachable from {}:", entry_sym);
+        for cycle in &cycles {
+            println!("  {}", cycle_line(&graph, cycle));
+        }
+        if more {
+            println!("");
+            println!("Stopped after {} cycles; there may be more.", cycles.len());
+        }
+    }
+
+    if graph.truncated {
+        println!("");
+        println!("Node limit of {} reached; the results only cover part of the call graph.", node_limit);
+        println!("{}", graph.limit_hint(node_limit).unwrap());
+    }
+    if let Some(note) = graph.filter_note() {
+        println!("");
+        println!("{}", note);
+    }
+}
+
+#[test]
+fn test_cycle_line() {
+    let mut graph = SymbolGraph::new();
+    let a = graph.add_node("_Z1av", "a", 0);
+    let b = graph.add_node("_Z1bv", "", 1);
+    assert_eq!(cycle_line(&graph, &[a, b]), "a -> _Z1bv -> a");
+    assert_eq!(cycle_line(&graph, &[b]), "_Z1bv -> _Z1bv");
+}
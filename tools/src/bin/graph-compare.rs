@@ -0,0 +1,204 @@
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+
+extern crate tools;
+extern crate getopts;
+extern crate rustc_serialize;
+
+use getopts::Options;
+use rustc_serialize::json::Json;
+
+use tools::config;
+use tools::callgraph::{find_callers, find_uses, resolve_name, SymbolGraph};
+use tools::file_format::crossref::CrossrefMap;
+use tools::file_format::identifiers::IdentMap;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("o", "dot",
+                "File to write the combined graph to, in dot format",
+                "FILE");
+    opts
+}
+
+// Reads the caller/callee pairs from an external call graph of the form
+// {"edges": [{"caller": "...", "callee": "..."}, ...]}.
+fn read_edges(json: &Json) -> Result<Vec<(String, String)>, &'static str> {
+    let edges = try!(json.find("edges").and_then(|e| e.as_array()).ok_or("missing edges array"));
+    let mut result = vec![];
+    for edge in edges {
+        let caller = try!(edge.find("caller").and_then(|c| c.as_string()).ok_or("edge without a caller"));
+        let callee = try!(edge.find("callee").and_then(|c| c.as_string()).ok_or("edge without a callee"));
+        result.push((caller.to_string(), callee.to_string()));
+    }
+    Ok(result)
+}
+
+// Puts the edges of both graphs into one, with the edges seen only
+// dynamically marked as added and those seen only statically as
+// removed. Names are numbered by hops from those nothing calls, so the
+// dot layout reads down from them.
+fn combined_graph(dynamic_edges: &BTreeSet<(String, String, bool)>,
+                  static_only: &BTreeSet<(String, String)>) -> SymbolGraph {
+    let edges = dynamic_edges.iter().map(|&(ref caller, ref callee, seen)| (caller, callee, if seen { "" } else { "added" }))
+        .chain(static_only.iter().map(|&(ref caller, ref callee)| (caller, callee, "removed")))
+        .collect::<Vec<_>>();
+
+    let callees = edges.iter().map(|&(_, callee, _)| callee).collect::<BTreeSet<_>>();
+    let mut depths = BTreeMap::new();
+    let mut queue = VecDeque::new();
+    for &(caller, _, _) in &edges {
+        if !callees.contains(caller) && !depths.contains_key(caller) {
+            depths.insert(caller, 0);
+            queue.push_back(caller);
+        }
+    }
+    while let Some(name) = queue.pop_front() {
+        let depth = depths[name];
+        for &(caller, callee, _) in &edges {
+            if caller == name && !depths.contains_key(callee) {
+                depths.insert(callee, depth + 1);
+                queue.push_back(callee);
+            }
+        }
+    }
+
+    let mut graph = SymbolGraph::new();
+    for &(caller, callee, status) in &edges {
+        // Names that are only reachable through a cycle go with the roots.
+        let from = graph.add_node(caller, "", depths.get(caller).cloned().unwrap_or(0));
+        let to = graph.add_node(callee, "", depths.get(callee).cloned().unwrap_or(0));
+        graph.add_edge(from, to);
+        if !status.is_empty() {
+            graph.edge_status.insert((from, to), status.to_string());
+        }
+    }
+    graph
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 3 {
+        let brief = format!("Usage: {} [options] config.json tree_name call-graph.json", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+
+    let mut input = String::new();
+    File::open(&matches.free[2]).unwrap().read_to_string(&mut input).unwrap();
+    let edges = match Json::from_str(&input).map_err(|_| "invalid JSON").and_then(|j| read_edges(&j)) {
+        Ok(edges) => edges,
+        Err(err) => {
+            println!("Can't read {}: {}", matches.free[2], err);
+            return;
+        }
+    };
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+    let ids = IdentMap::new(&format!("{}/identifiers", tree_config.paths.index_path));
+
+    // Map every symbol back to the name the external graph used, so the
+    // results are reported in its terms.
+    let mut names = BTreeMap::new();
+    let mut unresolved = BTreeSet::new();
+    let mut dynamic_edges = BTreeSet::new();
+    for &(ref caller, ref callee) in &edges {
+        let callers = resolve_name(caller, &crossref, &ids);
+        let callees = resolve_name(callee, &crossref, &ids);
+        if callers.is_empty() {
+            unresolved.insert(caller.clone());
+        }
+        if callees.is_empty() {
+            unresolved.insert(callee.clone());
+        }
+        for sym in &callers {
+            names.insert(sym.clone(), caller.clone());
+        }
+        for sym in &callees {
+            names.insert(sym.clone(), callee.clone());
+        }
+
+        let seen = callers.iter().any(|a| callees.iter().any(|b| !find_uses(&crossref, a, b).is_empty()));
+        dynamic_edges.insert((caller.clone(), callee.clone(), seen));
+    }
+
+    // Static edges between functions the external graph knows about.
+    let mut static_only = BTreeSet::new();
+    for (sym, name) in &names {
+        for (caller, _) in find_callers(&crossref, sym) {
+            let caller_name = match names.get(&caller) {
+                Some(caller_name) => caller_name,
+                None => continue,
+            };
+            if !dynamic_edges.iter().any(|&(ref a, ref b, _)| a == caller_name && b == name) {
+                static_only.insert((caller_name.clone(), name.clone()));
+            }
+        }
+    }
+
+    println!("Seen both statically and dynamically:");
+    for &(ref caller, ref callee, _) in dynamic_edges.iter().filter(|e| e.2) {
+        println!("  {} -> {}", caller, callee);
+    }
+    println!("");
+    println!("Seen only dynamically (indirect calls, or missing from the index):");
+    for &(ref caller, ref callee, _) in dynamic_edges.iter().filter(|e| !e.2) {
+        println!("  {} -> {}", caller, callee);
+    }
+    println!("");
+    println!("Seen only statically (not exercised, or not a call):");
+    for &(ref caller, ref callee) in &static_only {
+        println!("  {} -> {}", caller, callee);
+    }
+    if !unresolved.is_empty() {
+        println!("");
+        println!("Names not found in the index:");
+        for name in &unresolved {
+            println!("  {}", name);
+        }
+    }
+
+    if let Some(dot_fname) = matches.opt_str("dot") {
+        let mut dot_file = File::create(dot_fname).unwrap();
+        combined_graph(&dynamic_edges, &static_only).write_dot(&mut dot_file).unwrap();
+    }
+}
+
+#[test]
+fn test_read_edges() {
+    let json = Json::from_str(r#"{"edges": [{"caller": "main", "callee": "mid", "count": 3}]}"#).unwrap();
+    assert_eq!(read_edges(&json), Ok(vec![("main".to_string(), "mid".to_string())]));
+    let json = Json::from_str(r#"{"edges": [{"caller": "main"}]}"#).unwrap();
+    assert_eq!(read_edges(&json), Err("edge without a callee"));
+}
+
+#[test]
+fn test_combined_graph() {
+    let mut dynamic_edges = BTreeSet::new();
+    dynamic_edges.insert(("main".to_string(), "mid".to_string(), true));
+    dynamic_edges.insert(("mid".to_string(), "leaf".to_string(), false));
+    let mut static_only = BTreeSet::new();
+    static_only.insert(("main".to_string(), "leaf".to_string()));
+
+    let graph = combined_graph(&dynamic_edges, &static_only);
+    let nodes = graph.nodes.iter().map(|n| (n.sym.as_str(), n.depth)).collect::<Vec<_>>();
+    assert_eq!(nodes, vec![("main", 0), ("mid", 1), ("leaf", 1)]);
+    assert_eq!(graph.edges.iter().cloned().collect::<Vec<_>>(), vec![(0, 1), (0, 2), (1, 2)]);
+    assert_eq!(graph.edge_status.get(&(0, 1)), None);
+    assert_eq!(graph.edge_status[&(1, 2)], "added");
+    assert_eq!(graph.edge_status[&(0, 2)], "removed");
+}
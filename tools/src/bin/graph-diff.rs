@@ -0,0 +1,190 @@
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::process;
+
+extern crate tools;
+extern crate getopts;
+extern crate rustc_serialize;
+
+use getopts::Options;
+use rustc_serialize::json::Json;
+
+use tools::callgraph::SymbolGraph;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("o", "dot",
+                "File to write the merged graph to, in dot format",
+                "FILE");
+    opts.optopt("", "cytoscape",
+                "File to write the merged graph to, as Cytoscape.js JSON",
+                "FILE");
+    opts
+}
+
+// A graph read back from the Cytoscape.js JSON that the graph tools
+// write: the label and depth of each symbol, the edges between symbols,
+// and what kind of edges they are.
+struct Graph {
+    nodes: BTreeMap<String, (String, u32)>,
+    edges: BTreeSet<(String, String)>,
+    edge_kind: String,
+}
+
+fn read_graph(json: &Json) -> Result<Graph, &'static str> {
+    let nodes = try!(json.find_path(&["elements", "nodes"]).and_then(|n| n.as_array()).ok_or("missing elements.nodes"));
+    let edges = try!(json.find_path(&["elements", "edges"]).and_then(|e| e.as_array()).ok_or("missing elements.edges"));
+
+    let mut ids = BTreeMap::new();
+    let mut symbols = BTreeMap::new();
+    for node in nodes {
+        let data = try!(node.find("data").ok_or("node without data"));
+        let id = try!(data.find("id").and_then(|i| i.as_string()).ok_or("node without an id"));
+        let sym = try!(data.find("sym").and_then(|s| s.as_string()).ok_or("node without a sym"));
+        let label = data.find("label").and_then(|l| l.as_string()).unwrap_or(sym);
+        let depth = data.find("depth").and_then(|d| d.as_u64()).unwrap_or(0);
+        ids.insert(id.to_string(), sym.to_string());
+        symbols.insert(sym.to_string(), (label.to_string(), depth as u32));
+    }
+
+    let mut result = BTreeSet::new();
+    let mut edge_kind = "uses";
+    for edge in edges {
+        let data = try!(edge.find("data").ok_or("edge without data"));
+        let source = try!(data.find("source").and_then(|s| s.as_string()).and_then(|s| ids.get(s)).ok_or("edge with an unknown source"));
+        let target = try!(data.find("target").and_then(|t| t.as_string()).and_then(|t| ids.get(t)).ok_or("edge with an unknown target"));
+        result.insert((source.clone(), target.clone()));
+        if let Some(kind) = data.find("kind").and_then(|k| k.as_string()) {
+            edge_kind = kind;
+        }
+    }
+    Ok(Graph { nodes: symbols, edges: result, edge_kind: edge_kind.to_string() })
+}
+
+fn load(fname: &str) -> Graph {
+    let mut input = String::new();
+    File::open(fname).unwrap().read_to_string(&mut input).unwrap();
+    let json = match Json::from_str(&input) {
+        Ok(json) => json,
+        Err(e) => {
+            println!("{}: {}", fname, e);
+            process::exit(2);
+        },
+    };
+    match read_graph(&json) {
+        Ok(graph) => graph,
+        Err(e) => {
+            println!("{}: {}", fname, e);
+            process::exit(2);
+        },
+    }
+}
+
+fn status(in_old: bool, in_new: bool) -> &'static str {
+    match (in_old, in_new) {
+        (true, true) => "unchanged",
+        (false, _) => "added",
+        (true, false) => "removed",
+    }
+}
+
+// Returns the union of both graphs, with the status of each node and
+// edge. Symbols in both keep their label and depth from the new graph.
+fn diff(old: &Graph, new: &Graph) -> SymbolGraph {
+    let mut nodes = old.nodes.clone();
+    nodes.extend(new.nodes.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+    let mut graph = SymbolGraph::new();
+    for (sym, &(ref label, depth)) in &nodes {
+        let index = graph.add_node(sym, label, depth);
+        graph.nodes[index].status = status(old.nodes.contains_key(sym), new.nodes.contains_key(sym)).to_string();
+    }
+    for edge in old.edges.union(&new.edges) {
+        let from = graph.find(&edge.0).unwrap();
+        let to = graph.find(&edge.1).unwrap();
+        graph.add_edge(from, to);
+        graph.edge_status.insert((from, to), status(old.edges.contains(edge), new.edges.contains(edge)).to_string());
+    }
+    graph
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 2 {
+        let brief = format!("Usage: {} [options] old-graph.json new-graph.json", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let old = load(&matches.free[0]);
+    let new = load(&matches.free[1]);
+    let graph = diff(&old, &new);
+    let edge_status = |edge: &(usize, usize)| &graph.edge_status[edge][..];
+
+    for &(title, wanted) in &[("Added symbols:", "added"), ("Removed symbols:", "removed")] {
+        let nodes = graph.nodes.iter().filter(|node| node.status == wanted).collect::<Vec<_>>();
+        if nodes.is_empty() {
+            continue;
+        }
+        println!("{}", title);
+        for node in &nodes {
+            println!("  {}", node.pretty);
+        }
+        println!("");
+    }
+    for &(title, wanted) in &[("Added edges:", "added"), ("Removed edges:", "removed")] {
+        let edges = graph.edges.iter().filter(|edge| edge_status(*edge) == wanted).collect::<Vec<_>>();
+        if edges.is_empty() {
+            continue;
+        }
+        println!("{}", title);
+        for &&(from, to) in &edges {
+            println!("  {} -> {}", graph.nodes[from].pretty, graph.nodes[to].pretty);
+        }
+        println!("");
+    }
+    let unchanged = graph.edges.iter().filter(|edge| edge_status(*edge) == "unchanged").count();
+    println!("{} edges are unchanged.", unchanged);
+
+    if let Some(dot_fname) = matches.opt_str("dot") {
+        let mut dot_file = File::create(dot_fname).unwrap();
+        graph.write_dot(&mut dot_file).unwrap();
+    }
+    if let Some(cytoscape_fname) = matches.opt_str("cytoscape") {
+        let mut cytoscape_file = File::create(cytoscape_fname).unwrap();
+        graph.write_cytoscape(&mut cytoscape_file, &new.edge_kind).unwrap();
+    }
+}
+
+#[test]
+fn test_diff() {
+    let old = Json::from_str(r#"{"elements": {
+        "nodes": [{"data": {"id": "n0", "sym": "_Z1av", "label": "a", "depth": 0}}, {"data": {"id": "n1", "sym": "_Z1bv", "label": "b", "depth": 1}}],
+        "edges": [{"data": {"id": "e0", "source": "n1", "target": "n0", "kind": "uses"}}]}}"#).unwrap();
+    let new = Json::from_str(r#"{"elements": {
+        "nodes": [{"data": {"id": "n0", "sym": "_Z1av", "label": "a", "depth": 0}}, {"data": {"id": "n1", "sym": "_Z1cv", "label": "c", "depth": 1}}],
+        "edges": [{"data": {"id": "e0", "source": "n1", "target": "n0", "kind": "uses"}}]}}"#).unwrap();
+    let graph = diff(&read_graph(&old).unwrap(), &read_graph(&new).unwrap());
+    let nodes = graph.nodes.iter().map(|n| (n.sym.as_str(), n.status.as_str(), n.depth)).collect::<Vec<_>>();
+    assert_eq!(nodes, vec![("_Z1av", "unchanged", 0), ("_Z1bv", "removed", 1), ("_Z1cv", "added", 1)]);
+    assert_eq!(graph.edges.iter().cloned().collect::<Vec<_>>(), vec![(1, 0), (2, 0)]);
+    assert_eq!(graph.edge_status[&(1, 0)], "removed");
+    assert_eq!(graph.edge_status[&(2, 0)], "added");
+
+    let mut output = Vec::new();
+    graph.write_dot(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("  n1 [label=\"b\", color=red, style=dashed];\n"), "{}", output);
+    assert!(output.contains("  n2 -> n0 [color=green];\n"), "{}", output);
+
+    assert!(read_graph(&Json::from_str(r#"{"elements": {"nodes": []}}"#).unwrap()).is_err());
+}
@@ -0,0 +1,290 @@
+use std::cmp;
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::process;
+
+extern crate tools;
+extern crate getopts;
+
+use getopts::Options;
+
+use tools::config;
+use tools::callgraph::{collect, collect_ranked, entry_point_set, estimate, estimate_warning, find_uses, fit_depth, load_callers, use_count};
+use tools::callgraph::{add_output_options, write_outputs, BoringSymbols, Budgeted, Callers, Pruned};
+use tools::file_format::analysis::{read_analysis, read_target, AnalysisKind};
+use tools::file_format::crossref::CrossrefMap;
+use tools::file_format::file_classes::FileClasses;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("d", "max-depth",
+                "Maximum number of caller hops to follow (default 4)",
+                "DEPTH");
+    opts.optopt("n", "node-limit",
+                "Maximum number of symbols to add to the graph (default 256)",
+                "COUNT");
+    add_output_options(&mut opts, "the affected call graph");
+    opts.optmulti("x", "exclude",
+                  "Leave out callers defined in files of this class (test, build, generated or prod)",
+                  "CLASS");
+    opts.optflag("", "estimate",
+                 "Only print the estimated number of callers within each number of hops");
+    opts.optflag("", "fit-depth",
+                 "Lower the maximum depth if the walk is expected to exceed the node limit");
+    opts.optflag("e", "explain",
+                 "Print the chain of uses leading from each entry point to a changed symbol");
+    opts.optmulti("", "exclude-sym",
+                  "Leave out symbols matching this regexp (repeatable)",
+                  "REGEXP");
+    opts.optmulti("", "exclude-pretty",
+                  "Leave out symbols whose pretty name matches this regexp (repeatable)",
+                  "REGEXP");
+    opts.optopt("", "max-lookups",
+                "Stop walking after looking up the callers of this many symbols",
+                "COUNT");
+    opts.optopt("", "max-millis",
+                "Stop walking after this many milliseconds",
+                "MS");
+    opts.optflag("", "rank",
+                 "When the node limit is reached, keep the most used callers instead of the nearest ones");
+    opts
+}
+
+// Maps each file touched by a unified diff to the line numbers (in the
+// new version of the file) that were added, changed, or next to a
+// deletion.
+fn changed_lines(diff: &str) -> HashMap<String, BTreeSet<u32>> {
+    let mut result = HashMap::new();
+    let mut cur_path: Option<String> = None;
+    let mut old_left: u32 = 0;
+    let mut new_left: u32 = 0;
+    let mut new_lineno = 0;
+
+    for line in diff.lines() {
+        if old_left > 0 || new_left > 0 {
+            if line.starts_with('\\') {
+                continue;
+            }
+
+            let changed = match line.chars().next() {
+                Some('+') => {
+                    new_left = new_left.saturating_sub(1);
+                    new_lineno += 1;
+                    Some(new_lineno - 1)
+                },
+                Some('-') => {
+                    old_left = old_left.saturating_sub(1);
+                    Some(new_lineno)
+                },
+                _ => {
+                    old_left = old_left.saturating_sub(1);
+                    new_left = new_left.saturating_sub(1);
+                    new_lineno += 1;
+                    None
+                },
+            };
+
+            if let (Some(lineno), &Some(ref path)) = (changed, &cur_path) {
+                result.entry(path.clone()).or_insert(BTreeSet::new()).insert(lineno);
+            }
+            continue;
+        }
+
+        if line.starts_with("+++ ") {
+            let path = line[4..].split('\t').next().unwrap();
+            cur_path = if path == "/dev/null" {
+                None
+            } else if path.starts_with("b/") {
+                Some(path[2..].to_string())
+            } else {
+                Some(path.to_string())
+            };
+        } else if line.starts_with("@@ ") {
+            // @@ -old_start[,old_count] +new_start[,new_count] @@
+            let ranges = line.split(' ').skip(1).take(2).collect::<Vec<_>>();
+            if ranges.len() < 2 {
+                continue;
+            }
+
+            fn parse_range(range: &str) -> (u32, u32) {
+                let mut pieces = range[1..].split(',');
+                let start = pieces.next().unwrap().parse::<u32>().unwrap_or(0);
+                let count = pieces.next().map_or(1, |c| c.parse::<u32>().unwrap_or(0));
+                (start, count)
+            }
+
+            let (_, old_count) = parse_range(ranges[0]);
+            let (new_start, new_count) = parse_range(ranges[1]);
+            old_left = old_count;
+            new_left = new_count;
+            new_lineno = new_start;
+        }
+    }
+
+    result
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 3 {
+        let brief = format!("Usage: {} [options] config.json tree_name diff-file", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let max_depth = matches.opt_str("max-depth").map_or(tree_config.paths.graph_max_depth.unwrap_or(4), |d| d.parse::<u32>().unwrap());
+    let node_limit = matches.opt_str("node-limit").map_or(tree_config.paths.graph_node_limit.unwrap_or(256), |n| n.parse::<usize>().unwrap());
+
+    let mut diff = String::new();
+    File::open(&matches.free[2]).unwrap().read_to_string(&mut diff).unwrap();
+
+    // Find the symbols enclosing every changed line. Changes inside a
+    // function body are attributed to the function via contextsym;
+    // otherwise we use whatever is defined on the line.
+    let mut roots = BTreeMap::new();
+    for (path, lines) in changed_lines(&diff) {
+        let analysis_fname = format!("{}/analysis/{}", tree_config.paths.index_path, path);
+        let analysis = read_analysis(&analysis_fname, &read_target);
+        for datum in analysis {
+            if !lines.contains(&datum.loc.lineno) {
+                continue;
+            }
+            for piece in datum.data {
+                let contextsym = piece.contextsym.split(',').next().unwrap().to_string();
+                if !contextsym.is_empty() {
+                    roots.insert(contextsym, piece.context);
+                } else if piece.kind == AnalysisKind::Def {
+                    roots.insert(piece.sym, piece.pretty);
+                }
+            }
+        }
+    }
+    let roots = roots.into_iter().collect::<Vec<_>>();
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+    let boring = match BoringSymbols::from_options(tree_config, &matches) {
+        Ok(boring) => boring,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(2);
+        },
+    };
+    let classes = FileClasses::new(&tree_config.paths.index_path);
+    let exclude = matches.opt_strs("exclude");
+    let callers_index = load_callers(&tree_config.paths.index_path);
+    let users = Callers { crossref: &crossref, callers: callers_index.as_ref() };
+    let callers = Pruned { inner: &users, crossref: &crossref, classes: &classes, exclude: &exclude };
+    let totals = estimate(&callers, &roots, &boring, max_depth, 16);
+    if matches.opt_present("estimate") {
+        println!("Estimated symbols within each number of hops:");
+        for (depth, total) in totals.iter().enumerate() {
+            println!("  {}\t{}", depth, total);
+        }
+        return;
+    }
+    let mut max_depth = max_depth;
+    if matches.opt_present("fit-depth") {
+        let fit = cmp::max(fit_depth(&totals, node_limit).unwrap_or(0), 1);
+        if fit < max_depth {
+            println!("Lowering the maximum depth to {} to stay within the node limit.", fit);
+            println!("");
+            max_depth = fit;
+        }
+    } else if let Some(warning) = estimate_warning(&totals, node_limit) {
+        println!("{}", warning);
+        println!("");
+    }
+    let max_lookups = matches.opt_str("max-lookups").map(|n| n.parse::<usize>().unwrap());
+    let max_millis = matches.opt_str("max-millis").map(|n| n.parse::<u64>().unwrap());
+    let budgeted = Budgeted::new(&callers, max_lookups, max_millis);
+    let graph = if matches.opt_present("rank") {
+        collect_ranked(&budgeted, &roots, &boring, max_depth, node_limit, &|sym| use_count(&crossref, sym))
+    } else {
+        collect(&budgeted, &roots, &boring, max_depth, node_limit)
+    };
+
+    println!("Changed symbols:");
+    for &(ref sym, ref pretty) in &roots {
+        println!("  {}", if pretty.is_empty() { sym } else { pretty });
+    }
+
+    // Entry points are the symbols nothing else calls. Symbols at the
+    // depth limit weren't expanded, so they are reported separately.
+    println!("");
+    println!("Potentially affected entry points:");
+    for (i, node) in graph.nodes.iter().enumerate() {
+        if !graph.callers_of(i).is_empty() {
+            continue;
+        }
+        let location = match crossref.lookup(&node.sym).as_ref().and_then(|e| e.first_def().map(|(p, l)| format!("{}:{}", p, l))) {
+            Some(location) => location,
+            None => "(no definition)".to_string(),
+        };
+        let mut note = if node.depth >= max_depth { " (depth limit)".to_string() } else { "".to_string() };
+        if let Some(set_name) = entry_point_set(tree_config, None, &node.sym, &node.pretty) {
+            note.push_str(&format!(" (entry point: {})", set_name));
+        }
+        let name = if node.pretty.is_empty() { &node.sym } else { &node.pretty };
+        println!("  {}\t{}{}", name, location, note);
+
+        if matches.opt_present("explain") {
+            let path = graph.path_to_root(i);
+            for pair in path.windows(2) {
+                let (caller, callee) = (&graph.nodes[pair[0]], &graph.nodes[pair[1]]);
+                let location = match find_uses(&crossref, &caller.sym, &callee.sym).into_iter().next() {
+                    Some((path, lineno)) => format!("{}:{}", path, lineno),
+                    None => "(unknown location)".to_string(),
+                };
+                let name = if callee.pretty.is_empty() { &callee.sym } else { &callee.pretty };
+                println!("    -> uses {}\tat {}", name, location);
+            }
+        }
+    }
+
+    if graph.truncated {
+        println!("");
+        println!("Node limit of {} reached; some callers were not examined.", node_limit);
+        println!("{}", graph.limit_hint(node_limit).unwrap());
+    }
+    if let Some(note) = budgeted.exhausted_note() {
+        println!("");
+        println!("{}", note);
+    }
+    if let Some(note) = graph.filter_note() {
+        println!("");
+        println!("{}", note);
+    }
+
+    let title = format!("Impact of {}", matches.free[2]);
+    if let Err(e) = write_outputs(&matches, graph, tree_config, &crossref, "uses", &title) {
+        println!("{}", e);
+        process::exit(2);
+    }
+}
+
+#[test]
+fn test_changed_lines() {
+    let diff = "--- a/foo.cpp\n+++ b/foo.cpp\n@@ -1,3 +1,3 @@\n a\n-b\n+c\n d\n@@ -10 +10,2 @@\n x\n+y\n";
+    let changed = changed_lines(diff);
+    let lines = changed.get("foo.cpp").unwrap().iter().cloned().collect::<Vec<_>>();
+    assert_eq!(lines, vec![2, 11]);
+
+    // A hunk whose counts don't match its body mustn't underflow.
+    let diff = "+++ b/bar.cpp\n@@ -1,1 +1,5 @@\n-a\n-b\n+c\n";
+    let changed = changed_lines(diff);
+    let lines = changed.get("bar.cpp").unwrap().iter().cloned().collect::<Vec<_>>();
+    assert_eq!(lines, vec![1]);
+}
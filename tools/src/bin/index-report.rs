@@ -0,0 +1,291 @@
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::path::Path;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+extern crate tools;
+extern crate getopts;
+extern crate rustc_serialize;
+
+use getopts::Options;
+use rustc_serialize::json::{Json, ToJson};
+
+use tools::config;
+use tools::file_format::crossref::CrossrefMap;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("p", "previous",
+                "A JSON report of the previous index, to compute deltas against",
+                "FILE");
+    opts.optopt("", "max-failed",
+                "Maximum number of failed files to list (default 100)",
+                "COUNT");
+    opts.optflag("m", "markdown",
+                 "Print the report as Markdown instead of JSON");
+    opts
+}
+
+// Groups files by the indexer that analyzes them.
+fn language_of(path: &str) -> &'static str {
+    let ext = match Path::new(path).extension() {
+        Some(ext) => ext.to_str().unwrap_or(""),
+        None => "",
+    };
+    match ext {
+        "c" | "cc" | "cpp" | "cxx" | "h" | "hh" | "hpp" | "m" | "mm" => "cpp",
+        "js" | "jsm" | "jsx" | "sjs" | "html" | "xhtml" | "xul" => "js",
+        "idl" => "idl",
+        "webidl" => "webidl",
+        "ipdl" | "ipdlh" => "ipdl",
+        "rs" => "rust",
+        _ => "other",
+    }
+}
+
+fn walk(dir: &Path, prefix: &str, paths: &mut Vec<String>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries {
+        let entry = entry.unwrap();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+        if entry.file_type().unwrap().is_dir() {
+            walk(&entry.path(), &path, paths);
+        } else {
+            paths.push(path);
+        }
+    }
+}
+
+// Counts the records of each kind ("use", "def", etc., or "source") in
+// an analysis file. Returns None if the file is empty or malformed,
+// which means the indexer failed on it.
+fn count_records(filename: &str, counts: &mut BTreeMap<String, u64>) -> Option<()> {
+    let file = match File::open(filename) {
+        Ok(file) => file,
+        Err(_) => return None,
+    };
+    let mut file_counts = BTreeMap::new();
+    let mut records = 0;
+    for line in BufReader::new(file).lines() {
+        let json = match line.ok().and_then(|line| Json::from_str(&line).ok()) {
+            Some(json) => json,
+            None => return None,
+        };
+        let kind = match json.as_object() {
+            Some(obj) if obj.contains_key("target") =>
+                obj.get("kind").and_then(|k| k.as_string()).unwrap_or("other").to_string(),
+            Some(obj) if obj.contains_key("source") => "source".to_string(),
+            Some(_) => continue,
+            None => return None,
+        };
+        *file_counts.entry(kind).or_insert(0) += 1;
+        records += 1;
+    }
+    if records == 0 {
+        return None;
+    }
+    for (kind, count) in file_counts {
+        *counts.entry(kind).or_insert(0) += count;
+    }
+    Some(())
+}
+
+fn count_lines(filename: &str) -> u64 {
+    match File::open(filename) {
+        Ok(f) => BufReader::new(f).lines().count() as u64,
+        Err(_) => 0,
+    }
+}
+
+fn read_list(filename: &str) -> Vec<String> {
+    match File::open(filename) {
+        Ok(f) => BufReader::new(f).lines().map(|l| l.unwrap()).filter(|l| !l.is_empty()).collect(),
+        Err(_) => vec![],
+    }
+}
+
+// Collects the numbers in |json| under dotted key paths, such as
+// "files.cpp.analyzed".
+fn flatten(prefix: &str, json: &Json, out: &mut BTreeMap<String, i64>) {
+    match *json {
+        Json::Object(ref obj) => {
+            for (key, value) in obj {
+                if key == "deltas" {
+                    continue;
+                }
+                let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten(&path, value, out);
+            }
+        },
+        Json::I64(n) => { out.insert(prefix.to_string(), n); },
+        Json::U64(n) => { out.insert(prefix.to_string(), n as i64); },
+        _ => {},
+    }
+}
+
+// Returns the changes in every number between two reports. Numbers
+// missing from one side count as zero.
+fn deltas(previous: &Json, current: &Json) -> BTreeMap<String, i64> {
+    let mut old = BTreeMap::new();
+    let mut new = BTreeMap::new();
+    flatten("", previous, &mut old);
+    flatten("", current, &mut new);
+    let keys = old.keys().chain(new.keys()).cloned().collect::<BTreeSet<_>>();
+    keys.into_iter().filter_map(|key| {
+        let delta = new.get(&key).cloned().unwrap_or(0) - old.get(&key).cloned().unwrap_or(0);
+        if delta != 0 { Some((key, delta)) } else { None }
+    }).collect()
+}
+
+fn print_markdown(report: &Json) {
+    let number = |json: Option<&Json>| json.and_then(|n| n.as_u64()).unwrap_or(0);
+    let deltas = report.find("deltas").and_then(|d| d.as_object());
+    let delta = |key: &str| match deltas.and_then(|d| d.get(key)).and_then(|d| d.as_i64()) {
+        Some(d) if d > 0 => format!(" (+{})", d),
+        Some(d) => format!(" ({})", d),
+        None => "".to_string(),
+    };
+
+    println!("# Index report");
+    println!("");
+    println!("## Files");
+    println!("");
+    println!("| Language | Analyzed | Failed |");
+    println!("| --- | ---: | ---: |");
+    for (lang, stats) in report.find("files").and_then(|f| f.as_object()).unwrap() {
+        println!("| {} | {}{} | {}{} |", lang,
+                 number(stats.find("analyzed")), delta(&format!("files.{}.analyzed", lang)),
+                 number(stats.find("failed")), delta(&format!("files.{}.failed", lang)));
+    }
+    for &(key, title) in &[("records", "Records"), ("crossref", "Crossref")] {
+        println!("");
+        println!("## {}", title);
+        println!("");
+        for (name, count) in report.find(key).and_then(|r| r.as_object()).unwrap() {
+            println!("- {}: {}{}", name, number(Some(count)), delta(&format!("{}.{}", key, name)));
+        }
+    }
+    let failed = report.find("failed").and_then(|f| f.as_array()).unwrap();
+    if !failed.is_empty() {
+        println!("");
+        println!("## Failed files");
+        println!("");
+        for path in failed {
+            println!("- {}", path.as_string().unwrap());
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 2 {
+        let brief = format!("Usage: {} [options] config.json tree_name", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let index_path = &tree_config.paths.index_path;
+    let max_failed = matches.opt_str("max-failed").map_or(100, |n| n.parse::<usize>().unwrap());
+
+    let mut analyzed = Vec::new();
+    walk(Path::new(&format!("{}/analysis", index_path)), "", &mut analyzed);
+    analyzed.sort();
+
+    // A file fails when its analysis is empty or malformed, or when it is
+    // listed for one of the indexers that don't go through the build but
+    // has no analysis at all.
+    let mut files: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+    let mut records = BTreeMap::new();
+    let mut failed = BTreeSet::new();
+    for path in &analyzed {
+        let stats = files.entry(language_of(path)).or_insert((0, 0));
+        match count_records(&format!("{}/analysis/{}", index_path, path), &mut records) {
+            Some(()) => stats.0 += 1,
+            None => {
+                stats.1 += 1;
+                failed.insert(path.clone());
+            },
+        }
+    }
+    let analyzed = analyzed.into_iter().collect::<BTreeSet<_>>();
+    for list in &["js-files", "idl-files", "ipdl-files"] {
+        for path in read_list(&format!("{}/{}", index_path, list)) {
+            if !analyzed.contains(&path) && failed.insert(path.clone()) {
+                files.entry(language_of(&path)).or_insert((0, 0)).1 += 1;
+            }
+        }
+    }
+
+    let mut crossref_counts = BTreeMap::new();
+    let crossref_fname = format!("{}/crossref", index_path);
+    let symbols = if Path::new(&crossref_fname).exists() { CrossrefMap::new(&crossref_fname).symbols().len() as u64 } else { 0 };
+    crossref_counts.insert("symbols".to_string(), symbols.to_json());
+    crossref_counts.insert("jumps".to_string(), count_lines(&format!("{}/jumps", index_path)).to_json());
+    crossref_counts.insert("identifiers".to_string(), count_lines(&format!("{}/identifiers", index_path)).to_json());
+
+    let mut report = BTreeMap::new();
+    let files = files.into_iter().map(|(lang, (ok, bad))| {
+        let mut obj = BTreeMap::new();
+        obj.insert("analyzed".to_string(), ok.to_json());
+        obj.insert("failed".to_string(), bad.to_json());
+        (lang.to_string(), Json::Object(obj))
+    }).collect::<BTreeMap<_, _>>();
+    report.insert("files".to_string(), Json::Object(files));
+    report.insert("records".to_string(), records.to_json());
+    report.insert("crossref".to_string(), Json::Object(crossref_counts));
+    report.insert("failed".to_string(), failed.iter().take(max_failed).cloned().collect::<Vec<_>>().to_json());
+    let mut report = Json::Object(report);
+
+    if let Some(fname) = matches.opt_str("previous") {
+        let mut text = String::new();
+        File::open(fname).unwrap().read_to_string(&mut text).unwrap();
+        let previous = Json::from_str(&text).unwrap();
+        let changes = deltas(&previous, &report);
+        if let Json::Object(ref mut obj) = report {
+            obj.insert("deltas".to_string(), changes.to_json());
+        }
+    }
+
+    if matches.opt_present("markdown") {
+        print_markdown(&report);
+    } else {
+        println!("{}", report.pretty());
+    }
+}
+
+#[test]
+fn test_language_of() {
+    assert_eq!(language_of("dom/base/nsINode.cpp"), "cpp");
+    assert_eq!(language_of("__GENERATED__/dist/include/nsINode.h"), "cpp");
+    assert_eq!(language_of("browser/base/content/browser.js"), "js");
+    assert_eq!(language_of("dom/ipc/PContent.ipdl"), "ipdl");
+    assert_eq!(language_of("README"), "other");
+}
+
+#[test]
+fn test_deltas() {
+    let previous = Json::from_str(r#"{"files": {"cpp": {"analyzed": 10, "failed": 1}}, "records": {"use": 5}, "failed": ["a.cpp"]}"#).unwrap();
+    let current = Json::from_str(r#"{"files": {"cpp": {"analyzed": 10, "failed": 3}, "js": {"analyzed": 2, "failed": 0}}, "records": {}, "failed": ["a.cpp", "b.cpp", "c.cpp"]}"#).unwrap();
+    let changes = deltas(&previous, &current);
+    assert_eq!(changes.into_iter().collect::<Vec<_>>(),
+               vec![("files.cpp.failed".to_string(), 2), ("files.js.analyzed".to_string(), 2),
+                    ("records.use".to_string(), -5)]);
+}
@@ -0,0 +1,119 @@
+use std::env;
+use std::path::PathBuf;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+
+extern crate ipdl_parser;
+extern crate getopts;
+
+use getopts::Options;
+
+use ipdl_parser::parser;
+use ipdl_parser::ast;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optmulti("I", "include",
+                  "Additional directory to search for included protocol specifications",
+                  "DIR");
+    opts.optopt("o", "output",
+                "File to write the diagram to, in dot format (default stdout)",
+                "FILE");
+    opts
+}
+
+fn actor_name(protocol: &str, side: &str) -> String {
+    format!("{}{}", protocol, side)
+}
+
+// Sync messages block the sender, so they are drawn more heavily than
+// async ones.
+fn message_style(message: &ast::MessageDecl) -> &'static str {
+    if message.send_semantics == ast::SendSemantics::Intr {
+        "style=bold, color=red"
+    } else if message.send_semantics == ast::SendSemantics::Sync {
+        "style=bold"
+    } else {
+        "style=dashed"
+    }
+}
+
+fn write_protocol(writer: &mut Write, ns: &ast::Namespace, protocol: &ast::Protocol) -> io::Result<()> {
+    let name = &ns.name.id;
+
+    // Each side of the protocol lives in its own process, so the actors
+    // for that side (this one plus any it manages) are clustered together.
+    for &(side, label) in &[("Parent", "Parent process"), ("Child", "Child process")] {
+        try!(write!(writer, "  subgraph \"cluster_{}{}\" {{\n", name, side));
+        try!(write!(writer, "    label=\"{} ({})\";\n", name, label));
+        try!(write!(writer, "    \"{}\" [shape=box];\n", actor_name(name, side)));
+        for managed in &protocol.manages {
+            try!(write!(writer, "    \"{}\" [shape=box, style=dotted];\n", actor_name(&managed.id, side)));
+        }
+        try!(write!(writer, "  }}\n"));
+
+        for managed in &protocol.manages {
+            try!(write!(writer, "  \"{}\" -> \"{}\" [label=\"manages\", style=dotted, arrowhead=odiamond];\n",
+                        actor_name(name, side), actor_name(&managed.id, side)));
+        }
+    }
+
+    for message in &protocol.messages {
+        let style = message_style(message);
+        if message.direction == ast::Direction::ToChild || message.direction == ast::Direction::ToParentOrChild {
+            try!(write!(writer, "  \"{}\" -> \"{}\" [label=\"{}\", {}];\n",
+                        actor_name(name, "Parent"), actor_name(name, "Child"), message.name.id, style));
+        }
+        if message.direction == ast::Direction::ToParent || message.direction == ast::Direction::ToParentOrChild {
+            try!(write!(writer, "  \"{}\" -> \"{}\" [label=\"{}\", {}];\n",
+                        actor_name(name, "Child"), actor_name(name, "Parent"), message.name.id, style));
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args : Vec<String> = env::args().collect();
+
+    let opts = get_options_parser();
+
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => { m },
+        Err(f) => { panic!(f.to_string()) },
+    };
+
+    let mut include_dirs = Vec::new();
+    for i in matches.opt_strs("I") {
+        include_dirs.push(PathBuf::from(i))
+    }
+
+    let mut file_names = Vec::new();
+    for f in &matches.free {
+        file_names.push(PathBuf::from(f));
+    }
+
+    let maybe_tus = parser::parse(&include_dirs, file_names);
+
+    if maybe_tus.is_none() {
+        println!("Specification could not be parsed.");
+        return;
+    }
+
+    let tus = maybe_tus.unwrap();
+
+    let mut writer: Box<Write> = match matches.opt_str("o") {
+        Some(fname) => Box::new(File::create(fname).unwrap()),
+        None => Box::new(io::stdout()),
+    };
+
+    write!(writer, "digraph G {{\n").unwrap();
+    write!(writer, "  compound=true;\n").unwrap();
+    for (_, tu) in tus {
+        if let Some((ns, protocol)) = tu.protocol {
+            write_protocol(&mut writer, &ns, &protocol).unwrap();
+        }
+    }
+    write!(writer, "}}\n").unwrap();
+}
@@ -0,0 +1,181 @@
+use std::env;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::path::Path;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+
+extern crate tools;
+extern crate getopts;
+extern crate rustc_serialize;
+
+use getopts::Options;
+use rustc_serialize::base64::{ToBase64, STANDARD};
+use rustc_serialize::json::{Json, ToJson};
+
+use tools::config;
+use tools::find_source_file;
+use tools::file_format::analysis::{read_analysis, read_source};
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("p", "prefix",
+                "Only export files under this path prefix",
+                "DIR");
+    opts
+}
+
+fn kythe_language(path: &str) -> &'static str {
+    let ext = match Path::new(path).extension() {
+        Some(ext) => ext.to_str().unwrap_or(""),
+        None => "",
+    };
+    match ext {
+        "c" | "cc" | "cpp" | "h" | "hh" | "mm" => "c++",
+        "js" | "jsm" | "sjs" => "javascript",
+        "rs" => "rust",
+        "java" => "java",
+        "idl" | "webidl" | "ipdl" | "ipdlh" => "idl",
+        _ => "",
+    }
+}
+
+// Maps the syntax of a definition to a Kythe node kind.
+fn kythe_kind(syntax: &[String]) -> Option<&'static str> {
+    match syntax.get(1).map(|s| s.as_str()) {
+        Some("function") => Some("function"),
+        Some("type") | Some("class") | Some("struct") | Some("union") => Some("record"),
+        Some("enum") => Some("sum"),
+        Some("field") | Some("variable") | Some("const") => Some("variable"),
+        Some("macro") => Some("macro"),
+        _ => None,
+    }
+}
+
+// Maps the kind of a source record to the edge from its anchor.
+fn kythe_edge(syntax: &[String]) -> &'static str {
+    match syntax.first().map(|s| s.as_str()) {
+        Some("def") | Some("decl") => "/kythe/edge/defines/binding",
+        Some("assign") => "/kythe/edge/ref/writes",
+        _ => "/kythe/edge/ref",
+    }
+}
+
+// Returns the byte offset at which each line of |text| starts.
+fn line_starts(text: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    for (i, b) in text.bytes().enumerate() {
+        if b == '\n' as u8 {
+            starts.push(i + 1);
+        }
+    }
+    starts
+}
+
+fn vname(corpus: &str, path: &str, signature: &str, language: &str) -> Json {
+    let mut obj = BTreeMap::new();
+    obj.insert("corpus".to_string(), corpus.to_json());
+    if !path.is_empty() {
+        obj.insert("path".to_string(), path.to_json());
+    }
+    if !signature.is_empty() {
+        obj.insert("signature".to_string(), signature.to_json());
+    }
+    if !language.is_empty() {
+        obj.insert("language".to_string(), language.to_json());
+    }
+    Json::Object(obj)
+}
+
+fn fact(source: &Json, name: &str, value: &[u8]) {
+    let mut obj = BTreeMap::new();
+    obj.insert("source".to_string(), source.clone());
+    obj.insert("fact_name".to_string(), name.to_json());
+    obj.insert("fact_value".to_string(), value.to_base64(STANDARD).to_json());
+    println!("{}", Json::Object(obj));
+}
+
+fn edge(source: &Json, kind: &str, target: &Json) {
+    let mut obj = BTreeMap::new();
+    obj.insert("source".to_string(), source.clone());
+    obj.insert("edge_kind".to_string(), kind.to_json());
+    obj.insert("target".to_string(), target.clone());
+    obj.insert("fact_name".to_string(), "/".to_json());
+    println!("{}", Json::Object(obj));
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 3 {
+        let brief = format!("Usage: {} [options] config.json tree_name filenames-file", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let prefix = matches.opt_str("prefix").unwrap_or("".to_string());
+
+    let file_paths: Vec<String> = BufReader::new(File::open(&matches.free[2]).unwrap())
+        .lines().map(|x| x.unwrap()).filter(|p| p.starts_with(&prefix)).collect();
+
+    // Symbol nodes are shared between files, so their kind is only
+    // written once.
+    let mut kinds_written = HashSet::new();
+    for path in &file_paths {
+        let source_fname = find_source_file(path, &tree_config.paths.files_path, &tree_config.paths.objdir_path);
+        let mut text = String::new();
+        match File::open(source_fname) {
+            Ok(mut f) => if f.read_to_string(&mut text).is_err() { continue },
+            Err(_) => continue,
+        }
+        let starts = line_starts(&text);
+        let language = kythe_language(path);
+
+        let file = vname(tree_name, path, "", "");
+        fact(&file, "/kythe/node/kind", b"file");
+        fact(&file, "/kythe/text", text.as_bytes());
+
+        let analysis_fname = format!("{}/analysis/{}", tree_config.paths.index_path, path);
+        for datum in read_analysis(&analysis_fname, &read_source) {
+            let line_start = match starts.get(datum.loc.lineno as usize - 1) {
+                Some(&start) => start,
+                None => continue,
+            };
+            let start = line_start + datum.loc.col_start as usize;
+            let end = line_start + datum.loc.col_end as usize;
+            let anchor = vname(tree_name, path, &format!("@{}:{}", start, end), language);
+            fact(&anchor, "/kythe/node/kind", b"anchor");
+            fact(&anchor, "/kythe/loc/start", start.to_string().as_bytes());
+            fact(&anchor, "/kythe/loc/end", end.to_string().as_bytes());
+            edge(&anchor, "/kythe/edge/childof", &file);
+
+            for piece in datum.data {
+                for sym in piece.sym.split(',') {
+                    let node = vname(tree_name, "", sym, language);
+                    edge(&anchor, kythe_edge(&piece.syntax), &node);
+                    if piece.syntax.first().map_or(false, |s| s == "def") && kinds_written.insert(sym.to_string()) {
+                        if let Some(kind) = kythe_kind(&piece.syntax) {
+                            fact(&node, "/kythe/node/kind", kind.as_bytes());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_line_starts() {
+    assert_eq!(line_starts("ab\n\ncd\n"), vec![0, 3, 4, 7]);
+    assert_eq!(kythe_edge(&["assign".to_string()]), "/kythe/edge/ref/writes");
+}
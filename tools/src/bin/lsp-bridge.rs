@@ -0,0 +1,361 @@
+use std::env;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+extern crate tools;
+extern crate rustc_serialize;
+
+use rustc_serialize::json::{Json, ToJson};
+
+use tools::config;
+use tools::config::TreeConfig;
+use tools::find_source_file;
+use tools::callgraph::{callee_map, find_callers, find_uses};
+use tools::file_format::analysis::{find_at, read_analysis, read_source, AnalysisSource, Location, WithLocation};
+use tools::file_format::crossref::CrossrefMap;
+
+// Answers Language Server Protocol requests from the index of a single
+// tree. Positions are taken to be byte columns, which is what the
+// analysis records use; this is only exact for ASCII lines.
+struct Bridge<'a> {
+    tree_config: &'a TreeConfig,
+    crossref: CrossrefMap,
+    sources: HashMap<String, Vec<WithLocation<Vec<AnalysisSource>>>>,
+    // Built on the first outgoing-calls request, since it reads the
+    // whole crossref.
+    callees: Option<HashMap<String, Vec<(String, String)>>>,
+}
+
+fn object(pairs: Vec<(&str, Json)>) -> Json {
+    let mut obj = BTreeMap::new();
+    for (key, value) in pairs {
+        obj.insert(key.to_string(), value);
+    }
+    Json::Object(obj)
+}
+
+fn position(line: u32, character: u32) -> Json {
+    object(vec![("line", line.to_json()), ("character", character.to_json())])
+}
+
+fn range(loc: &Location) -> Json {
+    object(vec![("start", position(loc.lineno - 1, loc.col_start)),
+                ("end", position(loc.lineno - 1, loc.col_end))])
+}
+
+// Maps the syntax of a definition to an LSP SymbolKind.
+fn symbol_kind(syntax: &[String]) -> u32 {
+    match syntax.get(1).map(|s| s.as_str()) {
+        Some("function") => 12,
+        Some("type") | Some("class") | Some("struct") => 5,
+        Some("field") => 8,
+        Some("enum") => 10,
+        Some("macro") => 14,
+        _ => 13,
+    }
+}
+
+// Source records have pretty names like "function mozilla::Foo::Bar".
+fn strip_kind(pretty: &str) -> &str {
+    match pretty.find(' ') {
+        Some(pos) => &pretty[pos + 1 ..],
+        None => pretty,
+    }
+}
+
+impl<'a> Bridge<'a> {
+    fn path_of(&self, uri: &str) -> Option<String> {
+        let fname = if uri.starts_with("file://") { &uri[7 ..] } else { uri };
+        let files = format!("{}/", self.tree_config.paths.files_path);
+        let objdir = format!("{}/", self.tree_config.paths.objdir_path);
+        if fname.starts_with(&files) {
+            Some(fname[files.len() ..].to_string())
+        } else if fname.starts_with(&objdir) {
+            Some(format!("__GENERATED__/{}", &fname[objdir.len() ..]))
+        } else {
+            None
+        }
+    }
+
+    fn uri_of(&self, path: &str) -> String {
+        let fname = find_source_file(path, &self.tree_config.paths.files_path, &self.tree_config.paths.objdir_path);
+        format!("file://{}", fname)
+    }
+
+    fn sources(&mut self, path: &str) -> &Vec<WithLocation<Vec<AnalysisSource>>> {
+        if !self.sources.contains_key(path) {
+            let analysis_fname = format!("{}/analysis/{}", self.tree_config.paths.index_path, path);
+            self.sources.insert(path.to_string(), read_analysis(&analysis_fname, &read_source));
+        }
+        &self.sources[path]
+    }
+
+    // Returns the symbols of the source record under the given position,
+    // with the record's pretty name and location.
+    fn symbols_at(&mut self, params: &Json) -> Vec<(String, String, Location)> {
+        let uri = params.find_path(&["textDocument", "uri"]).and_then(|u| u.as_string()).unwrap_or("");
+        let line = params.find_path(&["position", "line"]).and_then(|l| l.as_u64()).unwrap_or(0) as u32;
+        let character = params.find_path(&["position", "character"]).and_then(|c| c.as_u64()).unwrap_or(0) as u32;
+        let path = match self.path_of(uri) {
+            Some(path) => path,
+            None => return vec![],
+        };
+
+        let mut result = vec![];
+        for datum in find_at(self.sources(&path), line + 1, character) {
+            for piece in &datum.data {
+                for sym in piece.sym.split(',') {
+                    let loc = Location { lineno: datum.loc.lineno, col_start: datum.loc.col_start, col_end: datum.loc.col_end };
+                    result.push((sym.to_string(), piece.pretty.clone(), loc));
+                }
+            }
+        }
+        result
+    }
+
+    // Finds the exact columns of |sym| on a line from the analysis. The
+    // crossref only records line numbers.
+    fn locate(&mut self, path: &str, lineno: u64, sym: &str) -> Json {
+        let mut loc = Location { lineno: lineno as u32, col_start: 0, col_end: 0 };
+        for datum in self.sources(path) {
+            if datum.loc.lineno as u64 == lineno && datum.data.iter().any(|p| p.sym.split(',').any(|s| s == sym)) {
+                loc = Location { lineno: datum.loc.lineno, col_start: datum.loc.col_start, col_end: datum.loc.col_end };
+                break;
+            }
+        }
+        object(vec![("uri", self.uri_of(path).to_json()), ("range", range(&loc))])
+    }
+
+    fn locations(&mut self, sym: &str, kind: &str) -> Vec<Json> {
+        let lines = match self.crossref.lookup(sym) {
+            Some(entry) => {
+                entry.get(kind).iter().flat_map(|p| p.lines.iter().map(move |l| (p.path.clone(), l.lineno))).collect::<Vec<_>>()
+            },
+            None => return vec![],
+        };
+        lines.into_iter().map(|(path, lineno)| self.locate(&path, lineno, sym)).collect()
+    }
+
+    fn call_item(&mut self, sym: &str, pretty: &str) -> Option<Json> {
+        let def = self.crossref.lookup(sym).and_then(|e| e.first_def().map(|(p, l)| (p.to_string(), l)));
+        let (path, lineno) = match def {
+            Some(def) => def,
+            None => return None,
+        };
+        let location = self.locate(&path, lineno, sym);
+        let range = location.find("range").unwrap().clone();
+        Some(object(vec![("name", (if pretty.is_empty() { sym } else { pretty }).to_json()),
+                         ("kind", 12.to_json()),
+                         ("uri", location.find("uri").unwrap().clone()),
+                         ("range", range.clone()),
+                         ("selectionRange", range),
+                         ("data", sym.to_json())]))
+    }
+
+    fn call_ranges(&mut self, caller: &str, callee: &str) -> Json {
+        let uses = find_uses(&self.crossref, caller, callee);
+        let ranges = uses.into_iter().map(|(path, lineno)| {
+            self.locate(&path, lineno, callee).find("range").unwrap().clone()
+        }).collect::<Vec<_>>();
+        Json::Array(ranges)
+    }
+
+    fn handle(&mut self, method: &str, params: &Json) -> Result<Json, (i64, &'static str)> {
+        match method {
+            "initialize" => {
+                let capabilities = object(vec![("definitionProvider", true.to_json()),
+                                               ("referencesProvider", true.to_json()),
+                                               ("hoverProvider", true.to_json()),
+                                               ("documentSymbolProvider", true.to_json()),
+                                               ("callHierarchyProvider", true.to_json())]);
+                Ok(object(vec![("capabilities", capabilities),
+                               ("serverInfo", object(vec![("name", "searchfox-lsp-bridge".to_json())]))]))
+            },
+            "shutdown" => Ok(Json::Null),
+            "textDocument/definition" => {
+                let mut result = vec![];
+                for (sym, _, _) in self.symbols_at(params) {
+                    let mut defs = self.locations(&sym, "Definitions");
+                    if defs.is_empty() {
+                        defs = self.locations(&sym, "Declarations");
+                    }
+                    result.extend(defs);
+                }
+                Ok(Json::Array(result))
+            },
+            "textDocument/references" => {
+                let declarations = params.find_path(&["context", "includeDeclaration"]).and_then(|d| d.as_boolean()).unwrap_or(false);
+                let mut kinds = vec!["Uses", "Assignments"];
+                if declarations {
+                    kinds.extend(&["Definitions", "Declarations"]);
+                }
+                let mut result = vec![];
+                for (sym, _, _) in self.symbols_at(params) {
+                    for kind in &kinds {
+                        result.extend(self.locations(&sym, kind));
+                    }
+                }
+                Ok(Json::Array(result))
+            },
+            "textDocument/hover" => {
+                let syms = self.symbols_at(params);
+                let (_, pretty, loc) = match syms.into_iter().next() {
+                    Some(sym) => sym,
+                    None => return Ok(Json::Null),
+                };
+                let contents = object(vec![("kind", "markdown".to_json()),
+                                           ("value", format!("```\n{}\n```", pretty).to_json())]);
+                Ok(object(vec![("contents", contents), ("range", range(&loc))]))
+            },
+            "textDocument/documentSymbol" => {
+                let uri = params.find_path(&["textDocument", "uri"]).and_then(|u| u.as_string()).unwrap_or("").to_string();
+                let path = match self.path_of(&uri) {
+                    Some(path) => path,
+                    None => return Ok(Json::Array(vec![])),
+                };
+                let mut result = vec![];
+                for datum in self.sources(&path) {
+                    for piece in &datum.data {
+                        if piece.syntax.first().map_or(true, |s| s != "def") {
+                            continue;
+                        }
+                        let location = object(vec![("uri", uri.to_json()), ("range", range(&datum.loc))]);
+                        result.push(object(vec![("name", strip_kind(&piece.pretty).to_json()),
+                                                ("kind", symbol_kind(&piece.syntax).to_json()),
+                                                ("location", location)]));
+                    }
+                }
+                Ok(Json::Array(result))
+            },
+            "textDocument/prepareCallHierarchy" => {
+                let mut result = vec![];
+                for (sym, pretty, _) in self.symbols_at(params) {
+                    if let Some(item) = self.call_item(&sym, strip_kind(&pretty)) {
+                        result.push(item);
+                    }
+                }
+                Ok(Json::Array(result))
+            },
+            "callHierarchy/incomingCalls" => {
+                let sym = params.find_path(&["item", "data"]).and_then(|d| d.as_string()).unwrap_or("").to_string();
+                let mut result = vec![];
+                for (caller, pretty) in find_callers(&self.crossref, &sym) {
+                    let item = match self.call_item(&caller, &pretty) {
+                        Some(item) => item,
+                        None => continue,
+                    };
+                    let ranges = self.call_ranges(&caller, &sym);
+                    result.push(object(vec![("from", item), ("fromRanges", ranges)]));
+                }
+                Ok(Json::Array(result))
+            },
+            "callHierarchy/outgoingCalls" => {
+                let sym = params.find_path(&["item", "data"]).and_then(|d| d.as_string()).unwrap_or("").to_string();
+                if self.callees.is_none() {
+                    self.callees = Some(callee_map(&self.crossref));
+                }
+                let targets = self.callees.as_ref().unwrap().get(&sym).cloned().unwrap_or(vec![]);
+                let mut result = vec![];
+                for (callee, pretty) in targets {
+                    let item = match self.call_item(&callee, &pretty) {
+                        Some(item) => item,
+                        None => continue,
+                    };
+                    let ranges = self.call_ranges(&sym, &callee);
+                    result.push(object(vec![("to", item), ("fromRanges", ranges)]));
+                }
+                Ok(Json::Array(result))
+            },
+            _ => Err((-32601, "Method not found")),
+        }
+    }
+}
+
+// Reads one message, framed by a Content-Length header. Returns None at
+// the end of the input.
+fn read_message(input: &mut BufRead) -> Option<Json> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header).unwrap_or(0) == 0 {
+            return None;
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if header.to_lowercase().starts_with("content-length:") {
+            length = header[15 ..].trim().parse::<usize>().ok();
+        }
+    }
+
+    let mut body = vec![0; length.unwrap_or(0)];
+    if input.read_exact(&mut body).is_err() {
+        return None;
+    }
+    Some(Json::from_str(&String::from_utf8_lossy(&body)).unwrap_or(Json::Null))
+}
+
+fn write_message(output: &mut Write, message: &Json) {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    output.flush().unwrap();
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    if args.len() != 3 {
+        println!("Usage: {} config.json tree_name", args[0]);
+        return;
+    }
+
+    let cfg = config::load(&args[1], false);
+    let tree_name = &args[2];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+
+    let mut bridge = Bridge {
+        tree_config: tree_config,
+        crossref: CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path)),
+        sources: HashMap::new(),
+        callees: None,
+    };
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+    while let Some(message) = read_message(&mut input) {
+        let method = message.find("method").and_then(|m| m.as_string()).unwrap_or("").to_string();
+        if method == "exit" {
+            break;
+        }
+        // Notifications have no id and get no response.
+        let id = match message.find("id") {
+            Some(id) => id.clone(),
+            None => continue,
+        };
+        let params = message.find("params").cloned().unwrap_or(Json::Null);
+
+        let mut response = vec![("jsonrpc", "2.0".to_json()), ("id", id)];
+        match bridge.handle(&method, &params) {
+            Ok(result) => response.push(("result", result)),
+            Err((code, msg)) => {
+                response.push(("error", object(vec![("code", code.to_json()), ("message", msg.to_json())])));
+            }
+        }
+        write_message(&mut output, &object(response));
+    }
+}
+
+#[test]
+fn test_read_message() {
+    let input = "Content-Length: 17\r\n\r\n{\"method\":\"exit\"}Content-Length: 2\r\n\r\n{";
+    let mut reader = io::BufReader::new(input.as_bytes());
+    let message = read_message(&mut reader).unwrap();
+    assert_eq!(message.find("method").and_then(|m| m.as_string()), Some("exit"));
+    assert!(read_message(&mut reader).is_none());
+    assert_eq!(strip_kind("function mozilla::Foo"), "mozilla::Foo");
+}
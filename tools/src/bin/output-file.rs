@@ -1,4 +1,5 @@
 use std::env;
+use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
 use std::io;
@@ -12,10 +13,13 @@ use std::process::Command;
 
 extern crate tools;
 use tools::find_source_file;
-use tools::file_format::analysis::{read_analysis, read_source, read_jumps};
+use tools::blame;
+use tools::file_format::analysis::{read_analysis, read_source, read_target, read_jumps, AnalysisKind};
+use tools::contexts::{bug_references, line_contexts};
 use tools::format::format_file_data;
 use tools::config;
 use tools::languages;
+use tools::tokenize;
 use languages::FormatAs;
 
 use tools::output::{PanelItem, PanelSection};
@@ -57,6 +61,7 @@ fn main() {
         &None => (None, None),
     };
     let blame_commit_ref = match blame_commit { Some(ref bc) => Some(bc), None => None };
+    let mut commit_dates = HashMap::new();
 
     for path in fname_args {
         println!("File {}", path);
@@ -150,6 +155,57 @@ fn main() {
             vec![]
         };
 
+        // Record when each line was last changed so that searches can
+        // be restricted to recently modified code.
+        if let (&Some(ref git), Some(blame_commit)) = (&tree_config.git, blame_commit_ref) {
+            if !path.contains("__GENERATED__") {
+                if let Ok(dates) = blame::get_line_dates(git, blame_commit, path, &mut commit_dates) {
+                    let dates_fname = format!("{}/line-dates/{}", tree_config.paths.index_path, path);
+                    let mut dates_file = BufWriter::new(File::create(dates_fname).unwrap());
+                    for date in dates {
+                        write!(dates_file, "{}\n", date).unwrap();
+                    }
+                }
+            }
+        }
+
+        // Record the enclosing function, comment positions and statement
+        // of each line so that text searches can be restricted to them
+        // and show the rest of the statement.
+        if let FormatAs::FormatCLike(spec) = format {
+            let mut defs = vec![];
+            for datum in read_analysis(&analysis_fname, &read_target) {
+                for piece in datum.data {
+                    if piece.kind == AnalysisKind::Def {
+                        defs.push((datum.loc.lineno, datum.loc.col_start, piece.pretty));
+                    }
+                }
+            }
+            let tokens = tokenize::tokenize_c_like(&input, spec);
+            let contexts_fname = format!("{}/line-contexts/{}", tree_config.paths.index_path, path);
+            let mut contexts_file = BufWriter::new(File::create(contexts_fname).unwrap());
+            for context in line_contexts(&input, &tokens, &defs) {
+                let comments = context.comments.iter().map(|&(start, end)| format!("{}-{}", start, end))
+                    .collect::<Vec<_>>();
+                write!(contexts_file, "{}\t{}\t{}-{}\n", context.function, comments.join(","),
+                       context.statement.0, context.statement.1).unwrap();
+            }
+
+            // Bugs mentioned in comments, for the bug: search term. The
+            // per-file lists are gathered into one index by
+            // build-bug-index.py.
+            let bugs = bug_references(&input, &tokens);
+            if !bugs.is_empty() {
+                let lines = input.lines().collect::<Vec<_>>();
+                let bugs_fname = format!("{}/bug-refs/{}", tree_config.paths.index_path, path);
+                let mut bugs_file = BufWriter::new(File::create(bugs_fname).unwrap());
+                for (lineno, bug) in bugs {
+                    let line = lines.get(lineno as usize - 1).map_or("", |l| l.trim());
+                    write!(bugs_file, "{}\t{}\t{}\n", bug, lineno, line).unwrap();
+                }
+            }
+        }
+
         format_file_data(&cfg,
                          tree_name,
                          &panel,
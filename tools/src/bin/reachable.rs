@@ -0,0 +1,171 @@
+use std::cmp;
+use std::env;
+use std::process;
+
+extern crate tools;
+extern crate getopts;
+
+use getopts::Options;
+
+use tools::config;
+use tools::callgraph::{collect, collect_ranked, entry_point_set, estimate, estimate_warning, find_uses, fit_depth, follow_rename, load_callers, use_count};
+use tools::callgraph::{BoringSymbols, Budgeted, Callers, Pruned};
+use tools::file_format::crossref::CrossrefMap;
+use tools::file_format::file_classes::FileClasses;
+use tools::file_format::renames::read_renames;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("s", "set",
+                "Only consider entry points from the named set",
+                "NAME");
+    opts.optopt("d", "max-depth",
+                "Maximum number of caller hops to follow (default 8)",
+                "DEPTH");
+    opts.optopt("n", "node-limit",
+                "Maximum number of callers to examine (default 1024)",
+                "COUNT");
+    opts.optmulti("x", "exclude",
+                  "Leave out callers defined in files of this class (test, build, generated or prod)",
+                  "CLASS");
+    opts.optflag("", "estimate",
+                 "Only print the estimated number of callers within each number of hops");
+    opts.optflag("", "fit-depth",
+                 "Lower the maximum depth if the walk is expected to exceed the node limit");
+    opts.optmulti("", "exclude-sym",
+                  "Leave out symbols matching this regexp (repeatable)",
+                  "REGEXP");
+    opts.optmulti("", "exclude-pretty",
+                  "Leave out symbols whose pretty name matches this regexp (repeatable)",
+                  "REGEXP");
+    opts.optopt("", "max-lookups",
+                "Stop walking after looking up the callers of this many symbols",
+                "COUNT");
+    opts.optopt("", "max-millis",
+                "Stop walking after this many milliseconds",
+                "MS");
+    opts.optflag("", "rank",
+                 "When the node limit is reached, keep the most used callers instead of the nearest ones");
+    opts
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 3 {
+        let brief = format!("Usage: {} [options] config.json tree_name symbol", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let max_depth = matches.opt_str("max-depth").map_or(tree_config.paths.graph_max_depth.unwrap_or(8), |d| d.parse::<u32>().unwrap());
+    let node_limit = matches.opt_str("node-limit").map_or(tree_config.paths.graph_node_limit.unwrap_or(1024), |n| n.parse::<usize>().unwrap());
+    let set = matches.opt_str("set");
+    let mut sym = matches.free[2].to_string();
+
+    if tree_config.paths.entry_points.is_none() {
+        println!("Tree {} has no entry_points in its config.", tree_name);
+        process::exit(2);
+    }
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+    if let Some((renamed, note)) = follow_rename(&sym, &crossref, &read_renames(&tree_config.paths.index_path)) {
+        println!("{}", note);
+        sym = renamed;
+    }
+    let roots = [(sym.to_string(), "".to_string())];
+    let boring = match BoringSymbols::from_options(tree_config, &matches) {
+        Ok(boring) => boring,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(2);
+        },
+    };
+    let classes = FileClasses::new(&tree_config.paths.index_path);
+    let exclude = matches.opt_strs("exclude");
+    let callers_index = load_callers(&tree_config.paths.index_path);
+    let users = Callers { crossref: &crossref, callers: callers_index.as_ref() };
+    let callers = Pruned { inner: &users, crossref: &crossref, classes: &classes, exclude: &exclude };
+    let totals = estimate(&callers, &roots, &boring, max_depth, 16);
+    if matches.opt_present("estimate") {
+        println!("Estimated symbols within each number of hops:");
+        for (depth, total) in totals.iter().enumerate() {
+            println!("  {}\t{}", depth, total);
+        }
+        return;
+    }
+    let mut max_depth = max_depth;
+    if matches.opt_present("fit-depth") {
+        let fit = cmp::max(fit_depth(&totals, node_limit).unwrap_or(0), 1);
+        if fit < max_depth {
+            println!("Lowering the maximum depth to {} to stay within the node limit.", fit);
+            println!("");
+            max_depth = fit;
+        }
+    } else if let Some(warning) = estimate_warning(&totals, node_limit) {
+        println!("{}", warning);
+        println!("");
+    }
+    let max_lookups = matches.opt_str("max-lookups").map(|n| n.parse::<usize>().unwrap());
+    let max_millis = matches.opt_str("max-millis").map(|n| n.parse::<u64>().unwrap());
+    let budgeted = Budgeted::new(&callers, max_lookups, max_millis);
+    let graph = if matches.opt_present("rank") {
+        collect_ranked(&budgeted, &roots, &boring, max_depth, node_limit, &|sym| use_count(&crossref, sym))
+    } else {
+        collect(&budgeted, &roots, &boring, max_depth, node_limit)
+    };
+
+    // Unless --rank was given, nodes are added breadth-first, so the
+    // nearest entry points come first.
+    let mut found = 0;
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let set_name = match entry_point_set(tree_config, set.as_ref().map(|s| s.as_str()), &node.sym, &node.pretty) {
+            Some(set_name) => set_name,
+            None => continue,
+        };
+        if found == 0 {
+            println!("{} is reachable from:", sym);
+        }
+        found += 1;
+
+        let name = if node.pretty.is_empty() { &node.sym } else { &node.pretty };
+        println!("  {} ({})\t{} hops", name, set_name, node.depth);
+        for pair in graph.path_to_root(i).windows(2) {
+            let (caller, callee) = (&graph.nodes[pair[0]], &graph.nodes[pair[1]]);
+            let location = match find_uses(&crossref, &caller.sym, &callee.sym).into_iter().next() {
+                Some((path, lineno)) => format!("{}:{}", path, lineno),
+                None => "(unknown location)".to_string(),
+            };
+            let name = if callee.pretty.is_empty() { &callee.sym } else { &callee.pretty };
+            println!("    -> uses {}\tat {}", name, location);
+        }
+    }
+
+    if found == 0 {
+        println!("No entry point reaches {} within {} hops.", sym, max_depth);
+    }
+    if graph.truncated {
+        println!("");
+        println!("Node limit of {} reached; some callers were not examined.", node_limit);
+        println!("{}", graph.limit_hint(node_limit).unwrap());
+    }
+    if let Some(note) = budgeted.exhausted_note() {
+        println!("");
+        println!("{}", note);
+    }
+    if let Some(note) = graph.filter_note() {
+        println!("");
+        println!("{}", note);
+    }
+    if found == 0 {
+        process::exit(1);
+    }
+}
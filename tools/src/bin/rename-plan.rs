@@ -0,0 +1,195 @@
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+extern crate tools;
+extern crate rustc_serialize;
+
+use rustc_serialize::json::{Json, ToJson};
+
+use tools::config;
+use tools::find_source_file;
+use tools::languages;
+use tools::languages::FormatAs;
+use tools::tokenize;
+use tools::tokenize::TokenKind;
+use tools::file_format::analysis::{read_analysis, read_source};
+use tools::file_format::crossref::CrossrefMap;
+
+fn is_ident(c: u8) -> bool {
+    c == '_' as u8 || (c as char).is_alphanumeric()
+}
+
+// Returns the identifier starting at column |col| of |line|.
+fn ident_at(line: &str, col: usize) -> &str {
+    let bytes = line.as_bytes();
+    let mut end = col;
+    while end < bytes.len() && is_ident(bytes[end]) {
+        end += 1;
+    }
+    &line[col .. end]
+}
+
+// Returns the columns at which |word| appears in |text| as a whole word.
+fn word_matches(text: &str, word: &str) -> Vec<usize> {
+    let bytes = text.as_bytes();
+    let mut result = vec![];
+    let mut start = 0;
+    while let Some(pos) = text[start ..].find(word) {
+        let pos = start + pos;
+        let end = pos + word.len();
+        if (pos == 0 || !is_ident(bytes[pos - 1])) && (end == bytes.len() || !is_ident(bytes[end])) {
+            result.push(pos);
+        }
+        start = end;
+    }
+    result
+}
+
+fn edit(path: &str, lineno: u32, col: usize, old: &str, new: &str, kind: &str) -> Json {
+    let mut obj = BTreeMap::new();
+    obj.insert("path".to_string(), path.to_json());
+    obj.insert("line".to_string(), lineno.to_json());
+    obj.insert("column".to_string(), col.to_json());
+    obj.insert("old".to_string(), old.to_json());
+    obj.insert("new".to_string(), new.to_json());
+    obj.insert("kind".to_string(), kind.to_json());
+    Json::Object(obj)
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    if args.len() != 5 {
+        println!("Usage: {} config.json tree_name symbol new-name", args[0]);
+        return;
+    }
+
+    let cfg = config::load(&args[1], false);
+    let tree_name = &args[2];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let sym = &args[3];
+    let new_name = &args[4];
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+    let entry = match crossref.lookup(sym) {
+        Some(entry) => entry,
+        None => {
+            println!("Symbol {} not found.", sym);
+            return;
+        }
+    };
+
+    let mut paths = BTreeSet::new();
+    for (_, kind_paths) in &entry.kinds {
+        for path in kind_paths {
+            paths.insert(path.path.clone());
+        }
+    }
+
+    // Every source record for the symbol marks an identifier to rename.
+    // Records are collected per file first so that the old name can be
+    // taken from the definition.
+    let mut sources = BTreeMap::new();
+    let mut old_name = None;
+    for path in &paths {
+        let source_fname = find_source_file(path, &tree_config.paths.files_path, &tree_config.paths.objdir_path);
+        let mut input = String::new();
+        match File::open(source_fname) {
+            Ok(mut f) => if f.read_to_string(&mut input).is_err() { continue },
+            Err(_) => continue,
+        }
+        let lines = input.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+
+        let analysis_fname = format!("{}/analysis/{}", tree_config.paths.index_path, path);
+        let mut records = BTreeMap::new();
+        for datum in read_analysis(&analysis_fname, &read_source) {
+            for piece in datum.data {
+                if !piece.sym.split(',').any(|s| s == sym) {
+                    continue;
+                }
+                let line = match lines.get(datum.loc.lineno as usize - 1) {
+                    Some(line) => line,
+                    None => continue,
+                };
+                let col = datum.loc.col_start as usize;
+                if col > line.len() {
+                    continue;
+                }
+                let text = ident_at(line, col).to_string();
+                let kind = piece.syntax.first().cloned().unwrap_or("".to_string());
+                if old_name.is_none() && kind == "def" {
+                    old_name = Some(text.clone());
+                }
+                records.insert((datum.loc.lineno, col), (text, kind));
+            }
+        }
+        sources.insert(path.clone(), (input, records));
+    }
+
+    let old_name = match old_name.or_else(|| {
+        sources.values().flat_map(|s| s.1.values()).map(|r| r.0.clone()).find(|t| !t.is_empty())
+    }) {
+        Some(name) => name,
+        None => {
+            println!("No source records found for {}.", sym);
+            return;
+        }
+    };
+
+    let mut edits = vec![];
+    let mut heuristic = vec![];
+    for (path, &(ref input, ref records)) in &sources {
+        for (&(lineno, col), &(ref text, ref kind)) in records {
+            let kind = if kind.is_empty() { "use" } else { kind };
+            if *text == old_name {
+                edits.push(edit(path, lineno, col, text, new_name, kind));
+            } else {
+                // Destructors, macros, and the like may not spell the
+                // name out at the recorded location.
+                heuristic.push(edit(path, lineno, col, text, new_name, "mismatched-text"));
+            }
+        }
+
+        // Mentions inside strings and comments can't be tied to the
+        // symbol, so they are only suggestions.
+        let spec = match languages::select_formatting(path) {
+            FormatAs::FormatCLike(spec) => spec,
+            _ => continue,
+        };
+        let mut line_start = 0;
+        let mut lineno = 1;
+        for token in tokenize::tokenize_c_like(input, spec) {
+            let kind = match token.kind {
+                TokenKind::Newline => {
+                    lineno += 1;
+                    line_start = token.end;
+                    continue;
+                },
+                TokenKind::StringLiteral => "string-literal",
+                TokenKind::Comment => "comment",
+                _ => continue,
+            };
+            let text = &input[token.start .. token.end];
+            for pos in word_matches(text, &old_name) {
+                let col = token.start - line_start + pos;
+                heuristic.push(edit(path, lineno, col, &old_name, new_name, kind));
+            }
+        }
+    }
+
+    let mut plan = BTreeMap::new();
+    plan.insert("symbol".to_string(), sym.to_json());
+    plan.insert("old".to_string(), old_name.to_json());
+    plan.insert("new".to_string(), new_name.to_json());
+    plan.insert("edits".to_string(), Json::Array(edits));
+    plan.insert("heuristic".to_string(), Json::Array(heuristic));
+    println!("{}", Json::Object(plan).pretty());
+}
+
+#[test]
+fn test_word_matches() {
+    assert_eq!(word_matches("Foo FooBar (Foo) _Foo Foo", "Foo"), vec![0, 12, 22]);
+    assert_eq!(ident_at("  mFoo->Bar()", 2), "mFoo");
+}
@@ -0,0 +1,97 @@
+use std::env;
+use std::io;
+use std::io::BufRead;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+extern crate tools;
+extern crate rustc_serialize;
+
+use rustc_serialize::json::{Json, ToJson};
+
+use tools::config;
+use tools::file_format::analysis::{find_at, read_analysis, read_source};
+use tools::file_format::crossref::CrossrefMap;
+
+// Parses "path:line:column", where the path itself may contain colons.
+fn parse_position(input: &str) -> Option<(&str, u32, u32)> {
+    let mut pieces = input.rsplitn(3, ':');
+    let col = pieces.next().and_then(|c| c.parse::<u32>().ok());
+    let line = pieces.next().and_then(|l| l.parse::<u32>().ok());
+    let path = pieces.next();
+    match (path, line, col) {
+        (Some(path), Some(line), Some(col)) => Some((path, line, col)),
+        _ => None,
+    }
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    if args.len() != 3 {
+        println!("Usage: {} config.json tree_name < positions", args[0]);
+        return;
+    }
+
+    let cfg = config::load(&args[1], false);
+    let tree_name = &args[2];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+
+    // Batches tend to hit the same files many times.
+    let mut analyses = HashMap::new();
+
+    let stdin = io::stdin();
+    for input in stdin.lock().lines() {
+        let input = input.unwrap();
+        let input = input.trim();
+        if input.is_empty() {
+            continue;
+        }
+
+        let mut obj = BTreeMap::new();
+        obj.insert("position".to_string(), input.to_json());
+        let (path, lineno, col) = match parse_position(input) {
+            Some(position) => position,
+            None => {
+                obj.insert("error".to_string(), "expected path:line:column".to_json());
+                println!("{}", Json::Object(obj));
+                continue;
+            }
+        };
+
+        let analysis = analyses.entry(path.to_string()).or_insert_with(|| {
+            read_analysis(&format!("{}/analysis/{}", tree_config.paths.index_path, path), &read_source)
+        });
+
+        let mut symbols = vec![];
+        for datum in find_at(analysis, lineno, col) {
+            for piece in &datum.data {
+                for sym in piece.sym.split(',') {
+                    let mut definitions = vec![];
+                    if let Some(entry) = crossref.lookup(sym) {
+                        for def in entry.get("Definitions") {
+                            for line in &def.lines {
+                                definitions.push(format!("{}:{}", def.path, line.lineno));
+                            }
+                        }
+                    }
+                    let mut sym_obj = BTreeMap::new();
+                    sym_obj.insert("symbol".to_string(), sym.to_json());
+                    sym_obj.insert("pretty".to_string(), piece.pretty.to_json());
+                    sym_obj.insert("definitions".to_string(), definitions.to_json());
+                    symbols.push(Json::Object(sym_obj));
+                }
+            }
+        }
+        obj.insert("symbols".to_string(), Json::Array(symbols));
+        println!("{}", Json::Object(obj));
+    }
+}
+
+#[test]
+fn test_parse_position() {
+    assert_eq!(parse_position("dom/base/nsINode.cpp:12:4"), Some(("dom/base/nsINode.cpp", 12, 4)));
+    assert_eq!(parse_position("c:/odd:1:0"), Some(("c:/odd", 1, 0)));
+    assert_eq!(parse_position("a.cpp:12"), None);
+}
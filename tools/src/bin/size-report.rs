@@ -0,0 +1,163 @@
+use std::env;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+extern crate tools;
+extern crate getopts;
+extern crate rustc_serialize;
+
+use getopts::Options;
+use rustc_serialize::json::{Json, ToJson};
+
+use tools::config;
+use tools::find_source_file;
+use tools::file_format::analysis::{read_analysis, read_source};
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("d", "depth",
+                "Number of directory levels to report (default 2)",
+                "DEPTH");
+    opts.optflag("m", "markdown",
+                 "Print the report as Markdown instead of JSON");
+    opts
+}
+
+#[derive(Default)]
+struct Stats {
+    files: u64,
+    lines: u64,
+    generated_files: u64,
+    generated_lines: u64,
+    // Number of definitions of each kind of symbol ("function", "type",
+    // etc., from the syntax of the source records).
+    symbols: BTreeMap<String, u64>,
+}
+
+impl Stats {
+    fn add(&mut self, other: &Stats) {
+        self.files += other.files;
+        self.lines += other.lines;
+        self.generated_files += other.generated_files;
+        self.generated_lines += other.generated_lines;
+        for (kind, count) in &other.symbols {
+            *self.symbols.entry(kind.clone()).or_insert(0) += *count;
+        }
+    }
+}
+
+fn file_stats(tree_config: &config::TreeConfig, path: &str) -> Stats {
+    let mut stats = Stats::default();
+
+    let source_fname = find_source_file(path, &tree_config.paths.files_path, &tree_config.paths.objdir_path);
+    let lines = match File::open(source_fname) {
+        Ok(f) => BufReader::new(f).split('\n' as u8).count() as u64,
+        Err(_) => 0,
+    };
+    if path.starts_with("__GENERATED__") {
+        stats.generated_files = 1;
+        stats.generated_lines = lines;
+    }
+    stats.files = 1;
+    stats.lines = lines;
+
+    let analysis_fname = format!("{}/analysis/{}", tree_config.paths.index_path, path);
+    let mut seen = BTreeSet::new();
+    for datum in read_analysis(&analysis_fname, &read_source) {
+        for piece in datum.data {
+            if piece.syntax.first().map_or(true, |s| s != "def") || !seen.insert(piece.sym.clone()) {
+                continue;
+            }
+            let kind = piece.syntax.get(1).cloned().unwrap_or("other".to_string());
+            *stats.symbols.entry(kind).or_insert(0) += 1;
+        }
+    }
+
+    stats
+}
+
+// Builds the JSON for directory |dir| and, recursively, the
+// directories below it.
+fn to_json(dir: &str, dirs: &BTreeMap<String, Stats>) -> Json {
+    let stats = &dirs[dir];
+    let mut obj = BTreeMap::new();
+    obj.insert("path".to_string(), dir.to_json());
+    obj.insert("files".to_string(), stats.files.to_json());
+    obj.insert("lines".to_string(), stats.lines.to_json());
+    obj.insert("generated_files".to_string(), stats.generated_files.to_json());
+    obj.insert("generated_lines".to_string(), stats.generated_lines.to_json());
+    obj.insert("symbols".to_string(), stats.symbols.to_json());
+
+    let children = children(dir, dirs).iter().map(|child| to_json(child, dirs)).collect::<Vec<_>>();
+    obj.insert("children".to_string(), Json::Array(children));
+    Json::Object(obj)
+}
+
+fn children<'a>(dir: &str, dirs: &'a BTreeMap<String, Stats>) -> Vec<&'a str> {
+    dirs.keys().filter(|d| {
+        if dir.is_empty() {
+            !d.is_empty() && !d.contains('/')
+        } else {
+            d.starts_with(dir) && d[dir.len() ..].starts_with('/') && !d[dir.len() + 1 ..].contains('/')
+        }
+    }).map(|d| d.as_str()).collect()
+}
+
+fn print_markdown(dir: &str, dirs: &BTreeMap<String, Stats>, level: usize) {
+    let stats = &dirs[dir];
+    let symbols = stats.symbols.iter().map(|(kind, count)| format!("{} {}", count, kind)).collect::<Vec<_>>();
+    println!("| {}{} | {} | {} | {} | {} |",
+             "&nbsp;&nbsp;".repeat(level), if dir.is_empty() { "/" } else { dir },
+             stats.files, stats.lines, stats.generated_lines, symbols.join(", "));
+    for child in children(dir, dirs) {
+        print_markdown(child, dirs, level + 1);
+    }
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 3 {
+        let brief = format!("Usage: {} [options] config.json tree_name filenames-file", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let depth = matches.opt_str("depth").map_or(2, |d| d.parse::<usize>().unwrap());
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+
+    let file_paths: Vec<String> = BufReader::new(File::open(&matches.free[2]).unwrap())
+        .lines().map(|x| x.unwrap()).collect();
+
+    // Stats are added to the root and to each enclosing directory up
+    // to |depth| levels down.
+    let mut dirs = BTreeMap::new();
+    dirs.insert("".to_string(), Stats::default());
+    for path in &file_paths {
+        let stats = file_stats(tree_config, path);
+        let mut elts = path.split('/').collect::<Vec<_>>();
+        elts.pop();
+        for i in 0 .. elts.len().min(depth) + 1 {
+            dirs.entry(elts[.. i].join("/")).or_insert(Stats::default()).add(&stats);
+        }
+    }
+
+    if matches.opt_present("markdown") {
+        println!("| Directory | Files | Lines | Generated lines | Definitions |");
+        println!("| --- | ---: | ---: | ---: | --- |");
+        print_markdown("", &dirs, 0);
+    } else {
+        println!("{}", to_json("", &dirs).pretty());
+    }
+}
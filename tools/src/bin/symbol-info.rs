@@ -0,0 +1,188 @@
+use std::env;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+extern crate tools;
+extern crate getopts;
+extern crate rustc_serialize;
+
+use getopts::Options;
+use rustc_serialize::json::{Json, ToJson};
+
+use tools::config;
+use tools::file_format::crossref::{CrossrefEntry, CrossrefMap};
+use tools::file_format::doc_summaries::read_doc_summaries;
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optflag("m", "markdown",
+                 "Print the summary as Markdown instead of JSON");
+    opts
+}
+
+// The jumps file is the only place the pretty name of a symbol is
+// recorded on its own, so look for it there.
+fn find_pretty(jumps_fname: &str, sym: &str) -> Option<String> {
+    let prefix = format!("[{},", sym.to_json());
+    for line in BufReader::new(File::open(jumps_fname).unwrap()).lines() {
+        let line = line.unwrap();
+        if !line.starts_with(&prefix) {
+            continue;
+        }
+        return Json::from_str(&line).ok()
+            .and_then(|j| j.as_array().and_then(|a| a.get(3).and_then(|p| p.as_string().map(|p| p.to_string()))));
+    }
+    None
+}
+
+// Returns the Bugzilla product and component owning |path|, from the
+// bug-components file written by find-bug-components.py.
+fn find_component(components_fname: &str, path: &str) -> Option<(String, String)> {
+    let file = match File::open(components_fname) {
+        Ok(file) => file,
+        Err(_) => return None,
+    };
+    let prefix = format!("[{},", path.to_json());
+    for line in BufReader::new(file).lines() {
+        let line = line.unwrap();
+        if !line.starts_with(&prefix) {
+            continue;
+        }
+        let json = match Json::from_str(&line) {
+            Ok(json) => json,
+            Err(_) => return None,
+        };
+        let get = |i: usize| json.as_array().and_then(|a| a.get(i)).and_then(|v| v.as_string()).unwrap_or("").to_string();
+        return Some((get(1), get(2)));
+    }
+    None
+}
+
+fn locations(entry: &CrossrefEntry, kind: &str) -> Vec<String> {
+    let mut result = vec![];
+    for path in entry.get(kind) {
+        for line in &path.lines {
+            result.push(format!("{}:{}", path.path, line.lineno));
+        }
+    }
+    result
+}
+
+fn summarize(sym: &str, pretty: Option<String>, doc: Option<String>,
+             component: Option<(String, String)>, entry: &CrossrefEntry) -> Json {
+    let mut obj = BTreeMap::new();
+    obj.insert("symbol".to_string(), sym.to_json());
+    obj.insert("pretty".to_string(), pretty.to_json());
+    obj.insert("doc_summary".to_string(), doc.to_json());
+    obj.insert("component".to_string(), component.map(|(p, c)| vec![p, c]).to_json());
+    obj.insert("definitions".to_string(), locations(entry, "Definitions").to_json());
+    obj.insert("declarations".to_string(), locations(entry, "Declarations").to_json());
+    obj.insert("idl".to_string(), locations(entry, "IDL").to_json());
+
+    let mut counts = BTreeMap::new();
+    for (kind, paths) in &entry.kinds {
+        let lines = paths.iter().map(|p| p.lines.len()).sum::<usize>();
+        counts.insert(kind.clone(), lines.to_json());
+    }
+    obj.insert("counts".to_string(), Json::Object(counts));
+
+    let uses = entry.get("Uses");
+    let mut callers = BTreeSet::new();
+    for path in uses {
+        for line in &path.lines {
+            let caller = line.contextsym.split(',').next().unwrap();
+            if !caller.is_empty() {
+                callers.insert(caller);
+            }
+        }
+    }
+    obj.insert("use_files".to_string(), uses.len().to_json());
+    obj.insert("callers".to_string(), callers.len().to_json());
+
+    Json::Object(obj)
+}
+
+fn print_markdown(summary: &Json) {
+    let get_str = |key: &str| summary.find(key).and_then(|v| v.as_string()).unwrap_or("").to_string();
+    let symbol = get_str("symbol");
+    let pretty = get_str("pretty");
+    println!("# {}", if pretty.is_empty() { &symbol } else { &pretty });
+    println!("");
+    println!("Symbol: `{}`", symbol);
+    let doc = get_str("doc_summary");
+    if !doc.is_empty() {
+        println!("");
+        println!("{}", doc);
+    }
+    if let Some(component) = summary.find("component").and_then(|c| c.as_array()) {
+        let names = component.iter().map(|c| c.as_string().unwrap_or("")).collect::<Vec<_>>();
+        println!("");
+        println!("Component: {}", names.join(" :: "));
+    }
+    for &(key, title) in &[("definitions", "Definitions"), ("declarations", "Declarations"), ("idl", "IDL")] {
+        let locs = summary.find(key).and_then(|v| v.as_array()).unwrap();
+        if locs.is_empty() {
+            continue;
+        }
+        println!("");
+        println!("## {}", title);
+        println!("");
+        for loc in locs {
+            println!("- {}", loc.as_string().unwrap());
+        }
+    }
+    println!("");
+    println!("## Counts");
+    println!("");
+    for (kind, count) in summary.find("counts").and_then(|c| c.as_object()).unwrap() {
+        println!("- {}: {}", kind, count);
+    }
+    println!("- Files with uses: {}", summary.find("use_files").unwrap());
+    println!("- Distinct callers: {}", summary.find("callers").unwrap());
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 3 {
+        let brief = format!("Usage: {} [options] config.json tree_name symbol", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let sym = &matches.free[2];
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+    let entry = match crossref.lookup(sym) {
+        Some(entry) => entry,
+        None => {
+            println!("Symbol {} not found.", sym);
+            return;
+        }
+    };
+    let pretty = find_pretty(&format!("{}/jumps", tree_config.paths.index_path), sym);
+
+    let component = entry.first_def().and_then(|(path, _)| {
+        find_component(&format!("{}/bug-components", tree_config.paths.index_path), path)
+    });
+
+    let doc = read_doc_summaries(&format!("{}/doc-summaries", tree_config.paths.index_path)).remove(sym);
+
+    let summary = summarize(sym, pretty, doc, component, &entry);
+    if matches.opt_present("markdown") {
+        print_markdown(&summary);
+    } else {
+        println!("{}", summary.pretty());
+    }
+}
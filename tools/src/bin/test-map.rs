@@ -0,0 +1,121 @@
+use std::env;
+use std::collections::BTreeMap;
+use std::process;
+
+extern crate tools;
+extern crate getopts;
+
+use getopts::Options;
+
+use tools::config;
+use tools::callgraph::{collect_callers, load_callers, BoringSymbols};
+use tools::file_format::crossref::CrossrefMap;
+use tools::file_format::file_classes::{looks_like_test, FileClasses};
+
+fn get_options_parser() -> Options {
+    let mut opts = Options::new();
+    opts.optopt("d", "max-depth",
+                "Maximum number of caller hops between a test and the symbol (default 0, direct uses only)",
+                "DEPTH");
+    opts.optopt("n", "node-limit",
+                "Maximum number of callers to examine (default 256)",
+                "COUNT");
+    opts.optmulti("", "exclude-sym",
+                  "Leave out symbols matching this regexp (repeatable)",
+                  "REGEXP");
+    opts.optmulti("", "exclude-pretty",
+                  "Leave out symbols whose pretty name matches this regexp (repeatable)",
+                  "REGEXP");
+    opts
+}
+
+// The file classes recorded at index time already take test_dirs into
+// account; older indexes fall back to it.
+fn is_test(classes: &FileClasses, test_dirs: &Option<Vec<String>>, path: &str) -> bool {
+    if classes.present() {
+        return classes.class_of(path) == "test";
+    }
+    match test_dirs {
+        &Some(ref dirs) => dirs.iter().any(|dir| path.starts_with(dir.as_str())),
+        &None => looks_like_test(path),
+    }
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    let opts = get_options_parser();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => panic!(f.to_string()),
+    };
+
+    if matches.free.len() != 3 {
+        let brief = format!("Usage: {} [options] config.json tree_name symbol", args[0]);
+        print!("{}", opts.usage(&brief));
+        return;
+    }
+
+    let cfg = config::load(&matches.free[0], false);
+    let tree_name = &matches.free[1];
+    let tree_config = cfg.trees.get(tree_name).unwrap();
+    let max_depth = matches.opt_str("max-depth").map_or(0, |d| d.parse::<u32>().unwrap());
+    let node_limit = matches.opt_str("node-limit").map_or(tree_config.paths.graph_node_limit.unwrap_or(256), |n| n.parse::<usize>().unwrap());
+    let sym = &matches.free[2];
+
+    let crossref = CrossrefMap::new(&format!("{}/crossref", tree_config.paths.index_path));
+    let classes = FileClasses::new(&tree_config.paths.index_path);
+    let callers = load_callers(&tree_config.paths.index_path);
+    let boring = match BoringSymbols::from_options(tree_config, &matches) {
+        Ok(boring) => boring,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(2);
+        },
+    };
+    let graph = collect_callers(&crossref, callers.as_ref(), &[(sym.to_string(), "".to_string())], &boring, max_depth, node_limit);
+
+    // Map from test file to the closest symbol it uses on the way to
+    // |sym|, as (depth, name).
+    let mut tests = BTreeMap::new();
+    for node in &graph.nodes {
+        let entry = match crossref.lookup(&node.sym) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        for path in entry.get("Uses") {
+            if !is_test(&classes, &tree_config.paths.test_dirs, &path.path) {
+                continue;
+            }
+            let name = if node.pretty.is_empty() { node.sym.clone() } else { node.pretty.clone() };
+            let best = tests.entry(path.path.clone()).or_insert((node.depth, name.clone()));
+            if node.depth < best.0 {
+                *best = (node.depth, name);
+            }
+        }
+    }
+
+    let mut tests = tests.into_iter().collect::<Vec<_>>();
+    tests.sort_by(|a, b| (a.1).0.cmp(&(b.1).0).then(a.0.cmp(&b.0)));
+    for (path, (depth, name)) in tests {
+        println!("{}\t{}\t{}", path, depth, name);
+    }
+
+    if graph.truncated {
+        println!("");
+        println!("Node limit of {} reached; some tests may be missing.", node_limit);
+        println!("{}", graph.limit_hint(node_limit).unwrap());
+    }
+    if let Some(note) = graph.filter_note() {
+        println!("");
+        println!("{}", note);
+    }
+}
+
+#[test]
+fn test_is_test() {
+    let classes = FileClasses::new("/nonexistent");
+    assert!(is_test(&classes, &None, "dom/base/test/test_foo.html"));
+    let dirs = Some(vec!["gtest/".to_string()]);
+    assert!(is_test(&classes, &dirs, "gtest/Foo.cpp"));
+    assert!(!is_test(&classes, &dirs, "dom/base/test/test_foo.html"));
+}
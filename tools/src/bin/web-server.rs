@@ -1,6 +1,7 @@
 extern crate hyper;
 extern crate env_logger;
 extern crate tools;
+extern crate rustc_serialize;
 
 use std::sync::Mutex;
 use std::fs::File;
@@ -8,6 +9,7 @@ use std::io::BufReader;
 use std::io::Read;
 use std::path::Path;
 use std::env;
+use std::str;
 use std::collections::HashMap;
 
 use hyper::status::StatusCode;
@@ -16,10 +18,12 @@ use hyper::server::{Request, Response};
 use hyper::header::ContentType;
 use hyper::mime::Mime;
 use hyper::uri;
+use rustc_serialize::json;
 
 use tools::config;
 use tools::blame;
 use tools::format;
+use tools::file_format::completions::{symbol_kind, Completion, Completions};
 use tools::file_format::identifiers::IdentMap;
 
 struct WebRequest {
@@ -73,7 +77,29 @@ fn handle_static(path: String, content_type: Option<&str>) -> WebResponse {
     WebResponse { status: StatusCode::Ok, content_type: content_type.to_owned(), output: input }
 }
 
-fn handle(cfg: &config::Config, ident_map: &HashMap<String, IdentMap>, req: WebRequest) -> WebResponse {
+// Decodes the %XX escapes in a path segment, such as the colons of a
+// qualified name.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = str::from_utf8(&bytes[i + 1 .. i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                result.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        result.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+fn handle(cfg: &config::Config, ident_map: &HashMap<String, IdentMap>,
+          completions: &HashMap<String, Completions>, req: WebRequest) -> WebResponse {
     let path = req.path.clone();
     let path = path[1..].split('/').collect::<Vec<_>>();
 
@@ -195,8 +221,24 @@ fn handle(cfg: &config::Config, ident_map: &HashMap<String, IdentMap>, req: WebR
         },
 
         "complete" => {
-            let ids = ident_map.get(&tree_name.to_string()).unwrap();
-            let json = ids.lookup_json(&path[2], false, false, 6);
+            if path.len() < 3 {
+                return not_found();
+            }
+
+            let needle = percent_decode(&path[2..].join("/"));
+
+            // Trees indexed before completions existed fall back to
+            // bisecting the identifiers file, unranked.
+            let results = match completions.get(&tree_name.to_string()) {
+                Some(completions) => completions.lookup(&needle, 10),
+                None => {
+                    let ids = ident_map.get(&tree_name.to_string()).unwrap();
+                    ids.lookup_raw(&needle, false, false, 10).into_iter().map(|(id, sym)| {
+                        Completion { name: id.to_string(), kind: symbol_kind(sym).to_string(), uses: 0 }
+                    }).collect()
+                },
+            };
+            let json = json::encode(&results).unwrap();
             WebResponse {
                 status: StatusCode::Ok,
                 content_type: "application/json".to_owned(),
@@ -215,8 +257,9 @@ fn main() {
 
     let cfg = config::load(&env::args().nth(1).unwrap(), true);
     let ident_map = IdentMap::load(&cfg);
+    let completions = Completions::load(&cfg);
 
-    let internal_data = Mutex::new((cfg, ident_map));
+    let internal_data = Mutex::new((cfg, ident_map, completions));
 
     let handler = move |req: Request, mut res: Response| {
         if req.method != Method::Get {
@@ -236,9 +279,9 @@ fn main() {
             Ok(guard) => guard,
             Err(poisoned) => poisoned.into_inner(),
         };
-        let (ref cfg, ref ident_map) = *guard;
+        let (ref cfg, ref ident_map, ref completions) = *guard;
 
-        let response = handle(&cfg, &ident_map, WebRequest { path: path });
+        let response = handle(&cfg, &ident_map, &completions, WebRequest { path: path });
 
         *res.status_mut() = response.status;
         let output = response.output.into_bytes();
@@ -250,3 +293,11 @@ fn main() {
     println!("On 8001");
     let _listening = hyper::Server::http("0.0.0.0:8001").unwrap().handle(handler);
 }
+
+#[test]
+fn test_percent_decode() {
+    assert_eq!(percent_decode("nsINode%3A%3AGetChild"), "nsINode::GetChild");
+    assert_eq!(percent_decode("dom%2Fbase"), "dom/base");
+    assert_eq!(percent_decode("100%"), "100%");
+    assert_eq!(percent_decode("%zz"), "%zz");
+}
@@ -1,9 +1,12 @@
 use config;
 
 use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
 use rustc_serialize::json::Json;
 use regex::Regex;
 use git2;
+use git2::Oid;
 
 use chrono::naive::datetime::NaiveDateTime;
 use chrono::offset::fixed::FixedOffset;
@@ -72,7 +75,47 @@ pub fn get_commit_info(cfg: &config::Config, tree_name: &str, rev: &str) -> Resu
 
     obj.insert("date".to_owned(), Json::String(t));
 
+    if let Some(hg_id) = git.hg_map.get(&commit.id()) {
+        obj.insert("hg".to_owned(), Json::String(hg_id.clone()));
+    }
+    if let Some(orig_hg_id) = git.hg_orig_map.get(&commit.id()) {
+        obj.insert("orig_hg".to_owned(), Json::String(orig_hg_id.clone()));
+    }
+
     let json = Json::Object(obj);
 
     Ok(json.to_string())
 }
+
+// Returns the commit time (in seconds since the epoch) of the revision
+// that last changed each line of |path|, according to the blame data
+// in |blame_commit|. Commit times are cached in |cache| since most
+// revisions touch many lines and files.
+pub fn get_line_dates(git: &config::GitData,
+                      blame_commit: &git2::Commit,
+                      path: &str,
+                      cache: &mut HashMap<Oid, i64>) -> Result<Vec<i64>, &'static str> {
+    let blame_tree = try!(blame_commit.tree().map_err(|_| "Bad revision"));
+    let blame_entry = try!(blame_tree.get_path(Path::new(path)).map_err(|_| "File not found"));
+    let blame_obj = try!(blame_entry.to_object(&git.blame_repo).map_err(|_| "Bad blame"));
+    let blame_blob = try!(blame_obj.as_blob().ok_or("Blame is not a blob"));
+    let blame = String::from_utf8_lossy(blame_blob.content());
+
+    let mut dates = Vec::new();
+    for line in blame.lines() {
+        let rev = try!(line.split(':').next().ok_or("Invalid blame line"));
+        let oid = try!(Oid::from_str(rev).map_err(|_| "Invalid blame revision"));
+        let date = match cache.get(&oid).cloned() {
+            Some(date) => date,
+            None => {
+                let commit = try!(git.repo.find_commit(oid).map_err(|_| "Bad revision"));
+                let date = commit.time().seconds();
+                cache.insert(oid, date);
+                date
+            }
+        };
+        dates.push(date);
+    }
+
+    Ok(dates)
+}
@@ -0,0 +1,2263 @@
+use std::cell::Cell;
+use std::cmp;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use getopts::{Matches, Options};
+use regex::Regex;
+use rustc_serialize::json::{Json, ToJson};
+
+use config::TreeConfig;
+use file_format::completions::symbol_kind;
+use file_format::crossref::CrossrefMap;
+use file_format::doc_summaries::read_doc_summaries;
+use file_format::file_classes::FileClasses;
+use file_format::identifiers::IdentMap;
+
+pub struct GraphNode {
+    pub sym: String,
+    pub pretty: String,
+    // Number of hops from the nearest root.
+    pub depth: u32,
+    // First sentence of the doc comment, if any (see add_summaries).
+    pub summary: String,
+    // Where rendered output links the node to, if anywhere (see
+    // add_links).
+    pub url: String,
+    // The directory the node is drawn in as a dot cluster, if any (see
+    // add_clusters).
+    pub cluster: String,
+    // How the node changed between two graphs being compared, such as
+    // "added" or "removed", if at all (see status_style).
+    pub status: String,
+}
+
+// Percent-encodes everything but letters, digits, '-', '_' and '.'.
+fn encode_url_component(s: &str) -> String {
+    s.bytes().map(|b| {
+        let c = b as char;
+        if b < 128 && (c.is_alphanumeric() || c == '-' || c == '_' || c == '.') {
+            c.to_string()
+        } else {
+            format!("%{:02X}", b)
+        }
+    }).collect()
+}
+
+// Returns the class or namespace qualifying a pretty name, such as
+// nsTArray<Foo> for nsTArray<Foo>::Length, or None if it has none.
+fn qualifier(pretty: &str) -> Option<&str> {
+    let mut nesting = 0;
+    let mut last = None;
+    let bytes = pretty.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'<' => nesting += 1,
+            b'>' if nesting > 0 => nesting -= 1,
+            b':' if nesting == 0 && i > 0 && bytes[i - 1] == b':' => last = Some(i - 1),
+            _ => {},
+        }
+    }
+    last.map(|pos| &pretty[.. pos])
+}
+
+// The dot attributes that mark a node or edge with the given status as
+// added or removed, if any.
+fn status_style(status: &str) -> &'static str {
+    match status {
+        "added" => "color=green",
+        "removed" => "color=red, style=dashed",
+        _ => "",
+    }
+}
+
+// The most partial paths paths_to_roots extends before giving up.
+const PATH_SEARCH_LIMIT: usize = 100000;
+
+// A graph of symbols. Edges point from a caller to the symbol it uses.
+pub struct SymbolGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: BTreeSet<(usize, usize)>,
+    // Set when the node limit stopped the traversal early.
+    pub truncated: bool,
+    // The symbols that were found but not added because of the node
+    // limit. Their own neighbors were never looked at, so this is only a
+    // lower bound on what is missing.
+    pub dropped: HashSet<String>,
+    // The symbols that were skipped because they are boring (see
+    // BoringSymbols).
+    pub filtered: HashSet<String>,
+    // For each edge, the number of uses of the callee inside the caller
+    // and where the first one is (see add_call_sites).
+    pub call_sites: HashMap<(usize, usize), (usize, String, u64)>,
+    // Edges standing for paths through nodes that were left out (see
+    // keep_central).
+    pub elided: BTreeSet<(usize, usize)>,
+    // How each edge changed, like GraphNode::status. Edges that aren't
+    // listed didn't change.
+    pub edge_status: HashMap<(usize, usize), String>,
+    index: HashMap<String, usize>,
+    // The URL nodes link to, if any (see add_links).
+    link_base: String,
+}
+
+impl SymbolGraph {
+    pub fn new() -> SymbolGraph {
+        SymbolGraph {
+            nodes: Vec::new(),
+            edges: BTreeSet::new(),
+            truncated: false,
+            dropped: HashSet::new(),
+            filtered: HashSet::new(),
+            call_sites: HashMap::new(),
+            elided: BTreeSet::new(),
+            edge_status: HashMap::new(),
+            index: HashMap::new(),
+            link_base: String::new(),
+        }
+    }
+
+    pub fn find(&self, sym: &str) -> Option<usize> {
+        self.index.get(sym).cloned()
+    }
+
+    pub fn add_node(&mut self, sym: &str, pretty: &str, depth: u32) -> usize {
+        if let Some(index) = self.find(sym) {
+            return index;
+        }
+        let index = self.nodes.len();
+        self.nodes.push(GraphNode {
+            sym: sym.to_string(),
+            pretty: pretty.to_string(),
+            depth: depth,
+            summary: String::new(),
+            url: String::new(),
+            cluster: String::new(),
+            status: String::new(),
+        });
+        self.index.insert(sym.to_string(), index);
+        index
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize) {
+        self.edges.insert((from, to));
+    }
+
+    // Returns advice to print when the node limit stopped the walk, or
+    // None if the graph is complete. Small overruns only need a higher
+    // limit; if more symbols were left out than kept, the query itself
+    // should be narrowed.
+    pub fn limit_hint(&self, node_limit: usize) -> Option<String> {
+        if !self.truncated {
+            return None;
+        }
+        let advice = if self.dropped.len() >= node_limit {
+            "Most of the graph is missing. Narrow the query with --max-depth, or list widely used \
+             symbols in the tree's boring_symbols config entry to skip them."
+        } else {
+            "Raise the limit with --node-limit (or graph_node_limit in the tree's config) to \
+             include them."
+        };
+        Some(format!("At least {} more symbols were left out. {}", self.dropped.len(), advice))
+    }
+
+    // Returns a note saying how many boring symbols were left out, or
+    // None if there were none.
+    pub fn filter_note(&self) -> Option<String> {
+        if self.filtered.is_empty() {
+            return None;
+        }
+        Some(format!("{} symbols matching boring_symbols, --exclude-sym or --exclude-pretty were left out.",
+                     self.filtered.len()))
+    }
+
+    pub fn callers_of(&self, node: usize) -> Vec<usize> {
+        self.edges.iter().filter(|&&(_, to)| to == node).map(|&(from, _)| from).collect()
+    }
+
+    pub fn callees_of(&self, node: usize) -> Vec<usize> {
+        self.edges.iter().filter(|&&(from, _)| from == node).map(|&(_, to)| to).collect()
+    }
+
+    // Returns the nodes on a shortest path from |node| down to one of the
+    // roots, starting with |node| itself. Every non-root node was
+    // discovered from a node one hop closer to the roots, so following
+    // edges whose depth decreases always gets there.
+    pub fn path_to_root(&self, node: usize) -> Vec<usize> {
+        let mut path = vec![node];
+        let mut cur = node;
+        while self.nodes[cur].depth > 0 {
+            let depth = self.nodes[cur].depth;
+            match self.callees_of(cur).into_iter().find(|&n| self.nodes[n].depth == depth - 1) {
+                Some(next) => {
+                    path.push(next);
+                    cur = next;
+                },
+                None => break,
+            }
+        }
+        path
+    }
+
+    // Returns up to |count| of the shortest paths from |node| down to a
+    // root, shortest first, along with whether there are more. Unlike
+    // path_to_root, the paths may take any edge, so they are limited to
+    // |max_hops| edges and never visit a node twice. Paths are extended
+    // breadth-first until one past |count| turns up, which gives up
+    // (reporting that there may be more) after PATH_SEARCH_LIMIT partial
+    // paths on dense graphs.
+    pub fn paths_to_roots(&self, node: usize, count: usize, max_hops: usize) -> (Vec<Vec<usize>>, bool) {
+        let mut found = Vec::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(vec![node]);
+        let mut examined = 0;
+        while let Some(path) = queue.pop_front() {
+            let last = path[path.len() - 1];
+            if self.nodes[last].depth == 0 {
+                found.push(path);
+                if found.len() > count {
+                    found.truncate(count);
+                    return (found, true);
+                }
+                continue;
+            }
+            examined += 1;
+            if examined > PATH_SEARCH_LIMIT {
+                return (found, true);
+            }
+            if path.len() > max_hops {
+                continue;
+            }
+            for next in self.callees_of(last) {
+                if !path.contains(&next) {
+                    let mut longer = path.clone();
+                    longer.push(next);
+                    queue.push_back(longer);
+                }
+            }
+        }
+        (found, false)
+    }
+
+    // Computes the immediate dominator of every node reachable from
+    // |entry| along edges, using the iterative algorithm from Cooper,
+    // Harvey and Kennedy, "A Simple, Fast Dominance Algorithm". The
+    // entry and unreachable nodes have no dominator.
+    pub fn dominators(&self, entry: usize) -> Vec<Option<usize>> {
+        let count = self.nodes.len();
+        let mut succs = vec![Vec::new(); count];
+        let mut preds = vec![Vec::new(); count];
+        for &(from, to) in &self.edges {
+            succs[from].push(to);
+            preds[to].push(from);
+        }
+
+        // Number the reachable nodes in postorder.
+        let mut postorder = Vec::new();
+        let mut visited = vec![false; count];
+        let mut stack = vec![(entry, 0)];
+        visited[entry] = true;
+        while let Some((node, next)) = stack.pop() {
+            if next < succs[node].len() {
+                stack.push((node, next + 1));
+                let succ = succs[node][next];
+                if !visited[succ] {
+                    visited[succ] = true;
+                    stack.push((succ, 0));
+                }
+            } else {
+                postorder.push(node);
+            }
+        }
+        let mut number = vec![0; count];
+        for (i, &node) in postorder.iter().enumerate() {
+            number[node] = i;
+        }
+
+        let mut idom = vec![None; count];
+        idom[entry] = Some(entry);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &node in postorder.iter().rev() {
+                if node == entry {
+                    continue;
+                }
+                let mut new_idom = None;
+                for &pred in &preds[node] {
+                    if idom[pred].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => pred,
+                        Some(mut other) => {
+                            let mut pred = pred;
+                            while pred != other {
+                                while number[pred] < number[other] {
+                                    pred = idom[pred].unwrap();
+                                }
+                                while number[other] < number[pred] {
+                                    other = idom[other].unwrap();
+                                }
+                            }
+                            pred
+                        }
+                    });
+                }
+                if new_idom != idom[node] {
+                    idom[node] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        idom[entry] = None;
+        idom
+    }
+
+    // Fills in the summary of each node from the tree's doc-summaries,
+    // unless it already has one.
+    pub fn add_summaries(&mut self, summaries: &HashMap<String, String>) {
+        for node in &mut self.nodes {
+            if !node.summary.is_empty() {
+                continue;
+            }
+            if let Some(summary) = summaries.get(&node.sym) {
+                node.summary = summary.clone();
+            }
+        }
+    }
+
+    // Links each node to its definition on the searchfox instance at
+    // |link_base| (the URL of the tree, such as
+    // https://searchfox.org/mozilla-central), or to a search for the
+    // symbol if it has no single definition.
+    pub fn add_links(&mut self, crossref: &CrossrefMap, link_base: &str) {
+        let base = link_base.trim_right_matches('/');
+        self.link_base = base.to_string();
+        for node in &mut self.nodes {
+            let def = crossref.lookup(&node.sym).as_ref()
+                .and_then(|entry| entry.first_def().map(|(path, lineno)| (path.to_string(), lineno)));
+            node.url = match def {
+                Some((path, lineno)) => format!("{}/source/{}#{}", base, path, lineno),
+                None => format!("{}/search?q=symbol:{}", base, encode_url_component(&node.sym)),
+            };
+        }
+    }
+
+    // Counts the uses behind each edge of a call graph, so renderers can
+    // draw edges backed by many call sites thicker and link to one.
+    pub fn add_call_sites(&mut self, crossref: &CrossrefMap) {
+        let edges = self.edges.iter().cloned().collect::<Vec<_>>();
+        for (from, to) in edges {
+            let uses = find_uses(crossref, &self.nodes[from].sym, &self.nodes[to].sym);
+            if let Some(&(ref path, lineno)) = uses.first() {
+                self.call_sites.insert((from, to), (uses.len(), path.clone(), lineno));
+            }
+        }
+    }
+
+    // Returns the URL of a line of code, or None if the nodes aren't
+    // linked.
+    fn line_url(&self, path: &str, lineno: u64) -> Option<String> {
+        if self.link_base.is_empty() {
+            None
+        } else {
+            Some(format!("{}/source/{}#{}", self.link_base, path, lineno))
+        }
+    }
+
+    // Groups the nodes by the directory of their definition, keeping the
+    // first |levels| components of it (so 1 gives dom/, netwerk/ and so
+    // on). write_dot draws each group as a cluster, nested within the
+    // clusters of its parent directories.
+    pub fn add_clusters(&mut self, crossref: &CrossrefMap, levels: usize) {
+        for node in &mut self.nodes {
+            let path = match crossref.lookup(&node.sym).as_ref().and_then(|entry| entry.first_def().map(|(path, _)| path.to_string())) {
+                Some(path) => path,
+                None => continue,
+            };
+            let dirs = path.split('/').collect::<Vec<_>>();
+            let dirs = &dirs[.. dirs.len() - 1];
+            node.cluster = dirs[.. cmp::min(levels, dirs.len())].join("/");
+        }
+    }
+
+    // Returns a copy of the graph where the nodes for which |group|
+    // returns Some((sym, pretty)) are merged into one node per symbol.
+    // Edges within a group are dropped, and the call sites of the edges
+    // merged into one are added up (edges without call sites count
+    // once).
+    fn merge_nodes(&self, group: &Fn(usize) -> Option<(String, String)>) -> SymbolGraph {
+        let mut result = SymbolGraph::new();
+        result.truncated = self.truncated;
+        result.dropped = self.dropped.clone();
+        result.filtered = self.filtered.clone();
+        result.link_base = self.link_base.clone();
+
+        let mut merged = Vec::with_capacity(self.nodes.len());
+        for (i, node) in self.nodes.iter().enumerate() {
+            let index = match group(i) {
+                Some((sym, pretty)) => result.add_node(&sym, &pretty, node.depth),
+                None => {
+                    let index = result.add_node(&node.sym, &node.pretty, node.depth);
+                    result.nodes[index].summary = node.summary.clone();
+                    result.nodes[index].url = node.url.clone();
+                    result.nodes[index].cluster = node.cluster.clone();
+                    result.nodes[index].status = node.status.clone();
+                    index
+                },
+            };
+            result.nodes[index].depth = cmp::min(result.nodes[index].depth, node.depth);
+            merged.push(index);
+        }
+
+        let mut sites: BTreeMap<(usize, usize), (usize, Option<(String, u64)>)> = BTreeMap::new();
+        for &(from, to) in &self.edges {
+            let edge = (merged[from], merged[to]);
+            if edge.0 == edge.1 {
+                continue;
+            }
+            result.add_edge(edge.0, edge.1);
+            let entry = sites.entry(edge).or_insert((0, None));
+            match self.call_sites.get(&(from, to)) {
+                Some(&(count, ref path, lineno)) => {
+                    entry.0 += count;
+                    if entry.1.is_none() {
+                        entry.1 = Some((path.clone(), lineno));
+                    }
+                },
+                None => entry.0 += 1,
+            }
+        }
+        for (edge, (count, site)) in sites {
+            if let Some((path, lineno)) = site {
+                result.call_sites.insert(edge, (count, path, lineno));
+            }
+        }
+        result
+    }
+
+    // Returns a copy of the graph where all the methods of a class are
+    // one node for the class, so a large call graph reads as the
+    // interactions between a few classes. |is_class| says whether a
+    // symbol is a class; symbols that aren't members of one keep their
+    // own node.
+    pub fn collapse_classes(&self, is_class: &Fn(&str) -> bool) -> SymbolGraph {
+        self.merge_nodes(&|i| {
+            let class = match qualifier(&self.nodes[i].pretty) {
+                Some(class) => class,
+                None => return None,
+            };
+            let sym = format!("T_{}", class.replace(" ", "_"));
+            if is_class(sym.as_str()) { Some((sym, class.to_string())) } else { None }
+        })
+    }
+
+    // Returns the strongly connected components of the graph that have
+    // more than one node, each sorted, using an iterative version of
+    // Tarjan's algorithm.
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        let count = self.nodes.len();
+        let mut succs = vec![Vec::new(); count];
+        for &(from, to) in &self.edges {
+            succs[from].push(to);
+        }
+
+        let mut index = vec![None; count];
+        let mut lowlink = vec![0; count];
+        let mut on_stack = vec![false; count];
+        let mut stack = Vec::new();
+        let mut next_index = 0;
+        let mut result = Vec::new();
+        for start in 0 .. count {
+            if index[start].is_some() {
+                continue;
+            }
+            // Each entry is a node and the number of its successors that
+            // have been looked at.
+            let mut work = vec![(start, 0)];
+            while let Some((node, next)) = work.pop() {
+                if next == 0 {
+                    index[node] = Some(next_index);
+                    lowlink[node] = next_index;
+                    next_index += 1;
+                    stack.push(node);
+                    on_stack[node] = true;
+                } else {
+                    let succ = succs[node][next - 1];
+                    if on_stack[succ] {
+                        lowlink[node] = cmp::min(lowlink[node], lowlink[succ]);
+                    }
+                }
+                if next < succs[node].len() {
+                    let succ = succs[node][next];
+                    work.push((node, next + 1));
+                    if index[succ].is_none() {
+                        work.push((succ, 0));
+                    }
+                    continue;
+                }
+                if Some(lowlink[node]) == index[node] {
+                    let mut component = Vec::new();
+                    loop {
+                        let member = stack.pop().unwrap();
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    if component.len() > 1 {
+                        component.sort();
+                        result.push(component);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    // Returns up to |limit| elementary cycles, each listed from its
+    // lowest node without repeating it, and whether there are more.
+    // Each cycle is found once, by a depth-first search from its lowest
+    // node through the higher nodes of the same strongly connected
+    // component, which looks for one cycle past |limit| to tell, and
+    // gives up (reporting that there may be more) after
+    // PATH_SEARCH_LIMIT steps.
+    pub fn elementary_cycles(&self, limit: usize) -> (Vec<Vec<usize>>, bool) {
+        let count = self.nodes.len();
+        let mut succs = vec![Vec::new(); count];
+        for &(from, to) in &self.edges {
+            succs[from].push(to);
+        }
+        let mut component = vec![None; count];
+        for (c, members) in self.cycles().iter().enumerate() {
+            for &member in members {
+                component[member] = Some(c);
+            }
+        }
+
+        let mut found = Vec::new();
+        let mut steps = 0;
+        for start in 0 .. count {
+            if self.edges.contains(&(start, start)) {
+                found.push(vec![start]);
+                if found.len() > limit {
+                    found.truncate(limit);
+                    return (found, true);
+                }
+            }
+            if component[start].is_none() {
+                continue;
+            }
+
+            // |stack| holds the number of successors looked at for each
+            // node on |path|.
+            let mut path = vec![start];
+            let mut on_path = vec![false; count];
+            on_path[start] = true;
+            let mut stack = vec![0];
+            while let Some(next) = stack.pop() {
+                let node = path[path.len() - 1];
+                if next >= succs[node].len() {
+                    path.pop();
+                    on_path[node] = false;
+                    continue;
+                }
+                stack.push(next + 1);
+                steps += 1;
+                if steps > PATH_SEARCH_LIMIT {
+                    return (found, true);
+                }
+                let succ = succs[node][next];
+                if succ == start && path.len() > 1 {
+                    found.push(path.clone());
+                    if found.len() > limit {
+                        found.truncate(limit);
+                        return (found, true);
+                    }
+                } else if succ > start && component[succ] == component[start] && !on_path[succ] {
+                    path.push(succ);
+                    on_path[succ] = true;
+                    stack.push(0);
+                }
+            }
+        }
+        (found, false)
+    }
+
+    // Returns a copy of the graph where each cycle is one node, leaving
+    // a DAG that stays readable when recursion ties many functions
+    // together. The node for a cycle keeps the symbol of its first
+    // member, and its summary lists all of them.
+    pub fn condense_cycles(&self) -> SymbolGraph {
+        let name = |i: usize| {
+            let node = &self.nodes[i];
+            if node.pretty.is_empty() { node.sym.clone() } else { node.pretty.clone() }
+        };
+        let mut group = vec![None; self.nodes.len()];
+        let mut members = Vec::new();
+        for component in self.cycles() {
+            let sym = self.nodes[component[0]].sym.clone();
+            let pretty = format!("{} (cycle of {})", name(component[0]), component.len());
+            for &i in &component {
+                group[i] = Some((sym.clone(), pretty.clone()));
+            }
+            let names = component.iter().map(|&i| name(i)).collect::<Vec<_>>();
+            members.push((sym, format!("Cycle of {}", names.join(", "))));
+        }
+
+        let mut result = self.merge_nodes(&|i| group[i].clone());
+        for (sym, summary) in members {
+            let index = result.find(&sym).unwrap();
+            result.nodes[index].summary = summary;
+        }
+        result
+    }
+
+    // Returns the betweenness centrality of each node: how many of the
+    // shortest paths between other nodes go through it, as computed by
+    // Brandes' algorithm.
+    fn betweenness(&self) -> Vec<f64> {
+        let count = self.nodes.len();
+        let mut succs = vec![Vec::new(); count];
+        for &(from, to) in &self.edges {
+            succs[from].push(to);
+        }
+
+        let mut centrality = vec![0.0; count];
+        for source in 0 .. count {
+            let mut order = Vec::new();
+            let mut preds = vec![Vec::new(); count];
+            let mut paths = vec![0.0; count];
+            let mut dist = vec![None; count];
+            paths[source] = 1.0;
+            dist[source] = Some(0);
+            let mut queue = VecDeque::new();
+            queue.push_back(source);
+            while let Some(node) = queue.pop_front() {
+                order.push(node);
+                let next = dist[node].unwrap() + 1;
+                for &succ in &succs[node] {
+                    if dist[succ].is_none() {
+                        dist[succ] = Some(next);
+                        queue.push_back(succ);
+                    }
+                    if dist[succ] == Some(next) {
+                        paths[succ] += paths[node];
+                        preds[succ].push(node);
+                    }
+                }
+            }
+
+            let mut dependency = vec![0.0; count];
+            while let Some(node) = order.pop() {
+                for &pred in &preds[node] {
+                    dependency[pred] += paths[pred] / paths[node] * (1.0 + dependency[node]);
+                }
+                if node != source {
+                    centrality[node] += dependency[node];
+                }
+            }
+        }
+        centrality
+    }
+
+    // Returns a copy of the graph with only the roots and the |count|
+    // most central other nodes: those that the most shortest paths go
+    // through, with ties going to the nodes with the most edges. Kept
+    // nodes that were only connected through dropped ones get an edge
+    // for those paths, which is also put in |elided|.
+    pub fn keep_central(&self, count: usize) -> SymbolGraph {
+        let centrality = self.betweenness();
+        let mut degree = vec![0; self.nodes.len()];
+        let mut succs = vec![Vec::new(); self.nodes.len()];
+        for &(from, to) in &self.edges {
+            degree[from] += 1;
+            degree[to] += 1;
+            succs[from].push(to);
+        }
+        let mut others = (0 .. self.nodes.len()).filter(|&i| self.nodes[i].depth > 0).collect::<Vec<_>>();
+        others.sort_by(|&a, &b| match centrality[b].partial_cmp(&centrality[a]).unwrap() {
+            cmp::Ordering::Equal => degree[b].cmp(&degree[a]),
+            ordering => ordering,
+        });
+        let mut kept = self.nodes.iter().map(|node| node.depth == 0).collect::<Vec<_>>();
+        for &i in others.iter().take(count) {
+            kept[i] = true;
+        }
+
+        let mut result = SymbolGraph::new();
+        result.truncated = self.truncated;
+        result.dropped = self.dropped.clone();
+        result.filtered = self.filtered.clone();
+        result.link_base = self.link_base.clone();
+        let mut index = vec![None; self.nodes.len()];
+        for (i, node) in self.nodes.iter().enumerate() {
+            if !kept[i] {
+                continue;
+            }
+            let new = result.add_node(&node.sym, &node.pretty, node.depth);
+            result.nodes[new].summary = node.summary.clone();
+            result.nodes[new].url = node.url.clone();
+            result.nodes[new].cluster = node.cluster.clone();
+            index[i] = Some(new);
+        }
+
+        for &(from, to) in &self.edges {
+            if let (Some(new_from), Some(new_to)) = (index[from], index[to]) {
+                result.add_edge(new_from, new_to);
+                if let Some(site) = self.call_sites.get(&(from, to)) {
+                    result.call_sites.insert((new_from, new_to), site.clone());
+                }
+            }
+        }
+        // Find the kept nodes each kept node reaches through dropped ones.
+        for from in 0 .. self.nodes.len() {
+            let new_from = match index[from] {
+                Some(new_from) => new_from,
+                None => continue,
+            };
+            let mut seen = vec![false; self.nodes.len()];
+            let mut queue = succs[from].iter().cloned().filter(|&succ| !kept[succ]).collect::<VecDeque<_>>();
+            while let Some(node) = queue.pop_front() {
+                for &succ in &succs[node] {
+                    match index[succ] {
+                        Some(new_to) => {
+                            if succ != from && !self.edges.contains(&(from, succ)) {
+                                result.add_edge(new_from, new_to);
+                                result.elided.insert((new_from, new_to));
+                            }
+                        },
+                        None => {
+                            if !seen[succ] {
+                                seen[succ] = true;
+                                queue.push_back(succ);
+                            }
+                        },
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    // Removes the edges implied by longer paths, one at a time, so that
+    // every node still reaches the same nodes. Without cycles, this
+    // leaves the transitive reduction of the graph. Removing them all at
+    // once could disconnect a cycle's members, which imply each other's
+    // edges.
+    pub fn remove_implied_edges(&mut self) {
+        let mut succs = vec![BTreeSet::new(); self.nodes.len()];
+        for &(from, to) in &self.edges {
+            succs[from].insert(to);
+        }
+        let edges = self.edges.iter().cloned().collect::<Vec<_>>();
+        for (from, to) in edges {
+            if from == to {
+                continue;
+            }
+            // Look for another path from |from| to |to|.
+            let mut seen = vec![false; self.nodes.len()];
+            seen[from] = true;
+            let mut stack = succs[from].iter().cloned().filter(|&succ| succ != to).collect::<Vec<_>>();
+            let mut implied = false;
+            while let Some(node) = stack.pop() {
+                if node == to {
+                    implied = true;
+                    break;
+                }
+                if seen[node] {
+                    continue;
+                }
+                seen[node] = true;
+                stack.extend(succs[node].iter().cloned());
+            }
+            if implied {
+                succs[from].remove(&to);
+                self.edges.remove(&(from, to));
+                self.call_sites.remove(&(from, to));
+                self.elided.remove(&(from, to));
+            }
+        }
+    }
+
+    pub fn write_dot(&self, writer: &mut Write) -> Result<(), &'static str> {
+        fn escape(s: &str) -> String {
+            s.replace("\\", "\\\\").replace("\"", "\\\"")
+        }
+
+        // Writes the cluster for directory |dir| and, inside it, those of
+        // its subdirectories.
+        fn write_cluster(out: &mut String, dir: &str, clusters: &BTreeMap<String, Vec<usize>>, indent: usize) {
+            let pad = ::std::iter::repeat(" ").take(indent).collect::<String>();
+            out.push_str(&format!("{}subgraph \"cluster_{}\" {{\n", pad, escape(dir)));
+            out.push_str(&format!("{}  label=\"{}/\";\n", pad, escape(dir)));
+            for &i in clusters.get(dir).map_or(&[][..], |nodes| &nodes[..]) {
+                out.push_str(&format!("{}  n{};\n", pad, i));
+            }
+            let prefix = format!("{}/", dir);
+            for sub in clusters.keys() {
+                if sub.starts_with(&prefix) && !sub[prefix.len() ..].contains('/') {
+                    write_cluster(out, sub, clusters, indent + 2);
+                }
+            }
+            out.push_str(&format!("{}}}\n", pad));
+        }
+
+        let mut out = String::from("digraph G {\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            let label = if node.pretty.is_empty() { &node.sym } else { &node.pretty };
+            let mut attrs = format!("label=\"{}\"", escape(label));
+            if !node.summary.is_empty() {
+                attrs.push_str(&format!(", tooltip=\"{}\"", escape(&node.summary)));
+            }
+            // Graphviz wraps linked nodes in <a> elements in SVG output.
+            if !node.url.is_empty() {
+                attrs.push_str(&format!(", URL=\"{}\", target=\"_top\"", escape(&node.url)));
+            }
+            if !status_style(&node.status).is_empty() {
+                attrs.push_str(&format!(", {}", status_style(&node.status)));
+            }
+            out.push_str(&format!("  n{} [{}];\n", i, attrs));
+        }
+        for &(from, to) in &self.edges {
+            let mut attrs = if self.elided.contains(&(from, to)) {
+                "style=dashed, tooltip=\"through symbols that were left out\"".to_string()
+            } else {
+                match self.call_sites.get(&(from, to)) {
+                    Some(&(count, ref path, lineno)) => {
+                        let width = (count as f64).log2().floor() as usize + 1;
+                        let mut attrs = format!("penwidth={}, tooltip=\"{} uses, first at {}:{}\"", width, count, escape(path), lineno);
+                        if let Some(url) = self.line_url(path, lineno) {
+                            attrs.push_str(&format!(", URL=\"{}\", target=\"_top\"", escape(&url)));
+                        }
+                        attrs
+                    },
+                    None => String::new(),
+                }
+            };
+            let style = self.edge_status.get(&(from, to)).map_or("", |status| status_style(status));
+            if !style.is_empty() {
+                if !attrs.is_empty() {
+                    attrs.push_str(", ");
+                }
+                attrs.push_str(style);
+            }
+            if attrs.is_empty() {
+                out.push_str(&format!("  n{} -> n{};\n", from, to));
+            } else {
+                out.push_str(&format!("  n{} -> n{} [{}];\n", from, to, attrs));
+            }
+        }
+
+        // Every parent directory of a cluster is a cluster too, even if
+        // none of the nodes is defined directly in it.
+        let mut clusters: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.cluster.is_empty() {
+                continue;
+            }
+            clusters.entry(node.cluster.clone()).or_insert(vec![]).push(i);
+            let mut dir = &node.cluster[..];
+            while let Some(pos) = dir.rfind('/') {
+                dir = &dir[.. pos];
+                clusters.entry(dir.to_string()).or_insert(vec![]);
+            }
+        }
+
+        if clusters.is_empty() {
+            // Keep the nodes found after the same number of hops on one
+            // rank, so the layout reads outwards from the roots. Graphviz
+            // can't combine this with clusters.
+            let mut ranks = BTreeMap::new();
+            for (i, node) in self.nodes.iter().enumerate() {
+                ranks.entry(node.depth).or_insert(vec![]).push(format!("n{}", i));
+            }
+            for (depth, nodes) in ranks {
+                out.push_str(&format!("  {{ rank=same; /* depth {} */ {}; }}\n", depth, nodes.join("; ")));
+            }
+        } else {
+            for dir in clusters.keys() {
+                if !dir.contains('/') {
+                    write_cluster(&mut out, dir, &clusters, 2);
+                }
+            }
+        }
+        out.push_str("}\n");
+        try!(writer.write_all(out.as_bytes()).map_err(|_| "Write err"));
+        Ok(())
+    }
+
+    // Writes the graph in GraphML, for tools like Gephi and yEd that lay
+    // out and analyze large graphs. Every edge gets |edge_kind| as its
+    // kind, since the edges of a graph all come from one relationship.
+    pub fn write_graphml(&self, writer: &mut Write, edge_kind: &str) -> Result<(), &'static str> {
+        fn escape(s: &str) -> String {
+            s.replace("&", "&amp;").replace("<", "&lt;").replace(">", "&gt;").replace("\"", "&quot;")
+        }
+
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for &(id, domain, name, ty) in &[("sym", "node", "sym", "string"), ("pretty", "node", "pretty", "string"),
+                                         ("kind", "node", "kind", "string"), ("depth", "node", "depth", "int"),
+                                         ("summary", "node", "summary", "string"), ("url", "node", "url", "string"),
+                                         ("edge_kind", "edge", "kind", "string"), ("sites", "edge", "sites", "int"),
+                                         ("site", "edge", "site", "string"), ("elided", "edge", "elided", "boolean")] {
+            out.push_str(&format!("  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>\n", id, domain, name, ty));
+        }
+        out.push_str("  <graph edgedefault=\"directed\">\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            out.push_str(&format!("    <node id=\"n{}\">\n", i));
+            out.push_str(&format!("      <data key=\"sym\">{}</data>\n", escape(&node.sym)));
+            out.push_str(&format!("      <data key=\"pretty\">{}</data>\n", escape(&node.pretty)));
+            out.push_str(&format!("      <data key=\"kind\">{}</data>\n", symbol_kind(&node.sym)));
+            out.push_str(&format!("      <data key=\"depth\">{}</data>\n", node.depth));
+            if !node.summary.is_empty() {
+                out.push_str(&format!("      <data key=\"summary\">{}</data>\n", escape(&node.summary)));
+            }
+            if !node.url.is_empty() {
+                out.push_str(&format!("      <data key=\"url\">{}</data>\n", escape(&node.url)));
+            }
+            out.push_str("    </node>\n");
+        }
+        for &(from, to) in &self.edges {
+            let mut sites = match self.call_sites.get(&(from, to)) {
+                Some(&(count, ref path, lineno)) =>
+                    format!("<data key=\"sites\">{}</data><data key=\"site\">{}:{}</data>", count, escape(path), lineno),
+                None => String::new(),
+            };
+            if self.elided.contains(&(from, to)) {
+                sites.push_str("<data key=\"elided\">true</data>");
+            }
+            out.push_str(&format!("    <edge source=\"n{}\" target=\"n{}\"><data key=\"edge_kind\">{}</data>{}</edge>\n",
+                                  from, to, escape(edge_kind), sites));
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        try!(writer.write_all(out.as_bytes()).map_err(|_| "Write err"));
+        Ok(())
+    }
+
+    // Writes the graph as a Mermaid flowchart, which Bugzilla comments,
+    // GitHub and many Markdown editors render in place. Clicking a node
+    // follows its link, if it has one.
+    pub fn write_mermaid(&self, writer: &mut Write) -> Result<(), &'static str> {
+        fn escape(s: &str) -> String {
+            s.replace("#", "#35;").replace("\"", "#quot;").replace("<", "#lt;").replace(">", "#gt;")
+        }
+
+        let mut out = String::from("flowchart LR\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            let label = if node.pretty.is_empty() { &node.sym } else { &node.pretty };
+            out.push_str(&format!("  n{}[\"{}\"]\n", i, escape(label)));
+        }
+        for &(from, to) in &self.edges {
+            match self.call_sites.get(&(from, to)) {
+                _ if self.elided.contains(&(from, to)) => out.push_str(&format!("  n{} -.-> n{}\n", from, to)),
+                Some(&(count, _, _)) if count > 1 => out.push_str(&format!("  n{} -->|{}| n{}\n", from, count, to)),
+                _ => out.push_str(&format!("  n{} --> n{}\n", from, to)),
+            }
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            if !node.url.is_empty() {
+                let tooltip = if node.summary.is_empty() { &node.sym } else { &node.summary };
+                out.push_str(&format!("  click n{} href \"{}\" \"{}\"\n", i, node.url.replace("\"", "%22"), escape(tooltip)));
+            }
+        }
+        try!(writer.write_all(out.as_bytes()).map_err(|_| "Write err"));
+        Ok(())
+    }
+
+    // Writes the graph as Cytoscape.js elements JSON, so a web page can
+    // load it into an interactive viewer. Each node's classes are its
+    // kind, its status if it has one and, for the nodes the walk started
+    // from, "root", for stylesheets to pick out. Every edge has
+    // |edge_kind| as its kind.
+    pub fn write_cytoscape(&self, writer: &mut Write, edge_kind: &str) -> Result<(), &'static str> {
+        let nodes = self.nodes.iter().enumerate().map(|(i, node)| {
+            let mut data = BTreeMap::new();
+            data.insert("id".to_string(), format!("n{}", i).to_json());
+            data.insert("sym".to_string(), node.sym.to_json());
+            data.insert("label".to_string(), (if node.pretty.is_empty() { &node.sym } else { &node.pretty }).to_json());
+            data.insert("kind".to_string(), symbol_kind(&node.sym).to_json());
+            data.insert("depth".to_string(), node.depth.to_json());
+            if !node.summary.is_empty() {
+                data.insert("summary".to_string(), node.summary.to_json());
+            }
+            if !node.url.is_empty() {
+                data.insert("url".to_string(), node.url.to_json());
+            }
+            let mut classes = vec![symbol_kind(&node.sym)];
+            if node.depth == 0 {
+                classes.push("root");
+            }
+            if !node.status.is_empty() {
+                data.insert("status".to_string(), node.status.to_json());
+                classes.push(&node.status[..]);
+            }
+            let mut obj = BTreeMap::new();
+            obj.insert("data".to_string(), Json::Object(data));
+            obj.insert("classes".to_string(), classes.join(" ").to_json());
+            Json::Object(obj)
+        }).collect::<Vec<_>>();
+        let edges = self.edges.iter().enumerate().map(|(i, &(from, to))| {
+            let mut data = BTreeMap::new();
+            data.insert("id".to_string(), format!("e{}", i).to_json());
+            data.insert("source".to_string(), format!("n{}", from).to_json());
+            data.insert("target".to_string(), format!("n{}", to).to_json());
+            data.insert("kind".to_string(), edge_kind.to_json());
+            if let Some(&(count, ref path, lineno)) = self.call_sites.get(&(from, to)) {
+                data.insert("sites".to_string(), count.to_json());
+                data.insert("site".to_string(), format!("{}:{}", path, lineno).to_json());
+                if let Some(url) = self.line_url(path, lineno) {
+                    data.insert("site_url".to_string(), url.to_json());
+                }
+            }
+            let mut classes = edge_kind.to_string();
+            if self.elided.contains(&(from, to)) {
+                data.insert("elided".to_string(), true.to_json());
+                classes.push_str(" elided");
+            }
+            if let Some(status) = self.edge_status.get(&(from, to)) {
+                data.insert("status".to_string(), status.to_json());
+                classes.push_str(&format!(" {}", status));
+            }
+            let mut obj = BTreeMap::new();
+            obj.insert("data".to_string(), Json::Object(data));
+            obj.insert("classes".to_string(), classes.to_json());
+            Json::Object(obj)
+        }).collect::<Vec<_>>();
+        let mut elements = BTreeMap::new();
+        elements.insert("nodes".to_string(), Json::Array(nodes));
+        elements.insert("edges".to_string(), Json::Array(edges));
+        let mut doc = BTreeMap::new();
+        doc.insert("elements".to_string(), Json::Object(elements));
+        try!(writer.write_all(Json::Object(doc).to_string().as_bytes()).map_err(|_| "Write err"));
+        Ok(())
+    }
+
+    // Writes a standalone HTML page with the graph embedded as JSON and
+    // a small viewer, so it can be explored without Graphviz.
+    pub fn write_html(&self, writer: &mut Write, title: &str) -> Result<(), &'static str> {
+        let nodes = self.nodes.iter().map(|node| {
+            let mut obj = BTreeMap::new();
+            obj.insert("sym".to_string(), node.sym.to_json());
+            obj.insert("pretty".to_string(), node.pretty.to_json());
+            obj.insert("depth".to_string(), node.depth.to_json());
+            obj.insert("summary".to_string(), node.summary.to_json());
+            obj.insert("url".to_string(), node.url.to_json());
+            Json::Object(obj)
+        }).collect::<Vec<_>>();
+        let edges = self.edges.iter().map(|&(from, to)| vec![from, to]).collect::<Vec<_>>();
+        let mut graph = BTreeMap::new();
+        graph.insert("nodes".to_string(), Json::Array(nodes));
+        graph.insert("edges".to_string(), edges.to_json());
+
+        // Keep the JSON from closing the script element.
+        let graph = Json::Object(graph).to_string().replace("</", "<\\/");
+        let title = title.replace("&", "&amp;").replace("<", "&lt;").replace(">", "&gt;");
+        let page = include_str!("graph-viewer.html").replace("{{TITLE}}", &title).replace("{{GRAPH}}", &graph);
+        try!(writer.write_all(page.as_bytes()).map_err(|_| "Write err"));
+        Ok(())
+    }
+}
+
+// Symbols that the graph tools leave out: string classes, smart
+// pointers, logging and the like, which are used from everywhere and
+// drown out the interesting edges. Each pattern is a regexp that is
+// matched against both the symbol and its pretty name.
+pub struct BoringSymbols {
+    patterns: Vec<Regex>,
+    sym_patterns: Vec<Regex>,
+    pretty_patterns: Vec<Regex>,
+}
+
+// Compiles each of |patterns|, naming |what| they came from in the
+// error for one that isn't a valid regexp.
+fn compile_patterns(patterns: &[String], what: &str) -> Result<Vec<Regex>, String> {
+    patterns.iter().map(|p| Regex::new(p).map_err(|e| format!("Invalid {} pattern {}: {}", what, p, e))).collect()
+}
+
+impl BoringSymbols {
+    pub fn new(patterns: &[String]) -> Result<BoringSymbols, String> {
+        Ok(BoringSymbols {
+            patterns: try!(compile_patterns(patterns, "boring_symbols")),
+            sym_patterns: vec![],
+            pretty_patterns: vec![],
+        })
+    }
+
+    // Uses the tree's boring_symbols config entry, if any.
+    pub fn from_config(tree_config: &TreeConfig) -> Result<BoringSymbols, String> {
+        match tree_config.paths.boring_symbols {
+            Some(ref patterns) => BoringSymbols::new(patterns),
+            None => BoringSymbols::new(&[]),
+        }
+    }
+
+    // Uses the tree's config entry along with the graph tools'
+    // --exclude-sym and --exclude-pretty options.
+    pub fn from_options(tree_config: &TreeConfig, matches: &Matches) -> Result<BoringSymbols, String> {
+        let mut boring = try!(BoringSymbols::from_config(tree_config));
+        try!(boring.exclude(&matches.opt_strs("exclude-sym"), &matches.opt_strs("exclude-pretty")));
+        Ok(boring)
+    }
+
+    // Also skips the symbols matching one of |syms| and those whose
+    // pretty name matches one of |pretties|, as given to the graph tools'
+    // --exclude-sym and --exclude-pretty options. Unlike the config
+    // patterns, these only look at one of the names.
+    pub fn exclude(&mut self, syms: &[String], pretties: &[String]) -> Result<(), String> {
+        self.sym_patterns.extend(try!(compile_patterns(syms, "--exclude-sym")));
+        self.pretty_patterns.extend(try!(compile_patterns(pretties, "--exclude-pretty")));
+        Ok(())
+    }
+
+    pub fn is_boring(&self, sym: &str, pretty: &str) -> bool {
+        self.patterns.iter().any(|p| p.is_match(sym) || (!pretty.is_empty() && p.is_match(pretty))) ||
+            self.sym_patterns.iter().any(|p| p.is_match(sym)) ||
+            (!pretty.is_empty() && self.pretty_patterns.iter().any(|p| p.is_match(pretty)))
+    }
+}
+
+// Returns the name of the set in the tree's entry_points config entry
+// that lists |sym| or its pretty name. When |only| is given, other sets
+// are ignored.
+pub fn entry_point_set<'a>(tree_config: &'a TreeConfig, only: Option<&str>,
+                           sym: &str, pretty: &str) -> Option<&'a str> {
+    let sets = match tree_config.paths.entry_points {
+        Some(ref sets) => sets,
+        None => return None,
+    };
+    for (name, entries) in sets {
+        if only.map_or(false, |only| only != name) {
+            continue;
+        }
+        if entries.iter().any(|e| e == sym || (!pretty.is_empty() && e == pretty)) {
+            return Some(name);
+        }
+    }
+    None
+}
+
+// Returns the functions that contain a |kind| record ("Uses",
+// "Assignments", etc.) of |sym|, as (symbol, pretty) pairs. This relies
+// on the contextsym recorded for each record.
+pub fn find_users(crossref: &CrossrefMap, sym: &str, kind: &str) -> Vec<(String, String)> {
+    let entry = match crossref.lookup(sym) {
+        Some(entry) => entry,
+        None => return vec![],
+    };
+
+    let mut users = BTreeMap::new();
+    for path in entry.get(kind) {
+        for line in &path.lines {
+            // The context may list several symbols when the enclosing
+            // method overrides others. The first one is the method itself.
+            let user = line.contextsym.split(',').next().unwrap();
+            if user.is_empty() || user == sym {
+                continue;
+            }
+            users.insert(user.to_string(), line.context.clone());
+        }
+    }
+    users.into_iter().collect()
+}
+
+// Names given by outside tools may be symbols or qualified pretty
+// names. A pretty name can resolve to several symbols when it is
+// overloaded.
+pub fn resolve_name(name: &str, crossref: &CrossrefMap, ids: &IdentMap) -> Vec<String> {
+    if crossref.contains(name) {
+        return vec![name.to_string()];
+    }
+    ids.lookup_raw(name, true, false, 16).into_iter()
+        .filter(|&(id, _)| id == name)
+        .map(|(_, sym)| sym.to_string())
+        .collect()
+}
+
+// Graphs that were bookmarked by their root symbol should still work
+// after the symbol is renamed or merged. Returns the symbol that |sym|
+// became, if it is gone from the crossref, along with a note saying so.
+pub fn follow_rename(sym: &str, crossref: &CrossrefMap,
+                     renames: &HashMap<String, (String, String)>) -> Option<(String, String)> {
+    if crossref.contains(sym) {
+        return None;
+    }
+    renames.get(sym).map(|&(ref new, ref reason)| {
+        (new.clone(), format!("{} is now {} ({})", sym, new, reason))
+    })
+}
+
+// Returns the functions that contain a use of |sym|.
+pub fn find_callers(crossref: &CrossrefMap, sym: &str) -> Vec<(String, String)> {
+    find_users(crossref, sym, "Uses")
+}
+
+// The crossref tool also writes ${index}/callers, which has the result
+// of find_callers for every symbol that has any, in the crossref's
+// format: a line with the symbol and then a line with a JSON array of
+// [caller, pretty] pairs. Heavily used symbols have huge Uses lists, so
+// reading this instead saves the walks up the graph from parsing them.
+// Returns None for trees indexed without the file.
+pub fn load_callers(index_path: &str) -> Option<CrossrefMap> {
+    let filename = format!("{}/callers", index_path);
+    match fs::metadata(&filename) {
+        Ok(ref metadata) if metadata.len() > 0 => Some(CrossrefMap::new(&filename)),
+        _ => None,
+    }
+}
+
+// Looks up the callers of |sym| in a map returned by load_callers.
+pub fn read_callers(callers: &CrossrefMap, sym: &str) -> Vec<(String, String)> {
+    let json = match callers.lookup_json(sym) {
+        Some(json) => json,
+        None => return vec![],
+    };
+    json.as_array().map_or(vec![], |pairs| {
+        pairs.iter().filter_map(|pair| {
+            let pair = match pair.as_array() {
+                Some(pair) if pair.len() == 2 => pair,
+                _ => return None,
+            };
+            match (pair[0].as_string(), pair[1].as_string()) {
+                (Some(sym), Some(pretty)) => Some((sym.to_string(), pretty.to_string())),
+                _ => None,
+            }
+        }).collect()
+    })
+}
+
+// Returns the locations (path and line number) of the uses of |callee|
+// inside |caller|.
+pub fn find_uses(crossref: &CrossrefMap, caller: &str, callee: &str) -> Vec<(String, u64)> {
+    let entry = match crossref.lookup(callee) {
+        Some(entry) => entry,
+        None => return vec![],
+    };
+
+    let mut uses = vec![];
+    for path in entry.get("Uses") {
+        for line in &path.lines {
+            if line.contextsym.split(',').next().unwrap() == caller {
+                uses.push((path.path.clone(), line.lineno));
+            }
+        }
+    }
+    uses
+}
+
+// Maps each function to the symbols it uses, as (symbol, pretty) pairs,
+// by inverting the contextsym of every use in the crossref. This reads
+// every entry in the crossref, so it is slow on large trees. The pretty
+// name of a callee is only known if it contains uses of its own.
+pub fn callee_map(crossref: &CrossrefMap) -> HashMap<String, Vec<(String, String)>> {
+    let mut pretty = HashMap::new();
+    let mut callees = HashMap::new();
+    for sym in crossref.symbols() {
+        let entry = match crossref.lookup(sym) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        for path in entry.get("Uses") {
+            for line in &path.lines {
+                let caller = line.contextsym.split(',').next().unwrap();
+                if caller.is_empty() || caller == sym {
+                    continue;
+                }
+                pretty.insert(caller.to_string(), line.context.clone());
+                callees.entry(caller.to_string()).or_insert(BTreeSet::new()).insert(sym.to_string());
+            }
+        }
+    }
+
+    callees.into_iter().map(|(caller, syms)| {
+        let syms = syms.into_iter().map(|sym| {
+            let name = pretty.get(&sym).cloned().unwrap_or("".to_string());
+            (sym, name)
+        }).collect();
+        (caller, syms)
+    }).collect()
+}
+
+// A relationship between symbols that the graph walks can follow. The
+// related symbols are returned as (symbol, pretty) pairs. If incoming()
+// is true, edges point from the related symbols to |sym| (as for
+// callers); otherwise they point from |sym| to them.
+pub trait Relationship {
+    fn related(&self, sym: &str) -> Vec<(String, String)>;
+    fn incoming(&self) -> bool;
+}
+
+// The functions containing a |kind| record of a symbol. With "Uses"
+// these are its callers.
+pub struct Users<'a> {
+    pub crossref: &'a CrossrefMap,
+    pub kind: &'a str,
+}
+
+impl<'a> Relationship for Users<'a> {
+    fn related(&self, sym: &str) -> Vec<(String, String)> {
+        find_users(self.crossref, sym, self.kind)
+    }
+
+    fn incoming(&self) -> bool {
+        true
+    }
+}
+
+// The callers of a symbol, read from the map returned by load_callers
+// if there is one and from the crossref's Uses otherwise.
+pub struct Callers<'a> {
+    pub crossref: &'a CrossrefMap,
+    pub callers: Option<&'a CrossrefMap>,
+}
+
+impl<'a> Relationship for Callers<'a> {
+    fn related(&self, sym: &str) -> Vec<(String, String)> {
+        match self.callers {
+            Some(callers) => read_callers(callers, sym),
+            None => find_callers(self.crossref, sym),
+        }
+    }
+
+    fn incoming(&self) -> bool {
+        true
+    }
+}
+
+// The symbols a function uses, from a map built by callee_map.
+pub struct Callees<'a> {
+    pub callees: &'a HashMap<String, Vec<(String, String)>>,
+}
+
+impl<'a> Relationship for Callees<'a> {
+    fn related(&self, sym: &str) -> Vec<(String, String)> {
+        self.callees.get(sym).cloned().unwrap_or(vec![])
+    }
+
+    fn incoming(&self) -> bool {
+        false
+    }
+}
+
+// Walks |relationship| breadth-first starting from |roots| (given as
+// (symbol, pretty) pairs), going at most |max_depth| hops and adding at
+// most |node_limit| nodes. Symbols in |boring| are skipped, except for
+// the roots.
+pub fn collect(relationship: &Relationship,
+               roots: &[(String, String)],
+               boring: &BoringSymbols,
+               max_depth: u32,
+               node_limit: usize) -> SymbolGraph {
+    let mut graph = SymbolGraph::new();
+    let mut to_visit = VecDeque::new();
+
+    for &(ref sym, ref pretty) in roots {
+        if graph.find(sym).is_none() {
+            to_visit.push_back(graph.add_node(sym, pretty, 0));
+        }
+    }
+
+    while let Some(node) = to_visit.pop_front() {
+        let depth = graph.nodes[node].depth;
+        if depth >= max_depth {
+            continue;
+        }
+
+        let sym = graph.nodes[node].sym.clone();
+        for (other, pretty) in relationship.related(&sym) {
+            if boring.is_boring(&other, &pretty) {
+                graph.filtered.insert(other);
+                continue;
+            }
+            let other_node = match graph.find(&other) {
+                Some(other_node) => other_node,
+                None => {
+                    if graph.nodes.len() >= node_limit {
+                        graph.truncated = true;
+                        graph.dropped.insert(other);
+                        continue;
+                    }
+                    let other_node = graph.add_node(&other, &pretty, depth + 1);
+                    to_visit.push_back(other_node);
+                    other_node
+                }
+            };
+            if relationship.incoming() {
+                graph.add_edge(other_node, node);
+            } else {
+                graph.add_edge(node, other_node);
+            }
+        }
+    }
+
+    graph
+}
+
+// Returns the number of uses of |sym| in the tree, a rough measure of
+// how central it is.
+pub fn use_count(crossref: &CrossrefMap, sym: &str) -> usize {
+    match crossref.lookup(sym) {
+        Some(entry) => entry.get("Uses").iter().map(|path| path.lines.len()).sum(),
+        None => 0,
+    }
+}
+
+// The symbols found next to the graph but not yet added to it, as
+// (importance, inverted depth, inverted order found, from, symbol,
+// pretty), so that ties in importance go to the shallowest and then to
+// the first found.
+struct Frontier {
+    candidates: BinaryHeap<(usize, u32, usize, usize, String, String)>,
+    found: usize,
+}
+
+fn expand(relationship: &Relationship,
+          boring: &BoringSymbols,
+          importance: &Fn(&str) -> usize,
+          graph: &mut SymbolGraph,
+          frontier: &mut Frontier,
+          node: usize) {
+    let depth = graph.nodes[node].depth;
+    let sym = graph.nodes[node].sym.clone();
+    for (other, pretty) in relationship.related(&sym) {
+        if boring.is_boring(&other, &pretty) {
+            graph.filtered.insert(other);
+            continue;
+        }
+        match graph.find(&other) {
+            Some(other_node) => link(relationship, graph, node, other_node),
+            None => {
+                let order = usize::max_value() - frontier.found;
+                frontier.found += 1;
+                frontier.candidates.push((importance(&other), u32::max_value() - depth, order, node, other, pretty));
+            }
+        }
+    }
+}
+
+fn link(relationship: &Relationship, graph: &mut SymbolGraph, node: usize, other_node: usize) {
+    if relationship.incoming() {
+        graph.add_edge(other_node, node);
+    } else {
+        graph.add_edge(node, other_node);
+    }
+}
+
+// Like collect, but instead of adding symbols breadth-first it always
+// adds the most important one (as given by |importance|, for instance
+// use_count) of all those found next to the graph so far. When the node
+// limit is reached, the graph then holds the most central symbols
+// rather than whichever were nearest. Each node's depth is one more
+// than the node it was found from, so path_to_root still works, but it
+// is not always the shortest distance from a root.
+pub fn collect_ranked(relationship: &Relationship,
+                      roots: &[(String, String)],
+                      boring: &BoringSymbols,
+                      max_depth: u32,
+                      node_limit: usize,
+                      importance: &Fn(&str) -> usize) -> SymbolGraph {
+    let mut graph = SymbolGraph::new();
+    let mut frontier = Frontier { candidates: BinaryHeap::new(), found: 0 };
+
+    let mut added = vec![];
+    for &(ref sym, ref pretty) in roots {
+        if graph.find(sym).is_none() {
+            added.push(graph.add_node(sym, pretty, 0));
+        }
+    }
+    if max_depth > 0 {
+        for node in added {
+            expand(relationship, boring, importance, &mut graph, &mut frontier, node);
+        }
+    }
+
+    while let Some((_, _, _, from, other, pretty)) = frontier.candidates.pop() {
+        if let Some(other_node) = graph.find(&other) {
+            link(relationship, &mut graph, from, other_node);
+            continue;
+        }
+        if graph.nodes.len() >= node_limit {
+            graph.truncated = true;
+            graph.dropped.insert(other);
+            continue;
+        }
+        let depth = graph.nodes[from].depth + 1;
+        let other_node = graph.add_node(&other, &pretty, depth);
+        link(relationship, &mut graph, from, other_node);
+        if depth < max_depth {
+            expand(relationship, boring, importance, &mut graph, &mut frontier, other_node);
+        }
+    }
+
+    graph
+}
+
+// Another relationship without the symbols that are defined in files
+// of the |exclude| classes (such as "test"), to keep tests and
+// generated glue out of a graph. Symbols without a definition are kept.
+pub struct Pruned<'a> {
+    pub inner: &'a Relationship,
+    pub crossref: &'a CrossrefMap,
+    pub classes: &'a FileClasses,
+    pub exclude: &'a [String],
+}
+
+impl<'a> Relationship for Pruned<'a> {
+    fn related(&self, sym: &str) -> Vec<(String, String)> {
+        let mut related = self.inner.related(sym);
+        if !self.exclude.is_empty() {
+            related.retain(|&(ref other, _)| {
+                let path = match self.crossref.lookup(other) {
+                    Some(entry) => entry.first_def().map(|(path, _)| path.to_string()),
+                    None => None,
+                };
+                match path {
+                    Some(path) => !self.exclude.iter().any(|class| class == self.classes.class_of(&path)),
+                    None => true,
+                }
+            });
+        }
+        related
+    }
+
+    fn incoming(&self) -> bool {
+        self.inner.incoming()
+    }
+}
+
+// Another relationship that stops returning anything once |inner| has
+// been asked about |max_lookups| symbols or |max_millis| milliseconds
+// have passed since it was made, so that a walk through a pathological
+// symbol ends with a partial graph instead of running on.
+pub struct Budgeted<'a> {
+    inner: &'a Relationship,
+    max_lookups: Option<usize>,
+    max_millis: Option<u64>,
+    deadline: Option<Instant>,
+    lookups: Cell<usize>,
+    exhausted: Cell<bool>,
+}
+
+impl<'a> Budgeted<'a> {
+    pub fn new(inner: &'a Relationship, max_lookups: Option<usize>, max_millis: Option<u64>) -> Budgeted<'a> {
+        Budgeted {
+            inner: inner,
+            max_lookups: max_lookups,
+            max_millis: max_millis,
+            deadline: max_millis.map(|ms| Instant::now() + Duration::from_millis(ms)),
+            lookups: Cell::new(0),
+            exhausted: Cell::new(false),
+        }
+    }
+
+    // Returns a note saying which budget ran out, or None if the walk
+    // stayed within them.
+    pub fn exhausted_note(&self) -> Option<String> {
+        if !self.exhausted.get() {
+            return None;
+        }
+        let mut budgets = vec![];
+        if let Some(max_lookups) = self.max_lookups {
+            budgets.push(format!("{} lookups", max_lookups));
+        }
+        if let Some(max_millis) = self.max_millis {
+            budgets.push(format!("{} ms", max_millis));
+        }
+        Some(format!("The budget of {} ran out after {} lookups; the neighbors of the remaining \
+                      symbols were not examined.", budgets.join(" or "), self.lookups.get()))
+    }
+}
+
+impl<'a> Relationship for Budgeted<'a> {
+    fn related(&self, sym: &str) -> Vec<(String, String)> {
+        if !self.exhausted.get() {
+            let out_of_lookups = self.max_lookups.map_or(false, |max| self.lookups.get() >= max);
+            let out_of_time = self.deadline.map_or(false, |deadline| Instant::now() >= deadline);
+            self.exhausted.set(out_of_lookups || out_of_time);
+        }
+        if self.exhausted.get() {
+            return vec![];
+        }
+        self.lookups.set(self.lookups.get() + 1);
+        self.inner.related(sym)
+    }
+
+    fn incoming(&self) -> bool {
+        self.inner.incoming()
+    }
+}
+
+// Walks callees breadth-first from |root|, using a map built by
+// callee_map.
+pub fn collect_callees(callees: &HashMap<String, Vec<(String, String)>>,
+                       root: (&str, &str),
+                       boring: &BoringSymbols,
+                       max_depth: u32,
+                       node_limit: usize) -> SymbolGraph {
+    let roots = [(root.0.to_string(), root.1.to_string())];
+    collect(&Callees { callees: callees }, &roots, boring, max_depth, node_limit)
+}
+
+// Walks callers breadth-first starting from |roots|, using |callers|
+// (from load_callers) if given.
+pub fn collect_callers(crossref: &CrossrefMap,
+                       callers: Option<&CrossrefMap>,
+                       roots: &[(String, String)],
+                       boring: &BoringSymbols,
+                       max_depth: u32,
+                       node_limit: usize) -> SymbolGraph {
+    collect(&Callers { crossref: crossref, callers: callers }, roots, boring, max_depth, node_limit)
+}
+
+// Predicts how many nodes a walk from |roots| would find within each
+// number of hops, without doing the walk, so callers can warn or pick a
+// smaller depth before spending the node limit. The first hop is
+// counted exactly; deeper levels assume that every node has as many
+// neighbors as the average of up to |sample| nodes from the first hop.
+// The result has an entry for each depth from 0 to |max_depth|.
+pub fn estimate(relationship: &Relationship,
+                roots: &[(String, String)],
+                boring: &BoringSymbols,
+                max_depth: u32,
+                sample: usize) -> Vec<usize> {
+    let root_syms = roots.iter().map(|&(ref sym, _)| sym.as_str()).collect::<HashSet<_>>();
+    let mut totals = vec![root_syms.len()];
+    if max_depth == 0 {
+        return totals;
+    }
+
+    let mut first = BTreeSet::new();
+    for &(ref sym, _) in roots {
+        for (other, pretty) in relationship.related(sym) {
+            if !boring.is_boring(&other, &pretty) && !root_syms.contains(other.as_str()) {
+                first.insert(other);
+            }
+        }
+    }
+    totals.push(root_syms.len() + first.len());
+
+    let sampled = first.iter().take(sample).map(|sym| {
+        relationship.related(sym).into_iter().filter(|&(ref other, ref pretty)| !boring.is_boring(other, pretty)).count()
+    }).collect::<Vec<_>>();
+    let branching = if sampled.is_empty() {
+        0.0
+    } else {
+        sampled.iter().sum::<usize>() as f64 / sampled.len() as f64
+    };
+
+    let mut level = first.len() as f64;
+    let mut total = totals[1] as f64;
+    for _ in 1 .. max_depth {
+        level *= branching;
+        total = (total + level).min(1e12);
+        totals.push(total as usize);
+    }
+    totals
+}
+
+// Returns the largest depth whose estimated size fits in |node_limit|,
+// given the totals returned by estimate, if there is one.
+pub fn fit_depth(totals: &[usize], node_limit: usize) -> Option<u32> {
+    totals.iter().rposition(|&total| total <= node_limit).map(|depth| depth as u32)
+}
+
+// Returns a warning to print before a walk that is expected to run into
+// the node limit, or None.
+pub fn estimate_warning(totals: &[usize], node_limit: usize) -> Option<String> {
+    let depth = totals.len() - 1;
+    if totals[depth] <= node_limit {
+        return None;
+    }
+    let advice = match fit_depth(totals, node_limit) {
+        Some(fit) if fit > 0 => format!("--max-depth {} is expected to fit, or pass --fit-depth to use it.", fit),
+        _ => "Even the direct callers may not fit; raise --node-limit.".to_string(),
+    };
+    Some(format!("About {} symbols are expected within {} hops, over the node limit of {}. {}",
+                 totals[depth], depth, node_limit, advice))
+}
+
+// Adds the options of the tools that write out a graph, which is
+// described as |what| (such as "the dominator tree") in their help.
+pub fn add_output_options(opts: &mut Options, what: &str) {
+    opts.optopt("o", "dot",
+                &format!("File to write {} to, in dot format", what),
+                "FILE");
+    opts.optopt("", "html",
+                &format!("File to write {} to, as an interactive HTML page", what),
+                "FILE");
+    opts.optopt("", "graphml",
+                &format!("File to write {} to, in GraphML format", what),
+                "FILE");
+    opts.optopt("", "cytoscape",
+                &format!("File to write {} to, as Cytoscape.js JSON", what),
+                "FILE");
+    opts.optopt("", "mermaid",
+                &format!("File to write {} to, as a Mermaid flowchart", what),
+                "FILE");
+    opts.optopt("", "cluster",
+                "Group the nodes of the --dot output by the first LEVELS directories of their definitions",
+                "LEVELS");
+    opts.optopt("", "collapse",
+                "Draw all the methods of a class as one node in the written graphs (KIND is class), or each cycle as one node (KIND is cycles)",
+                "KIND");
+    opts.optopt("", "keep-central",
+                "Only draw the roots and the COUNT most central other symbols in the written graphs",
+                "COUNT");
+    opts.optflag("", "reduce",
+                 "Leave out edges implied by longer paths in the written graphs");
+    opts.optopt("", "link-base",
+                "URL of the tree (such as https://searchfox.org/mozilla-central) to link nodes to definitions on",
+                "URL");
+}
+
+// Transforms |graph| as the options added by add_output_options ask,
+// and writes it to every file they name. Edges of the |edge_kind|
+// "uses" are calls, which get their call sites; |title| is the title of
+// the HTML page. Returns an error for an unknown --collapse kind.
+pub fn write_outputs(matches: &Matches, mut graph: SymbolGraph, tree_config: &TreeConfig, crossref: &CrossrefMap,
+                     edge_kind: &str, title: &str) -> Result<(), String> {
+    let outputs = ["dot", "html", "graphml", "cytoscape", "mermaid"];
+    if !outputs.iter().any(|name| matches.opt_present(name)) {
+        return Ok(());
+    }
+
+    if edge_kind == "uses" {
+        graph.add_call_sites(crossref);
+    }
+    match matches.opt_str("collapse") {
+        Some(ref kind) if kind == "class" => {
+            graph = graph.collapse_classes(&|sym| crossref.lookup(sym).is_some());
+        },
+        Some(ref kind) if kind == "cycles" => {
+            graph = graph.condense_cycles();
+        },
+        Some(kind) => return Err(format!("Unknown --collapse kind {}; only class and cycles are supported.", kind)),
+        None => {},
+    }
+    if let Some(count) = matches.opt_str("keep-central") {
+        let count = try!(count.parse::<usize>().map_err(|_| format!("Invalid --keep-central {}", count)));
+        let total = graph.nodes.len();
+        graph = graph.keep_central(count);
+        if graph.nodes.len() < total {
+            println!("");
+            println!("Only {} of the {} symbols are drawn; dashed edges stand for paths through the others.",
+                     graph.nodes.len(), total);
+        }
+    }
+    if matches.opt_present("reduce") {
+        graph.remove_implied_edges();
+    }
+    graph.add_summaries(&read_doc_summaries(&format!("{}/doc-summaries", tree_config.paths.index_path)));
+    if let Some(link_base) = matches.opt_str("link-base") {
+        graph.add_links(crossref, &link_base);
+    }
+    if let Some(levels) = matches.opt_str("cluster") {
+        let levels = try!(levels.parse::<usize>().map_err(|_| format!("Invalid --cluster {}", levels)));
+        graph.add_clusters(crossref, levels);
+    }
+
+    for name in &outputs {
+        let fname = match matches.opt_str(name) {
+            Some(fname) => fname,
+            None => continue,
+        };
+        let mut file = try!(File::create(&fname).map_err(|e| format!("{}: {}", fname, e)));
+        let result = match *name {
+            "dot" => graph.write_dot(&mut file),
+            "html" => graph.write_html(&mut file, title),
+            "graphml" => graph.write_graphml(&mut file, edge_kind),
+            "cytoscape" => graph.write_cytoscape(&mut file, edge_kind),
+            _ => graph.write_mermaid(&mut file),
+        };
+        try!(result.map_err(|e| format!("{}: {}", fname, e)));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_write_dot_escapes_labels() {
+    let mut graph = SymbolGraph::new();
+    let a = graph.add_node("_Z1av", "a", 0);
+    let b = graph.add_node("_Z1bv", "operator\"\"", 1);
+    graph.add_edge(b, a);
+    assert_eq!(graph.add_node("_Z1av", "ignored", 3), a);
+
+    let mut output = Vec::new();
+    graph.write_dot(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("n1 [label=\"operator\\\"\\\"\"];"), "{}", output);
+    assert!(output.contains("n1 -> n0;"), "{}", output);
+    assert!(output.contains("{ rank=same; /* depth 0 */ n0; }"), "{}", output);
+    assert!(output.contains("{ rank=same; /* depth 1 */ n1; }"), "{}", output);
+
+    graph.nodes[a].cluster = "dom/base".to_string();
+    graph.nodes[b].cluster = "dom".to_string();
+    let mut output = Vec::new();
+    graph.write_dot(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("  subgraph \"cluster_dom\" {\n    label=\"dom/\";\n    n1;\n    subgraph \"cluster_dom/base\" {\n      label=\"dom/base/\";\n      n0;\n    }\n  }\n"), "{}", output);
+    assert!(!output.contains("rank=same"), "{}", output);
+
+    let mut summaries = HashMap::new();
+    summaries.insert("_Z1av".to_string(), "Does \"a\".".to_string());
+    graph.add_summaries(&summaries);
+    let mut output = Vec::new();
+    graph.write_dot(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("n0 [label=\"a\", tooltip=\"Does \\\"a\\\".\"];"), "{}", output);
+}
+
+#[test]
+fn test_call_sites() {
+    let mut graph = SymbolGraph::new();
+    let a = graph.add_node("_Z1av", "a", 0);
+    let b = graph.add_node("_Z1bv", "b", 1);
+    graph.add_edge(b, a);
+    graph.call_sites.insert((b, a), (4, "b.cpp".to_string(), 12));
+    graph.link_base = "https://searchfox.org/mozilla-central".to_string();
+
+    let mut output = Vec::new();
+    graph.write_dot(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("n1 -> n0 [penwidth=3, tooltip=\"4 uses, first at b.cpp:12\", \
+                             URL=\"https://searchfox.org/mozilla-central/source/b.cpp#12\", target=\"_top\"];"), "{}", output);
+
+    let mut output = Vec::new();
+    graph.write_mermaid(&mut output).unwrap();
+    assert!(String::from_utf8(output).unwrap().contains("  n1 -->|4| n0\n"));
+}
+
+#[test]
+fn test_collapse_classes() {
+    assert_eq!(qualifier("nsTArray<mozilla::Foo>::Length"), Some("nsTArray<mozilla::Foo>"));
+    assert_eq!(qualifier("mozilla::dom::Element::SetAttr"), Some("mozilla::dom::Element"));
+    assert_eq!(qualifier("main"), None);
+
+    let mut graph = SymbolGraph::new();
+    let f = graph.add_node("_ZN1A1fEv", "A::f", 0);
+    let g = graph.add_node("_ZN1A1gEv", "A::g", 1);
+    let h = graph.add_node("_ZN1B1hEv", "B::h", 1);
+    let m = graph.add_node("_ZN2ns4mainEv", "ns::main", 2);
+    graph.add_edge(g, f);
+    graph.add_edge(h, f);
+    graph.add_edge(h, g);
+    graph.add_edge(m, h);
+    graph.call_sites.insert((h, f), (2, "b.cpp".to_string(), 10));
+    graph.call_sites.insert((h, g), (1, "b.cpp".to_string(), 12));
+
+    let collapsed = graph.collapse_classes(&|sym| sym == "T_A" || sym == "T_B");
+    let names = collapsed.nodes.iter().map(|n| (n.sym.as_str(), n.pretty.as_str(), n.depth)).collect::<Vec<_>>();
+    assert_eq!(names, vec![("T_A", "A", 0), ("T_B", "B", 1), ("_ZN2ns4mainEv", "ns::main", 2)]);
+    assert_eq!(collapsed.edges.iter().cloned().collect::<Vec<_>>(), vec![(1, 0), (2, 1)]);
+    assert_eq!(collapsed.call_sites.get(&(1, 0)), Some(&(3, "b.cpp".to_string(), 10)));
+    assert_eq!(collapsed.call_sites.get(&(2, 1)), None);
+}
+
+#[test]
+fn test_condense_cycles() {
+    let mut graph = SymbolGraph::new();
+    let a = graph.add_node("a", "", 0);
+    let b = graph.add_node("b", "", 1);
+    let c = graph.add_node("c", "", 2);
+    let d = graph.add_node("d", "", 3);
+    graph.add_edge(b, a);
+    graph.add_edge(c, b);
+    graph.add_edge(b, c);
+    graph.add_edge(d, c);
+    graph.add_edge(d, d);
+    assert_eq!(graph.cycles(), vec![vec![b, c]]);
+
+    let condensed = graph.condense_cycles();
+    let names = condensed.nodes.iter().map(|n| n.pretty.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["", "b (cycle of 2)", ""]);
+    assert_eq!(condensed.nodes[1].summary, "Cycle of b, c");
+    assert_eq!(condensed.edges.iter().cloned().collect::<Vec<_>>(), vec![(1, 0), (2, 1)]);
+}
+
+#[test]
+fn test_elementary_cycles() {
+    let mut graph = SymbolGraph::new();
+    let a = graph.add_node("a", "", 0);
+    let b = graph.add_node("b", "", 1);
+    let c = graph.add_node("c", "", 2);
+    let d = graph.add_node("d", "", 1);
+    graph.add_edge(a, b);
+    graph.add_edge(b, a);
+    graph.add_edge(b, c);
+    graph.add_edge(c, b);
+    graph.add_edge(d, d);
+    graph.add_edge(d, a);
+    assert_eq!(graph.elementary_cycles(10), (vec![vec![a, b], vec![b, c], vec![d]], false));
+    assert_eq!(graph.elementary_cycles(3), (vec![vec![a, b], vec![b, c], vec![d]], false));
+    assert_eq!(graph.elementary_cycles(2), (vec![vec![a, b], vec![b, c]], true));
+}
+
+#[test]
+fn test_remove_implied_edges() {
+    let mut graph = SymbolGraph::new();
+    let a = graph.add_node("a", "", 0);
+    let b = graph.add_node("b", "", 1);
+    let c = graph.add_node("c", "", 1);
+    let d = graph.add_node("d", "", 2);
+    graph.add_edge(a, b);
+    graph.add_edge(b, c);
+    graph.add_edge(a, c);
+    graph.add_edge(c, d);
+    graph.add_edge(a, d);
+    graph.call_sites.insert((a, d), (1, "a.cpp".to_string(), 3));
+    graph.remove_implied_edges();
+    assert_eq!(graph.edges.iter().cloned().collect::<Vec<_>>(), vec![(a, b), (b, c), (c, d)]);
+    assert!(graph.call_sites.is_empty());
+
+    // b -> c is implied by b -> a -> c, and a -> c by a -> b -> c, but
+    // only one of them can go.
+    let mut graph = SymbolGraph::new();
+    let a = graph.add_node("a", "", 0);
+    let b = graph.add_node("b", "", 1);
+    let c = graph.add_node("c", "", 2);
+    graph.add_edge(a, b);
+    graph.add_edge(b, a);
+    graph.add_edge(a, c);
+    graph.add_edge(b, c);
+    graph.remove_implied_edges();
+    assert_eq!(graph.edges.iter().cloned().collect::<Vec<_>>(), vec![(a, b), (b, a), (b, c)]);
+}
+
+#[test]
+fn test_keep_central() {
+    // root <- hub <- a, b, mid; mid <- c
+    let mut graph = SymbolGraph::new();
+    let root = graph.add_node("root", "", 0);
+    let hub = graph.add_node("hub", "", 1);
+    let a = graph.add_node("a", "", 2);
+    let b = graph.add_node("b", "", 2);
+    let mid = graph.add_node("mid", "", 2);
+    let c = graph.add_node("c", "", 3);
+    graph.add_edge(hub, root);
+    graph.add_edge(a, hub);
+    graph.add_edge(b, hub);
+    graph.add_edge(mid, hub);
+    graph.add_edge(c, mid);
+    graph.call_sites.insert((a, hub), (2, "a.cpp".to_string(), 5));
+
+    let kept = graph.keep_central(3);
+    let syms = kept.nodes.iter().map(|n| n.sym.as_str()).collect::<Vec<_>>();
+    assert_eq!(syms, vec!["root", "hub", "a", "mid"]);
+    assert_eq!(kept.call_sites.get(&(2, 1)), Some(&(2, "a.cpp".to_string(), 5)));
+    assert!(kept.elided.is_empty());
+
+    let kept = graph.keep_central(1);
+    let syms = kept.nodes.iter().map(|n| n.sym.as_str()).collect::<Vec<_>>();
+    assert_eq!(syms, vec!["root", "hub"]);
+    assert_eq!(kept.edges.iter().cloned().collect::<Vec<_>>(), vec![(1, 0)]);
+
+    let kept = graph.keep_central(0);
+    assert_eq!(kept.nodes.len(), 1);
+    assert!(kept.edges.is_empty());
+
+    // root <- mid <- hub <- a, b, c: hub lies on more shortest paths
+    // than mid, so keeping one node drops mid, and hub gets an edge
+    // standing for the path through it.
+    let mut graph = SymbolGraph::new();
+    let root = graph.add_node("root", "", 0);
+    let mid = graph.add_node("mid", "", 1);
+    let hub = graph.add_node("hub", "", 2);
+    graph.add_edge(mid, root);
+    graph.add_edge(hub, mid);
+    for sym in &["a", "b", "c"] {
+        let caller = graph.add_node(sym, "", 3);
+        graph.add_edge(caller, hub);
+    }
+    let kept = graph.keep_central(1);
+    let syms = kept.nodes.iter().map(|n| n.sym.as_str()).collect::<Vec<_>>();
+    assert_eq!(syms, vec!["root", "hub"]);
+    assert_eq!(kept.edges.iter().cloned().collect::<Vec<_>>(), vec![(1, 0)]);
+    assert!(kept.elided.contains(&(1, 0)));
+
+    let mut output = Vec::new();
+    kept.write_dot(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("n1 -> n0 [style=dashed"), "{}", output);
+}
+
+#[test]
+fn test_write_graphml() {
+    let mut graph = SymbolGraph::new();
+    let a = graph.add_node("_Z1av", "a", 0);
+    let b = graph.add_node("T_Vec<int>", "Vec<int>", 1);
+    graph.add_edge(b, a);
+
+    let mut output = Vec::new();
+    graph.write_graphml(&mut output, "uses").unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("<node id=\"n1\">\n      <data key=\"sym\">T_Vec&lt;int&gt;</data>"), "{}", output);
+    assert!(output.contains("<data key=\"kind\">class</data>"), "{}", output);
+    assert!(output.contains("<data key=\"depth\">1</data>"), "{}", output);
+    assert!(output.contains("<edge source=\"n1\" target=\"n0\"><data key=\"edge_kind\">uses</data></edge>"), "{}", output);
+}
+
+#[test]
+fn test_write_mermaid() {
+    let mut graph = SymbolGraph::new();
+    let a = graph.add_node("_Z1av", "a", 0);
+    let b = graph.add_node("#b", "operator\"\"<T>", 1);
+    graph.add_edge(b, a);
+
+    let mut output = Vec::new();
+    graph.write_mermaid(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(output, "flowchart LR\n  n0[\"a\"]\n  n1[\"operator#quot;#quot;#lt;T#gt;\"]\n  n1 --> n0\n");
+
+    graph.nodes[b].url = format!("https://searchfox.org/mozilla-central/search?q=symbol:{}", encode_url_component("#b"));
+    let mut output = Vec::new();
+    graph.write_mermaid(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("  click n1 href \"https://searchfox.org/mozilla-central/search?q=symbol:%23b\" \"#35;b\"\n"), "{}", output);
+
+    let mut output = Vec::new();
+    graph.write_dot(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("URL=\"https://searchfox.org/mozilla-central/search?q=symbol:%23b\", target=\"_top\""), "{}", output);
+}
+
+#[test]
+fn test_write_cytoscape() {
+    let mut graph = SymbolGraph::new();
+    let a = graph.add_node("_Z1av", "a", 0);
+    let b = graph.add_node("T_Foo", "", 1);
+    graph.add_edge(b, a);
+
+    let mut output = Vec::new();
+    graph.write_cytoscape(&mut output, "uses").unwrap();
+    let json = Json::from_str(&String::from_utf8(output).unwrap()).unwrap();
+    let nodes = json.find_path(&["elements", "nodes"]).unwrap().as_array().unwrap();
+    assert_eq!(nodes[0].find("classes").unwrap().as_string(), Some("function root"));
+    assert_eq!(nodes[1].find_path(&["data", "label"]).unwrap().as_string(), Some("T_Foo"));
+    assert_eq!(nodes[1].find("classes").unwrap().as_string(), Some("class"));
+    let edge = &json.find_path(&["elements", "edges"]).unwrap().as_array().unwrap()[0];
+    assert_eq!(edge.find_path(&["data", "source"]).unwrap().as_string(), Some("n1"));
+    assert_eq!(edge.find_path(&["data", "kind"]).unwrap().as_string(), Some("uses"));
+}
+
+#[test]
+fn test_status() {
+    let mut graph = SymbolGraph::new();
+    let a = graph.add_node("_Z1av", "a", 0);
+    let b = graph.add_node("_Z1bv", "b", 1);
+    let c = graph.add_node("_Z1cv", "c", 1);
+    graph.add_edge(b, a);
+    graph.add_edge(c, a);
+    graph.nodes[b].status = "removed".to_string();
+    graph.nodes[c].status = "added".to_string();
+    graph.edge_status.insert((b, a), "removed".to_string());
+    graph.edge_status.insert((c, a), "added".to_string());
+    graph.call_sites.insert((c, a), (1, "c.cpp".to_string(), 4));
+
+    let mut output = Vec::new();
+    graph.write_dot(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("  n0 [label=\"a\"];\n"), "{}", output);
+    assert!(output.contains("  n1 [label=\"b\", color=red, style=dashed];\n"), "{}", output);
+    assert!(output.contains("  n1 -> n0 [color=red, style=dashed];\n"), "{}", output);
+    assert!(output.contains("  n2 -> n0 [penwidth=1, tooltip=\"1 uses, first at c.cpp:4\", color=green];\n"), "{}", output);
+
+    let mut output = Vec::new();
+    graph.write_cytoscape(&mut output, "uses").unwrap();
+    let json = Json::from_str(&String::from_utf8(output).unwrap()).unwrap();
+    let nodes = json.find_path(&["elements", "nodes"]).unwrap().as_array().unwrap();
+    assert_eq!(nodes[0].find_path(&["data", "status"]), None);
+    assert_eq!(nodes[2].find("classes").unwrap().as_string(), Some("function added"));
+    let edge = &json.find_path(&["elements", "edges"]).unwrap().as_array().unwrap()[0];
+    assert_eq!(edge.find_path(&["data", "status"]).unwrap().as_string(), Some("removed"));
+    assert_eq!(edge.find("classes").unwrap().as_string(), Some("uses removed"));
+}
+
+#[test]
+fn test_write_html() {
+    let mut graph = SymbolGraph::new();
+    let a = graph.add_node("a", "</script>", 0);
+    let b = graph.add_node("b", "", 1);
+    graph.add_edge(b, a);
+
+    let mut output = Vec::new();
+    graph.write_html(&mut output, "Callers of <a>").unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("<title>Callers of &lt;a&gt;</title>"), "{}", output);
+    assert!(output.contains(r#""edges":[[1,0]]"#), "{}", output);
+    assert!(!output.contains("\"</script>"), "{}", output);
+}
+
+#[test]
+fn test_path_to_root() {
+    let mut graph = SymbolGraph::new();
+    let leaf = graph.add_node("leaf", "", 0);
+    let mid = graph.add_node("mid", "", 1);
+    let other = graph.add_node("other", "", 1);
+    let top = graph.add_node("top", "", 2);
+    graph.add_edge(mid, leaf);
+    graph.add_edge(other, leaf);
+    graph.add_edge(top, mid);
+    graph.add_edge(top, other);
+    graph.add_edge(other, mid);
+    assert_eq!(graph.path_to_root(top).len(), 3);
+    assert_eq!(graph.path_to_root(other), vec![other, leaf]);
+    assert_eq!(graph.path_to_root(leaf), vec![leaf]);
+
+    assert_eq!(graph.paths_to_roots(top, 2, 8), (vec![vec![top, mid, leaf], vec![top, other, leaf]], true));
+    assert_eq!(graph.paths_to_roots(top, 3, 8).1, false);
+    assert_eq!(graph.paths_to_roots(top, 5, 8).0.len(), 3);
+    assert_eq!(graph.paths_to_roots(top, 5, 2), (vec![vec![top, mid, leaf], vec![top, other, leaf]], false));
+    assert_eq!(graph.paths_to_roots(leaf, 5, 8), (vec![vec![leaf]], false));
+}
+
+#[test]
+fn test_dominators() {
+    // entry -> a -> {b, c} -> d -> e, plus entry -> c.
+    let mut graph = SymbolGraph::new();
+    let entry = graph.add_node("entry", "", 0);
+    let a = graph.add_node("a", "", 1);
+    let b = graph.add_node("b", "", 2);
+    let c = graph.add_node("c", "", 1);
+    let d = graph.add_node("d", "", 3);
+    let e = graph.add_node("e", "", 4);
+    let unreachable = graph.add_node("unreachable", "", 0);
+    graph.add_edge(entry, a);
+    graph.add_edge(entry, c);
+    graph.add_edge(a, b);
+    graph.add_edge(a, c);
+    graph.add_edge(b, d);
+    graph.add_edge(c, d);
+    graph.add_edge(d, e);
+    graph.add_edge(unreachable, e);
+
+    let idom = graph.dominators(entry);
+    assert_eq!(idom[entry], None);
+    assert_eq!(idom[a], Some(entry));
+    assert_eq!(idom[b], Some(a));
+    assert_eq!(idom[c], Some(entry));
+    assert_eq!(idom[d], Some(entry));
+    assert_eq!(idom[e], Some(d));
+    assert_eq!(idom[unreachable], None);
+}
+
+#[test]
+fn test_boring_symbols() {
+    let boring = BoringSymbols::new(&["^ns(C)?String".to_string(), "^RefPtr<".to_string(), "MOZ_LOG".to_string()]).unwrap();
+    assert!(boring.is_boring("_ZN8nsString6AssignEv", "nsString::Assign"));
+    assert!(boring.is_boring("_Z1fv", "RefPtr<nsIFoo>::operator->"));
+    assert!(!boring.is_boring("_Z8nsStringv", ""));
+    assert!(!boring.is_boring("_ZN3Foo3BarEv", "Foo::Bar"));
+    assert!(!BoringSymbols::new(&[]).unwrap().is_boring("_ZN8nsString6AssignEv", "nsString::Assign"));
+    assert!(BoringSymbols::new(&["^RefPtr<(".to_string()]).err().unwrap().starts_with("Invalid boring_symbols pattern ^RefPtr<(: "));
+
+    let mut boring = BoringSymbols::new(&[]).unwrap();
+    boring.exclude(&["^_Z13NS_DebugBreak".to_string()], &["^mozilla::detail::log".to_string()]).unwrap();
+    assert!(boring.is_boring("_Z13NS_DebugBreakjPKcS0_S0_i", "NS_DebugBreak"));
+    assert!(boring.is_boring("_ZN7mozilla6detail5log_vEv", "mozilla::detail::log_v"));
+    assert!(!boring.is_boring("mozilla::detail::log", ""));
+    assert!(!boring.is_boring("_ZN3Foo3BarEv", "_Z13NS_DebugBreak"));
+    assert!(boring.exclude(&[], &["[".to_string()]).err().unwrap().starts_with("Invalid --exclude-pretty pattern [: "));
+}
+
+#[test]
+fn test_collect() {
+    let mut callees = HashMap::new();
+    callees.insert("main".to_string(), vec![("a".to_string(), "".to_string()), ("log".to_string(), "".to_string())]);
+    callees.insert("a".to_string(), vec![("b".to_string(), "".to_string()), ("main".to_string(), "".to_string())]);
+    let boring = BoringSymbols::new(&["^log$".to_string()]).unwrap();
+
+    let graph = collect_callees(&callees, ("main", ""), &boring, 4, 16);
+    assert_eq!(graph.nodes.iter().map(|n| n.sym.as_str()).collect::<Vec<_>>(), vec!["main", "a", "b"]);
+    assert_eq!(graph.filtered.iter().collect::<Vec<_>>(), vec!["log"]);
+    assert!(graph.edges.contains(&(0, 1)) && graph.edges.contains(&(1, 2)) && graph.edges.contains(&(1, 0)));
+
+    let graph = collect_callees(&callees, ("main", ""), &boring, 1, 2);
+    assert_eq!(graph.nodes.len(), 2);
+    assert!(!graph.truncated);
+    let graph = collect_callees(&callees, ("main", ""), &boring, 2, 2);
+    assert!(graph.truncated);
+    assert_eq!(graph.dropped.iter().collect::<Vec<_>>(), vec!["b"]);
+    assert!(graph.limit_hint(2).unwrap().contains("--node-limit"));
+    assert!(graph.limit_hint(1).unwrap().contains("--max-depth"));
+}
+
+#[test]
+fn test_collect_ranked() {
+    let mut callees = HashMap::new();
+    callees.insert("main".to_string(), vec![("a".to_string(), "".to_string()), ("b".to_string(), "".to_string())]);
+    callees.insert("b".to_string(), vec![("c".to_string(), "".to_string()), ("main".to_string(), "".to_string())]);
+    let uses = |sym: &str| match sym { "a" => 1, "b" => 10, "c" => 5, _ => 0 };
+    let roots = [("main".to_string(), "".to_string())];
+    let boring = BoringSymbols::new(&[]).unwrap();
+
+    let graph = collect_ranked(&Callees { callees: &callees }, &roots, &boring, 4, 3, &uses);
+    assert_eq!(graph.nodes.iter().map(|n| (n.sym.as_str(), n.depth)).collect::<Vec<_>>(),
+               vec![("main", 0), ("b", 1), ("c", 2)]);
+    assert!(graph.edges.contains(&(0, 1)) && graph.edges.contains(&(1, 2)) && graph.edges.contains(&(1, 0)));
+    assert_eq!(graph.dropped.iter().collect::<Vec<_>>(), vec!["a"]);
+
+    let graph = collect_ranked(&Callees { callees: &callees }, &roots, &boring, 1, 16, &uses);
+    assert_eq!(graph.nodes.iter().map(|n| n.sym.as_str()).collect::<Vec<_>>(), vec!["main", "b", "a"]);
+}
+
+#[test]
+fn test_budgeted() {
+    let mut callees = HashMap::new();
+    callees.insert("main".to_string(), vec![("a".to_string(), "".to_string())]);
+    callees.insert("a".to_string(), vec![("b".to_string(), "".to_string())]);
+    let roots = [("main".to_string(), "".to_string())];
+    let boring = BoringSymbols::new(&[]).unwrap();
+    let relationship = Callees { callees: &callees };
+
+    let budgeted = Budgeted::new(&relationship, Some(1), None);
+    let graph = collect(&budgeted, &roots, &boring, 4, 16);
+    assert_eq!(graph.nodes.iter().map(|n| n.sym.as_str()).collect::<Vec<_>>(), vec!["main", "a"]);
+    assert!(budgeted.exhausted_note().unwrap().contains("1 lookups"));
+
+    let budgeted = Budgeted::new(&relationship, Some(16), Some(60000));
+    assert_eq!(collect(&budgeted, &roots, &boring, 4, 16).nodes.len(), 3);
+    assert_eq!(budgeted.exhausted_note(), None);
+}
+
+#[test]
+fn test_estimate() {
+    let mut callees = HashMap::new();
+    callees.insert("main".to_string(), vec![("a".to_string(), "".to_string()), ("b".to_string(), "".to_string())]);
+    callees.insert("a".to_string(), vec![("c".to_string(), "".to_string()), ("d".to_string(), "".to_string())]);
+    callees.insert("b".to_string(), vec![("e".to_string(), "".to_string()), ("f".to_string(), "".to_string())]);
+    let boring = BoringSymbols::new(&[]).unwrap();
+    let roots = [("main".to_string(), "".to_string())];
+
+    let totals = estimate(&Callees { callees: &callees }, &roots, &boring, 3, 16);
+    assert_eq!(totals, vec![1, 3, 7, 15]);
+    assert_eq!(fit_depth(&totals, 10), Some(2));
+    assert_eq!(estimate_warning(&totals, 20), None);
+    assert!(estimate_warning(&totals, 10).unwrap().contains("--max-depth 2"));
+}
+
+#[test]
+fn test_read_callers() {
+    let dir = ::TestDir::new("callers-test");
+    let index_path = dir.path.to_str().unwrap();
+    assert!(load_callers(index_path).is_none());
+
+    let mut file = fs::File::create(dir.path.join("callers")).unwrap();
+    file.write_all(b"_ZN3Foo3BarEv\n[[\"_ZN3Foo3BazEv\",\"Foo::Baz\"],[\"_Z4mainv\",\"main\"]]\n").unwrap();
+    let callers = load_callers(index_path).unwrap();
+    assert_eq!(read_callers(&callers, "_ZN3Foo3BarEv"),
+               vec![("_ZN3Foo3BazEv".to_string(), "Foo::Baz".to_string()), ("_Z4mainv".to_string(), "main".to_string())]);
+    assert!(read_callers(&callers, "_Z4mainv").is_empty());
+}
@@ -16,6 +16,19 @@ pub struct TreeConfigPaths {
     pub git_path: Option<String>,
     pub git_blame_path: Option<String>,
     pub objdir_path: String,
+    // Path prefixes holding tests. When absent, tools fall back to
+    // guessing from directory names.
+    pub test_dirs: Option<Vec<String>>,
+    // Defaults for the call graph tools, for when --max-depth or
+    // --node-limit isn't given.
+    pub graph_max_depth: Option<u32>,
+    pub graph_node_limit: Option<usize>,
+    // Regexps for symbols that the call graph tools and the search
+    // server should treat as noise. See callgraph::BoringSymbols.
+    pub boring_symbols: Option<Vec<String>>,
+    // Named sets of well-known entry points (main, module constructors,
+    // message handlers, ...), given as symbols or pretty names.
+    pub entry_points: Option<BTreeMap<String, Vec<String>>>,
 }
 
 pub struct GitData {
@@ -24,6 +37,7 @@ pub struct GitData {
 
     pub blame_map: HashMap<Oid, Oid>, // Maps repo OID to blame_repo OID.
     pub hg_map: HashMap<Oid, String>, // Maps repo OID to Hg rev.
+    pub hg_orig_map: HashMap<Oid, String>, // Maps repo OID to the Hg rev it was originally authored as.
 }
 
 pub struct TreeConfig {
@@ -50,12 +64,14 @@ pub fn get_git_path(tree_config: &TreeConfig) -> Result<&str, &'static str> {
     }
 }
 
-fn index_blame(_repo: &Repository, blame_repo: &Repository) -> (HashMap<Oid, Oid>, HashMap<Oid, String>) {
+fn index_blame(_repo: &Repository, blame_repo: &Repository)
+               -> (HashMap<Oid, Oid>, HashMap<Oid, String>, HashMap<Oid, String>) {
     let mut walk = blame_repo.revwalk().unwrap();
     walk.push_head().unwrap();
 
     let mut blame_map = HashMap::new();
     let mut hg_map = HashMap::new();
+    let mut hg_orig_map = HashMap::new();
     for r in walk {
         let oid = r.unwrap();
         let commit = blame_repo.find_commit(oid).unwrap();
@@ -70,9 +86,16 @@ fn index_blame(_repo: &Repository, blame_repo: &Repository) -> (HashMap<Oid, Oid
             let hg_id = pieces[3].to_owned();
             hg_map.insert(orig_oid, hg_id);
         }
+
+        // Set by transform-repo.py when obsolescence markers say the
+        // landed changeset was rewritten from a different one.
+        if pieces.len() > 5 && pieces[4] == "orig-hg" {
+            let orig_hg_id = pieces[5].to_owned();
+            hg_orig_map.insert(orig_oid, orig_hg_id);
+        }
     }
 
-    (blame_map, hg_map)
+    (blame_map, hg_map, hg_orig_map)
 }
 
 pub fn load(config_path: &str, need_indexes: bool) -> Config {
@@ -99,10 +122,10 @@ pub fn load(config_path: &str, need_indexes: bool) -> Config {
                 let repo = Repository::open(&git_path).unwrap();
                 let blame_repo = Repository::open(&git_blame_path).unwrap();
 
-                let (blame_map, hg_map) = if need_indexes {
+                let (blame_map, hg_map, hg_orig_map) = if need_indexes {
                     index_blame(&repo, &blame_repo)
                 } else {
-                    (HashMap::new(), HashMap::new())
+                    (HashMap::new(), HashMap::new(), HashMap::new())
                 };
 
                 Some(GitData {
@@ -110,6 +133,7 @@ pub fn load(config_path: &str, need_indexes: bool) -> Config {
                     blame_repo: blame_repo,
                     blame_map: blame_map,
                     hg_map: hg_map,
+                    hg_orig_map: hg_orig_map,
                 })
             },
             _ => None,
@@ -0,0 +1,253 @@
+use std::cmp;
+
+use regex::Regex;
+
+use tokenize::{Token, TokenKind};
+
+// Returns the indices of the tokens opening and closing the first
+// brace-delimited block at or after byte offset |start|. Returns None if
+// a semicolon comes first, since then |start| isn't the beginning of a
+// function definition.
+pub fn body_range(input: &str, tokens: &[Token], start: usize) -> Option<(usize, usize)> {
+    let first = match tokens.iter().position(|t| t.start >= start) {
+        Some(first) => first,
+        None => return None,
+    };
+
+    let mut depth = 0;
+    let mut open = first;
+    for i in first .. tokens.len() {
+        let token = &tokens[i];
+        if token.kind != TokenKind::Punctuation {
+            continue;
+        }
+        match &input[token.start .. token.end] {
+            ";" if depth == 0 => return None,
+            "{" => {
+                if depth == 0 {
+                    open = i;
+                }
+                depth += 1;
+            },
+            "}" => {
+                if depth == 0 {
+                    return None;
+                }
+                depth -= 1;
+                if depth == 0 {
+                    return Some((open, i));
+                }
+            },
+            _ => {},
+        }
+    }
+    None
+}
+
+// What surrounds each line of a file: the innermost function whose body
+// contains it, the column ranges of its comments, and the first and last
+// line numbers of the statement it is part of.
+pub struct LineContext {
+    pub function: String,
+    pub comments: Vec<(usize, usize)>,
+    pub statement: (usize, usize),
+}
+
+// Computes a LineContext for every line of |input|. |defs| gives the
+// line number, starting column, and pretty name of each definition in
+// the file.
+pub fn line_contexts(input: &str, tokens: &[Token], defs: &[(u32, u32, String)]) -> Vec<LineContext> {
+    let mut line_starts = vec![0];
+    for (i, c) in input.bytes().enumerate() {
+        if c == '\n' as u8 {
+            line_starts.push(i + 1);
+        }
+    }
+    let line_of = |offset: usize| match line_starts.binary_search(&offset) {
+        Ok(line) => line,
+        Err(line) => line - 1,
+    };
+
+    let mut result = line_starts.iter().enumerate()
+        .map(|(line, _)| LineContext { function: "".to_string(), comments: vec![], statement: (line + 1, line + 1) })
+        .collect::<Vec<_>>();
+
+    // Assign larger functions first so that nested ones (like methods
+    // defined inside a class body) win.
+    let mut bodies = defs.iter().filter_map(|&(lineno, col, ref pretty)| {
+        let start = match line_starts.get(lineno as usize - 1) {
+            Some(&line_start) => line_start + col as usize,
+            None => return None,
+        };
+        body_range(input, tokens, start).map(|(open, close)| {
+            (line_of(tokens[open].start), line_of(tokens[close].start), pretty)
+        })
+    }).collect::<Vec<_>>();
+    bodies.sort_by(|a, b| (b.1 - b.0).cmp(&(a.1 - a.0)));
+    for (first, last, pretty) in bodies {
+        for line in first .. last + 1 {
+            result[line].function = pretty.clone();
+        }
+    }
+
+    for token in tokens.iter().filter(|t| t.kind == TokenKind::Comment) {
+        let line = line_of(token.start);
+        let line_start = line_starts[line];
+        result[line].comments.push((token.start - line_start, token.end - line_start));
+    }
+
+    // A statement runs from its first token up to a ';' outside of
+    // parentheses, or up to a brace. Comments and string literals are
+    // single tokens, so punctuation inside them doesn't count. Each
+    // brace saves the parenthesis depth outside it, so statements in a
+    // lambda body passed as an argument still end at their ';'.
+    // Preprocessor directives are statements of their own line.
+    let mut first = None;
+    let mut parens = 0;
+    let mut saved_parens = vec![];
+    let mut directive_line = None;
+    for token in tokens {
+        let line = line_of(token.start);
+        let text = &input[token.start .. token.end];
+        match token.kind {
+            TokenKind::Newline | TokenKind::Comment => continue,
+            _ if directive_line == Some(line) => continue,
+            TokenKind::Identifier(_) if text.starts_with('#') => {
+                directive_line = Some(line);
+                continue;
+            },
+            _ => {},
+        }
+        let start = *first.get_or_insert(line);
+        let ends = match (&token.kind, text) {
+            (&TokenKind::Punctuation, "(") => { parens += 1; false },
+            (&TokenKind::Punctuation, ")") => { parens = cmp::max(parens, 1) - 1; false },
+            (&TokenKind::Punctuation, ";") => parens == 0,
+            (&TokenKind::Punctuation, "{") => {
+                saved_parens.push(parens);
+                parens = 0;
+                true
+            },
+            (&TokenKind::Punctuation, "}") => {
+                parens = saved_parens.pop().unwrap_or(0);
+                true
+            },
+            _ => false,
+        };
+        if ends {
+            for context in &mut result[start .. line + 1] {
+                context.statement = (start + 1, line + 1);
+            }
+            first = None;
+        }
+    }
+    if let Some(start) = first {
+        let last = result.len();
+        for context in &mut result[start ..] {
+            context.statement = (start + 1, last);
+        }
+    }
+
+    result
+}
+
+// Returns the (line number, bug number) pairs for the Bugzilla bugs
+// mentioned in comments, as "Bug 1234", "bug #1234", or a bug URL.
+pub fn bug_references(input: &str, tokens: &[Token]) -> Vec<(u32, u64)> {
+    lazy_static! {
+        static ref BUG_REGEX: Regex = {
+            Regex::new(r"(?i)(\bbug\s*#?\s*|show_bug\.cgi\?id=|bugzil\.la/)([1-9][0-9]{3,8})\b").unwrap()
+        };
+    }
+
+    let mut result = vec![];
+    let mut line_start = 1;
+    for token in tokens {
+        let text = &input[token.start .. token.end];
+        if token.kind == TokenKind::Newline {
+            line_start += 1;
+            continue;
+        }
+        if token.kind != TokenKind::Comment {
+            continue;
+        }
+        // Block comments may span several lines.
+        let mut lineno = line_start;
+        let mut counted = 0;
+        for caps in BUG_REGEX.captures_iter(text) {
+            let (pos, _) = caps.pos(2).unwrap();
+            lineno += text[counted .. pos].bytes().filter(|&c| c == '\n' as u8).count() as u32;
+            counted = pos;
+            let bug = caps.at(2).unwrap().parse::<u64>().unwrap();
+            if !result.contains(&(lineno, bug)) {
+                result.push((lineno, bug));
+            }
+        }
+        line_start += text.bytes().filter(|&c| c == '\n' as u8).count() as u32;
+    }
+    result
+}
+
+#[test]
+fn test_line_contexts() {
+    use languages;
+    use languages::FormatAs;
+    use tokenize;
+
+    let input = "int x; // top\nvoid f() {\n  g(); /* hi */\n}\n";
+    let spec = match languages::select_formatting("a.cpp") {
+        FormatAs::FormatCLike(spec) => spec,
+        _ => panic!("Expected C-like formatting"),
+    };
+    let tokens = tokenize::tokenize_c_like(input, spec);
+    let defs = vec![(1, 4, "x".to_string()), (2, 5, "f".to_string())];
+    let contexts = line_contexts(input, &tokens, &defs);
+
+    assert_eq!(contexts[0].function, "");
+    assert_eq!(contexts[0].comments, vec![(7, 13)]);
+    assert_eq!(contexts[1].function, "f");
+    assert_eq!(contexts[2].function, "f");
+    assert_eq!(contexts[2].comments, vec![(7, 15)]);
+    assert_eq!(contexts[3].function, "f");
+    assert_eq!(contexts[4].function, "");
+    assert_eq!(contexts[0].statement, (1, 1));
+    assert_eq!(contexts[2].statement, (3, 3));
+}
+
+#[test]
+fn test_statements() {
+    use languages;
+    use languages::FormatAs;
+    use tokenize;
+
+    let input = concat!("#define X(a) a;\n",
+                        "int y = f(a,  // not done;\n",
+                        "          *p,\n",
+                        "          \"{\");\n",
+                        "for (i = 0; i < n; i++) {\n",
+                        "  g([] { h(); },\n",
+                        "    2);\n",
+                        "}\n");
+    let spec = match languages::select_formatting("a.cpp") {
+        FormatAs::FormatCLike(spec) => spec,
+        _ => panic!("Expected C-like formatting"),
+    };
+    let tokens = tokenize::tokenize_c_like(input, spec);
+    let statements = line_contexts(input, &tokens, &[]).iter().map(|c| c.statement).collect::<Vec<_>>();
+    assert_eq!(statements, vec![(1, 1), (2, 4), (2, 4), (2, 4), (5, 5), (6, 7), (6, 7), (8, 8), (9, 9)]);
+}
+
+#[test]
+fn test_bug_references() {
+    use languages;
+    use languages::FormatAs;
+    use tokenize;
+
+    let input = "// Bug 123456: see bug #654321\nint bug1234;\n/* https://bugzilla.mozilla.org/show_bug.cgi?id=1111\n   bugzil.la/2222, Debug 99999 */\n";
+    let spec = match languages::select_formatting("a.cpp") {
+        FormatAs::FormatCLike(spec) => spec,
+        _ => panic!("Expected C-like formatting"),
+    };
+    let tokens = tokenize::tokenize_c_like(input, spec);
+    assert_eq!(bug_references(input, &tokens), vec![(1, 123456), (1, 654321), (3, 1111), (4, 2222)]);
+}
@@ -116,6 +116,15 @@ pub fn read_analysis<T>(filename: &str, filter: &Fn(&Object) -> Option<T>) -> Ve
     result2
 }
 
+// Returns the records whose range covers byte column |col| (0-based) of
+// line |lineno| (1-based). This is only useful for source records, since
+// target records don't have an end column.
+pub fn find_at<T>(analysis: &[WithLocation<Vec<T>>], lineno: u32, col: u32) -> Vec<&WithLocation<Vec<T>>> {
+    analysis.iter().filter(|datum| {
+        datum.loc.lineno == lineno && datum.loc.col_start <= col && col < datum.loc.col_end
+    }).collect()
+}
+
 pub fn read_target(obj : &Object) -> Option<AnalysisTarget> {
     if !obj.contains_key("target") {
         return None;
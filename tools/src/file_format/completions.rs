@@ -0,0 +1,322 @@
+extern crate memmap;
+
+use self::memmap::{Mmap, Protection};
+use std::str;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+
+use config;
+
+// Completions for search-as-you-type. At index time the crossref tool
+// writes a "completions" file with a line "key\tname\tkind\tuses" for
+// every qualified-name suffix and file path, sorted by key and then by
+// rank, and a "completion-prefixes" file with a line "prefix\toffsets"
+// giving the offsets of the best completions for each key prefix of up
+// to PREFIX_LEN characters. Short needles, which match the most names,
+// are answered from the prefix file without scanning. Longer ones only
+// match a few lines of the completions file.
+
+const PREFIX_LEN: usize = 4;
+const TOP_COUNT: usize = 10;
+
+// The most lines a long needle may scan before the best of them are
+// returned.
+const SCAN_LIMIT: usize = 4096;
+
+#[derive(Clone, Debug, PartialEq, RustcEncodable)]
+pub struct Completion {
+    pub name: String,
+    pub kind: String,
+    pub uses: usize,
+}
+
+// Infers what kind of identifier a symbol is from the prefixes the
+// indexers give their symbols.
+pub fn symbol_kind(sym: &str) -> &'static str {
+    if sym.starts_with("NS_") {
+        "namespace"
+    } else if sym.starts_with("T_") {
+        "class"
+    } else if sym.starts_with("F_") {
+        "field"
+    } else if sym.starts_with("E_") {
+        "enumerator"
+    } else if sym.starts_with("_Z") {
+        "function"
+    } else {
+        "member"
+    }
+}
+
+fn kind_order(kind: &str) -> usize {
+    ["class", "function", "namespace", "field", "enumerator", "member", "file"]
+        .iter().position(|k| *k == kind).unwrap_or(7)
+}
+
+// Ranks the most used names first. Ties go to types and functions over
+// other kinds, and then to shorter names.
+pub fn compare_rank(a: &Completion, b: &Completion) -> Ordering {
+    if a.uses != b.uses {
+        return b.uses.cmp(&a.uses);
+    }
+    let (ka, kb) = (kind_order(&a.kind), kind_order(&b.kind));
+    if ka != kb {
+        return ka.cmp(&kb);
+    }
+    (a.name.len(), &a.name).cmp(&(b.name.len(), &b.name))
+}
+
+// Sorts |list| (indexes into |entries|) by rank and keeps the best
+// TOP_COUNT distinct names.
+fn keep_best(entries: &[(String, Completion)], list: &mut Vec<usize>) {
+    list.sort_by(|&a, &b| compare_rank(&entries[a].1, &entries[b].1));
+    let mut seen = HashSet::new();
+    list.retain(|&i| seen.insert(entries[i].1.name.clone()));
+    list.truncate(TOP_COUNT);
+}
+
+pub struct CompletionsBuilder {
+    entries: Vec<(String, Completion)>,
+}
+
+impl CompletionsBuilder {
+    pub fn new() -> CompletionsBuilder {
+        CompletionsBuilder { entries: Vec::new() }
+    }
+
+    // Makes |name| a completion for needles that are a prefix of |key|,
+    // ignoring case.
+    pub fn add(&mut self, key: &str, name: &str, kind: &str, uses: usize) {
+        let completion = Completion { name: name.to_string(), kind: kind.to_string(), uses: uses };
+        self.entries.push((key.to_lowercase(), completion));
+    }
+
+    // Adds every path in the file |filename|, which has one per line
+    // (like repo-files), under both its full path and its file name.
+    pub fn add_paths(&mut self, filename: &str) {
+        let file = match File::open(filename) {
+            Ok(file) => file,
+            Err(_) => return,
+        };
+        for path in BufReader::new(file).lines() {
+            let path = path.unwrap();
+            if path.is_empty() {
+                continue;
+            }
+            let name = path.rsplit('/').next().unwrap().to_string();
+            self.add(&path, &path, "file", 0);
+            if name != path {
+                self.add(&name, &path, "file", 0);
+            }
+        }
+    }
+
+    pub fn write(mut self, index_path: &str) {
+        self.entries.sort_by(|a, b| match a.0.cmp(&b.0) {
+            Ordering::Equal => compare_rank(&a.1, &b.1),
+            ordering => ordering,
+        });
+        self.entries.dedup_by(|a, b| a.0 == b.0 && a.1.name == b.1.name);
+
+        let mut offsets = Vec::with_capacity(self.entries.len());
+        let mut offset = 0;
+        let mut completionsf = File::create(format!("{}/completions", index_path)).unwrap();
+        for &(ref key, ref completion) in &self.entries {
+            let line = format!("{}\t{}\t{}\t{}\n", key, completion.name, completion.kind, completion.uses);
+            let _ = completionsf.write_all(line.as_bytes());
+            offsets.push(offset);
+            offset += line.len();
+        }
+
+        let mut tops: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, &(ref key, _)) in self.entries.iter().enumerate() {
+            let mut prefix = String::new();
+            for c in key.chars().take(PREFIX_LEN) {
+                prefix.push(c);
+                let list = tops.entry(prefix.clone()).or_insert(Vec::new());
+                list.push(i);
+                if list.len() >= TOP_COUNT * 4 {
+                    keep_best(&self.entries, list);
+                }
+            }
+        }
+
+        let mut prefixes = tops.keys().cloned().collect::<Vec<_>>();
+        prefixes.sort();
+        let mut prefixf = File::create(format!("{}/completion-prefixes", index_path)).unwrap();
+        for prefix in prefixes {
+            let list = tops.get_mut(&prefix).unwrap();
+            keep_best(&self.entries, list);
+            let list = list.iter().map(|&i| offsets[i].to_string()).collect::<Vec<_>>();
+            let _ = prefixf.write_all(format!("{}\t{}\n", prefix, list.join(",")).as_bytes());
+        }
+    }
+}
+
+// Returns the bounds of the line containing |pos|. A newline belongs to
+// the line it ends.
+fn line_bounds(bytes: &[u8], pos: usize) -> (usize, usize) {
+    let mut start = pos;
+    while start > 0 && bytes[start - 1] != '\n' as u8 {
+        start -= 1;
+    }
+    let mut end = pos;
+    while end < bytes.len() && bytes[end] != '\n' as u8 {
+        end += 1;
+    }
+    (start, end)
+}
+
+// Returns the offset of the first line that isn't less than |needle|, or
+// the length of |bytes| if there is none.
+fn bisect(bytes: &[u8], needle: &[u8]) -> usize {
+    let mut first = 0;
+    let mut count = bytes.len();
+
+    while count > 0 {
+        let step = count / 2;
+        let pos = first + step;
+
+        let (start, end) = line_bounds(bytes, pos);
+        if &bytes[start .. end] < needle {
+            first = pos + 1;
+            count -= step + 1;
+        } else {
+            count = step;
+        }
+    }
+
+    first
+}
+
+fn parse_line(line: &[u8]) -> Option<Completion> {
+    let line = match str::from_utf8(line) {
+        Ok(line) => line,
+        Err(_) => return None,
+    };
+    let pieces = line.split('\t').collect::<Vec<_>>();
+    if pieces.len() != 4 {
+        return None;
+    }
+    Some(Completion {
+        name: pieces[1].to_string(),
+        kind: pieces[2].to_string(),
+        uses: pieces[3].parse().unwrap_or(0),
+    })
+}
+
+pub struct Completions {
+    completions: Mmap,
+    prefixes: Mmap,
+}
+
+impl Completions {
+    // Returns None for trees indexed without completions.
+    pub fn new(index_path: &str) -> Option<Completions> {
+        let completions = Mmap::open_path(format!("{}/completions", index_path), Protection::Read);
+        let prefixes = Mmap::open_path(format!("{}/completion-prefixes", index_path), Protection::Read);
+        match (completions, prefixes) {
+            (Ok(completions), Ok(prefixes)) => Some(Completions { completions: completions, prefixes: prefixes }),
+            _ => None,
+        }
+    }
+
+    pub fn load(config: &config::Config) -> HashMap<String, Completions> {
+        let mut result = HashMap::new();
+        for (tree_name, tree_config) in &config.trees {
+            println!("Loading completions {}", tree_name);
+            if let Some(completions) = Completions::new(&tree_config.paths.index_path) {
+                result.insert(tree_name.clone(), completions);
+            }
+        }
+        result
+    }
+
+    // Returns the best completions of |needle|, ignoring case.
+    pub fn lookup(&self, needle: &str, max_results: usize) -> Vec<Completion> {
+        let key = needle.to_lowercase();
+        if key.is_empty() {
+            return vec![];
+        }
+
+        let completions: &[u8] = unsafe { self.completions.as_slice() };
+
+        if key.chars().count() <= PREFIX_LEN {
+            let prefixes: &[u8] = unsafe { self.prefixes.as_slice() };
+            let needle = format!("{}\t", key);
+            let pos = bisect(prefixes, needle.as_bytes());
+            if pos >= prefixes.len() {
+                return vec![];
+            }
+            let (start, end) = line_bounds(prefixes, pos);
+            let line = &prefixes[start .. end];
+            if !line.starts_with(needle.as_bytes()) {
+                return vec![];
+            }
+            let offsets = str::from_utf8(&line[needle.len() ..]).unwrap_or("");
+            return offsets.split(',').filter_map(|offset| offset.parse::<usize>().ok())
+                .filter(|&offset| offset < completions.len())
+                .filter_map(|offset| {
+                    let (start, end) = line_bounds(completions, offset);
+                    parse_line(&completions[start .. end])
+                })
+                .take(max_results)
+                .collect();
+        }
+
+        let pos = bisect(completions, key.as_bytes());
+        let mut result = completions[pos ..].split(|&c| c == '\n' as u8)
+            .take_while(|line| line.starts_with(key.as_bytes()))
+            .take(SCAN_LIMIT)
+            .filter_map(parse_line)
+            .collect::<Vec<_>>();
+        result.sort_by(compare_rank);
+        let mut seen = HashSet::new();
+        result.retain(|c| seen.insert(c.name.clone()));
+        result.truncate(max_results);
+        result
+    }
+}
+
+#[test]
+fn test_symbol_kind() {
+    assert_eq!(symbol_kind("T_mozilla::dom::Element"), "class");
+    assert_eq!(symbol_kind("F_<T_nsINode>_3"), "field");
+    assert_eq!(symbol_kind("_ZN7nsINode8GetFirstEv"), "function");
+    assert_eq!(symbol_kind("#foo"), "member");
+}
+
+#[test]
+fn test_compare_rank() {
+    let c = |name: &str, kind: &str, uses: usize| Completion { name: name.to_string(), kind: kind.to_string(), uses: uses };
+    assert_eq!(compare_rank(&c("a::Foo", "function", 10), &c("Foo", "class", 2)), Ordering::Less);
+    assert_eq!(compare_rank(&c("Foo", "field", 2), &c("Foo", "class", 2)), Ordering::Greater);
+    assert_eq!(compare_rank(&c("Foo", "file", 0), &c("a/Foo.h", "file", 0)), Ordering::Less);
+}
+
+#[test]
+fn test_lookup() {
+    let dir = ::TestDir::new("completions-test");
+    let index_path = dir.path.to_str().unwrap();
+
+    let mut builder = CompletionsBuilder::new();
+    builder.add("nsINode::GetChild", "nsINode::GetChild", "function", 40);
+    builder.add("GetChild", "nsINode::GetChild", "function", 40);
+    builder.add("GetChildAt", "GetChildAt", "function", 90);
+    builder.add("nsINode", "nsINode", "class", 500);
+    builder.add("GetChildCount", "GetChildCount", "function", 3);
+    builder.write(index_path);
+
+    let completions = Completions::new(index_path).unwrap();
+    let names = |needle: &str| completions.lookup(needle, 10).into_iter().map(|c| c.name).collect::<Vec<_>>();
+    assert_eq!(names("getc"), vec!["GetChildAt", "nsINode::GetChild", "GetChildCount"]);
+    assert_eq!(names("GetChild"), vec!["GetChildAt", "nsINode::GetChild", "GetChildCount"]);
+    assert_eq!(names("nsinode::getchild"), vec!["nsINode::GetChild"]);
+    assert_eq!(names("n"), vec!["nsINode", "nsINode::GetChild"]);
+    assert!(names("x").is_empty());
+}
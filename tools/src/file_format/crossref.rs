@@ -0,0 +1,164 @@
+extern crate memmap;
+
+use self::memmap::{Mmap, Protection};
+use std::str;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use rustc_serialize::json::Json;
+
+use config;
+
+pub struct CrossrefLine {
+    pub lineno: u64,
+    pub line: String,
+    pub context: String,
+    pub contextsym: String,
+    // Byte offsets of the symbol within |line|, when recorded.
+    pub bounds: Option<(usize, usize)>,
+}
+
+pub struct CrossrefPath {
+    pub path: String,
+    pub lines: Vec<CrossrefLine>,
+}
+
+// All the target records for one symbol, keyed by kind ("Uses",
+// "Definitions", etc.).
+pub struct CrossrefEntry {
+    pub kinds: BTreeMap<String, Vec<CrossrefPath>>,
+}
+
+impl CrossrefEntry {
+    pub fn get(&self, kind: &str) -> &[CrossrefPath] {
+        match self.kinds.get(kind) {
+            Some(paths) => paths,
+            None => &[],
+        }
+    }
+
+    // Returns the path and line number of the first definition, if any.
+    pub fn first_def(&self) -> Option<(&str, u64)> {
+        for path in self.get("Definitions") {
+            if let Some(line) = path.lines.first() {
+                return Some((&path.path, line.lineno));
+            }
+        }
+        None
+    }
+}
+
+fn get_string(obj: &Json, key: &str) -> String {
+    match obj.find(key) {
+        Some(json) => json.as_string().unwrap_or("").to_string(),
+        None => "".to_string(),
+    }
+}
+
+fn get_bounds(obj: &Json) -> Option<(usize, usize)> {
+    let bounds = match obj.find("bounds").and_then(|b| b.as_array()) {
+        Some(bounds) => bounds,
+        None => return None,
+    };
+    match (bounds.get(0).and_then(|b| b.as_u64()), bounds.get(1).and_then(|b| b.as_u64())) {
+        (Some(start), Some(end)) => Some((start as usize, end as usize)),
+        _ => None,
+    }
+}
+
+fn read_entry(json: &Json) -> CrossrefEntry {
+    let mut kinds = BTreeMap::new();
+    for (kind, paths) in json.as_object().unwrap() {
+        let paths = paths.as_array().unwrap().iter().map(|path| {
+            let lines = path.find("lines").unwrap().as_array().unwrap().iter().map(|line| {
+                CrossrefLine {
+                    lineno: line.find("lno").unwrap().as_u64().unwrap(),
+                    line: get_string(line, "line"),
+                    context: get_string(line, "context"),
+                    contextsym: get_string(line, "contextsym"),
+                    bounds: get_bounds(line),
+                }
+            }).collect::<Vec<_>>();
+            CrossrefPath { path: get_string(path, "path"), lines: lines }
+        }).collect::<Vec<_>>();
+        kinds.insert(kind.clone(), paths);
+    }
+    CrossrefEntry { kinds: kinds }
+}
+
+// The crossref file alternates between a line holding a symbol name and
+// a line holding the JSON for that symbol. We keep the file mapped and
+// remember where each symbol's JSON lives so that only the entries
+// that are actually needed get parsed.
+pub struct CrossrefMap {
+    mmap: Mmap,
+    offsets: HashMap<String, (usize, usize)>,
+}
+
+impl CrossrefMap {
+    pub fn new(filename: &str) -> CrossrefMap {
+        let file_mmap = Mmap::open_path(filename, Protection::Read).unwrap();
+        let mut offsets = HashMap::new();
+        {
+            let bytes: &[u8] = unsafe { file_mmap.as_slice() };
+            let mut pos = 0;
+            let mut key = None;
+            while pos < bytes.len() {
+                let mut end = pos;
+                while end < bytes.len() && bytes[end] != '\n' as u8 {
+                    end += 1;
+                }
+
+                match key {
+                    None => {
+                        key = Some(str::from_utf8(&bytes[pos .. end]).unwrap().to_string());
+                    },
+                    Some(k) => {
+                        offsets.insert(k, (pos, end));
+                        key = None;
+                    },
+                }
+
+                pos = end + 1;
+            }
+        }
+        CrossrefMap { mmap: file_mmap, offsets: offsets }
+    }
+
+    pub fn load(config: &config::Config) -> HashMap<String, CrossrefMap> {
+        let mut result = HashMap::new();
+        for (tree_name, tree_config) in &config.trees {
+            println!("Loading crossref {}", tree_name);
+            let filename = format!("{}/crossref", tree_config.paths.index_path);
+            let map = CrossrefMap::new(&filename);
+            result.insert(tree_name.clone(), map);
+        }
+        result
+    }
+
+    // Returns every symbol in the file, sorted so that tools walking the
+    // whole crossref produce the same output from run to run.
+    pub fn symbols(&self) -> Vec<&str> {
+        let mut symbols = self.offsets.keys().map(|k| k.as_str()).collect::<Vec<_>>();
+        symbols.sort();
+        symbols
+    }
+
+    pub fn contains(&self, sym: &str) -> bool {
+        self.offsets.contains_key(sym)
+    }
+
+    pub fn lookup_json(&self, sym: &str) -> Option<Json> {
+        let &(start, end) = match self.offsets.get(sym) {
+            Some(offsets) => offsets,
+            None => return None,
+        };
+        let bytes: &[u8] = unsafe { self.mmap.as_slice() };
+        let data = str::from_utf8(&bytes[start .. end]).unwrap();
+        Json::from_str(data).ok()
+    }
+
+    pub fn lookup(&self, sym: &str) -> Option<CrossrefEntry> {
+        self.lookup_json(sym).map(|json| read_entry(&json))
+    }
+}
@@ -0,0 +1,29 @@
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::collections::HashMap;
+
+use rustc_serialize::json::Json;
+
+// Reads the doc-summaries file written by the crossref tool, which has a
+// JSON line [symbol, summary] for each symbol with a doc comment. Trees
+// indexed before the file existed simply have no summaries.
+pub fn read_doc_summaries(filename: &str) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+    let file = match File::open(filename) {
+        Ok(file) => file,
+        Err(_) => return result,
+    };
+    for line in BufReader::new(file).lines() {
+        let line = line.unwrap();
+        let json = match Json::from_str(&line) {
+            Ok(json) => json,
+            Err(_) => continue,
+        };
+        let get = |i: usize| json.as_array().and_then(|a| a.get(i)).and_then(|v| v.as_string()).map(|s| s.to_string());
+        if let (Some(sym), Some(summary)) = (get(0), get(1)) {
+            result.insert(sym, summary);
+        }
+    }
+    result
+}
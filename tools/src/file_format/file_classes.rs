@@ -0,0 +1,80 @@
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::collections::HashMap;
+
+use rustc_serialize::json::Json;
+
+// The guess the search server made before file-classes existed, which is
+// still used for trees indexed without it.
+pub fn looks_like_test(path: &str) -> bool {
+    if path.contains("/unit/") {
+        return true;
+    }
+    if !path.contains("test") {
+        return false;
+    }
+    ["/test/", "/tests/", "/mochitest/", "testing/", "/jsapi-tests/", "/reftests/", "/reftest/",
+     "/crashtests/", "/crashtest/"].iter().any(|dir| path.contains(dir))
+}
+
+// The class of each file ("test", "build", "generated" or "prod"), as
+// recorded at index time by scripts/classify-files.py. Only files that
+// aren't "prod" are listed.
+pub struct FileClasses {
+    classes: HashMap<String, String>,
+    present: bool,
+}
+
+impl FileClasses {
+    pub fn new(index_path: &str) -> FileClasses {
+        let mut classes = HashMap::new();
+        let file = match File::open(format!("{}/file-classes", index_path)) {
+            Ok(file) => file,
+            Err(_) => return FileClasses { classes: classes, present: false },
+        };
+        for line in BufReader::new(file).lines() {
+            let line = line.unwrap();
+            let json = match Json::from_str(&line) {
+                Ok(json) => json,
+                Err(_) => continue,
+            };
+            let get = |i: usize| json.as_array().and_then(|a| a.get(i)).and_then(|v| v.as_string()).map(|s| s.to_string());
+            if let (Some(path), Some(class)) = (get(0), get(1)) {
+                classes.insert(path, class);
+            }
+        }
+        FileClasses { classes: classes, present: true }
+    }
+
+    // Whether the index has a file-classes file at all.
+    pub fn present(&self) -> bool {
+        self.present
+    }
+
+    pub fn class_of(&self, path: &str) -> &str {
+        if let Some(class) = self.classes.get(path) {
+            return class;
+        }
+        if path.starts_with("__GENERATED__/") {
+            "generated"
+        } else if !self.present && looks_like_test(path) {
+            "test"
+        } else {
+            "prod"
+        }
+    }
+}
+
+#[test]
+fn test_class_of() {
+    let mut classes = FileClasses { classes: HashMap::new(), present: false };
+    assert_eq!(classes.class_of("dom/base/test/test_foo.html"), "test");
+    assert_eq!(classes.class_of("__GENERATED__/dist/include/foo.h"), "generated");
+    assert!(!looks_like_test("dom/base/nsContentTest.cpp"));
+
+    classes.present = true;
+    classes.classes.insert("dom/moz.build".to_string(), "build".to_string());
+    assert_eq!(classes.class_of("dom/moz.build"), "build");
+    assert_eq!(classes.class_of("dom/base/test/test_foo.html"), "prod");
+}
@@ -2,7 +2,7 @@ extern crate memmap;
 
 use self::memmap::{Mmap, Protection};
 use std::str;
-use std::io::BufRead;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::process::Command;
 
@@ -10,12 +10,16 @@ use rustc_serialize::json;
 
 use config;
 
-fn uppercase(s: &[u8]) -> Vec<u8> {
-    let mut result = vec![];
-    for i in 0 .. s.len() {
-        result.push(if s[i] >= 'a' as u8 && s[i] <= 'z' as u8 { s[i] - ('a' as u8) + ('A' as u8) } else { s[i] });
-    }
-    result
+fn uppercase(c: u8) -> u8 {
+    if c >= 'a' as u8 && c <= 'z' as u8 { c - ('a' as u8) + ('A' as u8) } else { c }
+}
+
+// Compares |line| against |needle| (followed by |suffix|, if any)
+// ignoring case, without allocating.
+fn compare_folded(line: &[u8], needle: &[u8], suffix: Option<u8>) -> Ordering {
+    let line = line.iter().map(|&c| uppercase(c));
+    let needle = needle.iter().map(|&c| uppercase(c)).chain(suffix);
+    line.cmp(needle)
 }
 
 pub struct IdentMap {
@@ -42,7 +46,7 @@ fn demangle_name(name: &str) -> String {
 }
 
 impl IdentMap {
-    fn new(filename: &str) -> IdentMap {
+    pub fn new(filename: &str) -> IdentMap {
         let file_mmap = Mmap::open_path(filename, Protection::Read).unwrap();
         IdentMap { mmap: file_mmap }
     }
@@ -81,10 +85,7 @@ impl IdentMap {
     }
 
     fn bisect(&self, needle: &[u8], upper_bound: bool) -> usize {
-        let mut needle = uppercase(needle);
-        if upper_bound {
-            needle.push('~' as u8);
-        }
+        let suffix = if upper_bound { Some('~' as u8) } else { None };
 
         let mut first = 0;
         let mut count = self.mmap.len();
@@ -94,8 +95,12 @@ impl IdentMap {
             let pos = first + step;
 
             let line = self.get_line(pos);
-            let line_upper = uppercase(line);
-            if line_upper < needle || (upper_bound && line_upper == needle) {
+            let before = match compare_folded(line, needle, suffix) {
+                Ordering::Less => true,
+                Ordering::Equal => upper_bound,
+                Ordering::Greater => false,
+            };
+            if before {
                 first = pos + 1;
                 count -= step + 1;
             } else {
@@ -106,9 +111,9 @@ impl IdentMap {
         first
     }
 
-    // NEED A WAY TO LIMIT NUMBER OF RESULTS RETURNED TO 6
-    // Also need to apply c++filt to the ident as a better human-readable name
-    pub fn lookup(&self, needle: &str, complete: bool, fold_case: bool, max_results: usize) -> Vec<IdentResult> {
+    // Returns (identifier, symbol) pairs borrowed from the mapped file,
+    // without demangling. This is cheap enough to run on every keystroke.
+    pub fn lookup_raw(&self, needle: &str, complete: bool, fold_case: bool, max_results: usize) -> Vec<(&str, &str)> {
         let start = self.bisect(needle.as_bytes(), false);
         let end = self.bisect(needle.as_bytes(), true);
 
@@ -116,11 +121,17 @@ impl IdentMap {
         let bytes: &[u8] = unsafe { self.mmap.as_slice() };
         let slice = &bytes[start .. end];
 
-        for line in slice.lines() {
-            let line = line.unwrap();
+        for line in slice.split(|&c| c == '\n' as u8) {
+            let line = match str::from_utf8(line) {
+                Ok(line) => line,
+                Err(_) => continue,
+            };
             let mut pieces = line.split(' ');
-            let mut id = pieces.next().unwrap().to_string();
-            let symbol = pieces.next().unwrap();
+            let id = pieces.next().unwrap();
+            let symbol = match pieces.next() {
+                Some(symbol) => symbol,
+                None => continue,
+            };
 
             {
                 let suffix = &id[needle.len() ..];
@@ -132,12 +143,7 @@ impl IdentMap {
                 continue;
             }
 
-            let demangled = demangle_name(&symbol);
-            if demangled != symbol {
-                id = demangled;
-            }
-
-            result.push(IdentResult { id: id, symbol: symbol.to_string() });
+            result.push((id, symbol));
             if result.len() == max_results {
                 break;
             }
@@ -146,8 +152,24 @@ impl IdentMap {
         result
     }
 
+    pub fn lookup(&self, needle: &str, complete: bool, fold_case: bool, max_results: usize) -> Vec<IdentResult> {
+        self.lookup_raw(needle, complete, fold_case, max_results).into_iter().map(|(id, symbol)| {
+            let demangled = demangle_name(symbol);
+            let id = if demangled != symbol { demangled } else { id.to_string() };
+            IdentResult { id: id, symbol: symbol.to_string() }
+        }).collect()
+    }
+
     pub fn lookup_json(&self, needle: &str, complete: bool, fold_case: bool, max_results: usize) -> String {
         let results = self.lookup(needle, complete, fold_case, max_results);
         json::encode(&results).unwrap()
     }
 }
+
+#[test]
+fn test_compare_folded() {
+    assert_eq!(compare_folded(b"foo bar", b"FOO", None), Ordering::Greater);
+    assert_eq!(compare_folded(b"Foo", b"fOO", None), Ordering::Equal);
+    assert_eq!(compare_folded(b"foo bar", b"foo", Some('~' as u8)), Ordering::Less);
+    assert_eq!(compare_folded(b"fo", b"foo", None), Ordering::Less);
+}
@@ -1,2 +1,7 @@
 pub mod analysis;
 pub mod identifiers;
+pub mod crossref;
+pub mod doc_summaries;
+pub mod file_classes;
+pub mod renames;
+pub mod completions;
@@ -0,0 +1,30 @@
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::collections::HashMap;
+
+use rustc_serialize::json::Json;
+
+// Reads the symbol-renames file written by
+// scripts/match-renamed-symbols.py, which has a JSON line
+// [old, new, reason] for each symbol that was renamed or merged since an
+// earlier index. Returns old -> (new, reason).
+pub fn read_renames(index_path: &str) -> HashMap<String, (String, String)> {
+    let mut result = HashMap::new();
+    let file = match File::open(format!("{}/symbol-renames", index_path)) {
+        Ok(file) => file,
+        Err(_) => return result,
+    };
+    for line in BufReader::new(file).lines() {
+        let line = line.unwrap();
+        let json = match Json::from_str(&line) {
+            Ok(json) => json,
+            Err(_) => continue,
+        };
+        let get = |i: usize| json.as_array().and_then(|a| a.get(i)).and_then(|v| v.as_string()).map(|s| s.to_string());
+        if let (Some(old), Some(new), Some(reason)) = (get(0), get(1), get(2)) {
+            result.insert(old, (new, reason));
+        }
+    }
+    result
+}
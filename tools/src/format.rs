@@ -741,6 +741,14 @@ fn generate_commit_info(tree_name: &str,
 
         None => vec![]
     };
+    let orig_hg = match git.hg_orig_map.get(&commit.id()) {
+        Some(hg_id) => {
+            let hg_link = format!("<a href=\"https://hg.mozilla.org/mozilla-central/rev/{}\">{}</a>", hg_id, hg_id);
+            vec![F::T(format!("<tr><td>hg (original)</td><td>{}</td></tr>", hg_link))]
+        },
+
+        None => vec![]
+    };
 
     let git = format!("<a href=\"https://github.com/mozilla/gecko-dev/commit/{}\">{}</a>",
                       commit.id(), commit.id());
@@ -759,6 +767,7 @@ fn generate_commit_info(tree_name: &str,
             F::T(format!("<tr><td>commit</td><td>{}</td></tr>", format_rev(tree_name, commit.id()))),
             F::Seq(parents),
             F::Seq(hg),
+            F::Seq(orig_hg),
             F::T(format!("<tr><td>git</td><td>{}</td></tr>", git)),
             F::T(format!("<tr><td>author</td><td>{}</td></tr>", format_sig(commit.author()))),
             F::T(format!("<tr><td>committer</td><td>{}</td></tr>", format_sig(commit.committer()))),
@@ -4,6 +4,7 @@ extern crate rustc_serialize;
 extern crate git2;
 extern crate regex;
 extern crate chrono;
+extern crate getopts;
 
 pub mod file_format;
 
@@ -13,6 +14,9 @@ pub mod output;
 pub mod languages;
 pub mod format;
 pub mod tokenize;
+pub mod callgraph;
+pub mod contexts;
+pub mod sarif;
 
 pub fn find_source_file(path: &str, files_root: &str, objdir: &str) -> String {
     if path.starts_with("__GENERATED__") {
@@ -20,3 +24,28 @@ pub fn find_source_file(path: &str, files_root: &str, objdir: &str) -> String {
     }
     format!("{}/{}", files_root, path)
 }
+
+// A scratch directory for a test, named after the test and the process
+// so that concurrent or aborted runs don't collide. It is removed when
+// dropped, even if the test fails.
+#[cfg(test)]
+pub struct TestDir {
+    pub path: std::path::PathBuf,
+}
+
+#[cfg(test)]
+impl TestDir {
+    pub fn new(name: &str) -> TestDir {
+        let path = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TestDir { path: path }
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -112,8 +112,9 @@ pub fn generate_header(opt: &Options, writer: &mut Write) -> Result<(), &'static
             F::S(r#"<div id="query-section">"#),
             F::Indent(vec![
                 F::S(r#"<label for="query" class="query_label visually-hidden">Find</label>"#),
-                F::T(format!(r#"<input type="text" name="q" value="" maxlength="2048" id="query" accesskey="s" title="Search" placeholder="Search {}" autocomplete="off" />"#,
+                F::T(format!(r#"<input type="text" name="q" value="" maxlength="2048" id="query" accesskey="s" title="Search" placeholder="Search {}" autocomplete="off" list="query-completions" />"#,
                              opt.tree_name)),
+                F::S(r#"<datalist id="query-completions"></datalist>"#),
                 F::S(r#"<div class="zero-size-container">"#),
                 F::Indent(vec![
                     F::S(r#"<div class="bubble" id="query-bubble">"#),
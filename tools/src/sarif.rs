@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+
+use rustc_serialize::json::{Json, ToJson};
+
+// A single result for a SARIF log. |path| is relative to the root of
+// the tree, and |line| is 1-based.
+pub struct Finding {
+    pub rule: String,
+    pub message: String,
+    pub path: Option<String>,
+    pub line: Option<u64>,
+}
+
+fn object(pairs: Vec<(&str, Json)>) -> Json {
+    let mut obj = BTreeMap::new();
+    for (key, value) in pairs {
+        obj.insert(key.to_string(), value);
+    }
+    Json::Object(obj)
+}
+
+// Builds a SARIF 2.1.0 log with one run of |tool| reporting |findings|.
+// Locations are given relative to the SRCROOT base, which the consumer
+// maps to its own checkout.
+pub fn to_sarif(tool: &str, level: &str, findings: &[Finding]) -> Json {
+    let mut rules = findings.iter().map(|f| f.rule.clone()).collect::<Vec<_>>();
+    rules.sort();
+    rules.dedup();
+    let rules = rules.into_iter().map(|r| object(vec![("id", r.to_json())])).collect::<Vec<_>>();
+
+    let results = findings.iter().map(|f| {
+        let mut result = vec![("ruleId", f.rule.to_json()),
+                              ("level", level.to_json()),
+                              ("message", object(vec![("text", f.message.to_json())]))];
+        if let Some(ref path) = f.path {
+            let artifact = object(vec![("uri", path.to_json()), ("uriBaseId", "SRCROOT".to_json())]);
+            let mut physical = vec![("artifactLocation", artifact)];
+            if let Some(line) = f.line {
+                physical.push(("region", object(vec![("startLine", line.to_json())])));
+            }
+            result.push(("locations", Json::Array(vec![object(vec![("physicalLocation", object(physical))])])));
+        }
+        object(result)
+    }).collect::<Vec<_>>();
+
+    let driver = object(vec![("name", tool.to_json()), ("rules", Json::Array(rules))]);
+    let run = object(vec![("tool", object(vec![("driver", driver)])), ("results", Json::Array(results))]);
+    object(vec![("version", "2.1.0".to_json()),
+                ("$schema", "https://json.schemastore.org/sarif-2.1.0.json".to_json()),
+                ("runs", Json::Array(vec![run]))])
+}
+
+#[test]
+fn test_to_sarif() {
+    let findings = vec![Finding { rule: "jumps".to_string(), message: "no definition".to_string(),
+                                  path: Some("a.cpp".to_string()), line: Some(3) },
+                        Finding { rule: "identifiers".to_string(), message: "missing symbol".to_string(),
+                                  path: None, line: None }];
+    let log = to_sarif("check-crossref", "error", &findings);
+    let run = &log.find("runs").unwrap().as_array().unwrap()[0];
+    let rules = run.find_path(&["tool", "driver", "rules"]).unwrap().as_array().unwrap();
+    assert_eq!(rules.len(), 2);
+    let results = run.find("results").unwrap().as_array().unwrap();
+    let region = results[0].find("locations").unwrap().as_array().unwrap()[0].find_path(&["physicalLocation", "region"]);
+    assert_eq!(region.and_then(|r| r.find("startLine")).and_then(|l| l.as_u64()), Some(3));
+    assert!(results[1].find("locations").is_none());
+}